@@ -17,4 +17,65 @@ mod common;
         ];
 
         common::test_cases(&inputs, &cases);
-    }
\ No newline at end of file
+    }
+
+#[test]
+fn byte_strings() {
+    use regex_deriv::{DFA, NaiveLexTable, Scan, Operator};
+
+    let header = re::literal_bytes(&[0x00, 0xff, 0x00]);
+    let payload = re::any_bytes(&[0x7f, 0xff]).plus();
+
+    let frame = DFA::from(&header.then(&payload));
+    let end = [0x00, 0xff, 0x00, 0xff].iter().fold(1, |id, &byte| frame.step(id, byte));
+    assert_eq!(frame.class(end), Some(0));
+
+    let table = NaiveLexTable::new(&DFA::from(&[header.clone(), payload.clone()]).minimize());
+    let input = [0x00, 0xff, 0x00, 0xff, 0x7f, 0xff, 0x00, 0xff, 0x00];
+    let tokens: Vec<_> = Scan::new(&table, &input).collect::<Result<_, _>>().unwrap();
+    let tokens: Vec<_> = tokens.into_iter().map(|token| (token.class, token.span)).collect();
+    assert_eq!(tokens, [(0, 0..3), (1, 3..6), (0, 6..9)]);
+
+    assert!(matches!(re::any("aeiou").operator(), Operator::Set(_)));
+    assert!(matches!(re::any_bytes(b"aeiou").operator(), Operator::Set(_)));
+    assert!(re::any("aあ").is_fullmatch("あ"));
+    assert!(!re::any("aあ").is_fullmatch("aあ"));
+}
+
+#[test]
+fn from_str() {
+    use regex_deriv::RegEx;
+
+    let regex: RegEx = "[a-z]+(_[a-z0-9]+)*".parse().unwrap();
+    assert!(regex.is_fullmatch("snake_case_2"));
+    assert!(!regex.is_fullmatch("snake__case"));
+
+    let error = "(ab".parse::<RegEx>().unwrap_err();
+    assert_eq!(error.kind(), &re::ParseErrorKind::UnexpectedEnd);
+    assert_eq!(error.pos(), 3);
+
+    let error = "a)b".parse::<RegEx>().unwrap_err();
+    assert_eq!(error.kind(), &re::ParseErrorKind::UnexpectedToken(")".to_string()));
+    assert_eq!(error.pos(), 1);
+
+    let error = "a[]".parse::<RegEx>().unwrap_err();
+    assert_eq!(error.kind(), &re::ParseErrorKind::EmptyClass);
+    assert_eq!(error.pos(), 1);
+    assert_eq!(error.to_string(), "empty character class at 1");
+    assert_eq!(re::parse("[^]").unwrap_err().kind(), &re::ParseErrorKind::EmptyClass);
+
+    let regex: RegEx = "[^a-cあ]".parse().unwrap();
+    assert!(regex.is_fullmatch("d") && regex.is_fullmatch("い") && regex.is_fullmatch("^"));
+    assert!(!regex.is_fullmatch("a") && !regex.is_fullmatch("あ") && !regex.is_fullmatch("") && !regex.is_fullmatch("dd"));
+    assert!(!regex.is_fullmatch_bytes(&[0xff]));
+}
+
+#[test]
+fn as_literal() {
+    assert_eq!(re::literal("abc").as_literal(), Some(b"abc".to_vec()));
+    assert_eq!(re::literal("").as_literal(), Some(Vec::new()));
+    assert_eq!(re::any("a").as_literal(), Some(b"a".to_vec()));
+    assert_eq!(re::any("ab").as_literal(), None);
+    assert_eq!(re::literal("a").or(&re::literal("b")).as_literal(), None);
+    assert!(re::literal("あ").is_literal());
+}
@@ -0,0 +1,84 @@
+use std::hint::black_box;
+use std::time::Instant;
+use rand_core::{RngCore, SeedableRng};
+use rand_pcg::Pcg32;
+use regex_deriv::ByteSet;
+
+const ITERATIONS: usize = 1_000_000;
+
+/// The previous layout of `ByteSet`, a bitmap of 32 bytes, as a baseline.
+#[derive(Clone)]
+struct Bytewise {
+    bitmap: [u8; 32],
+}
+
+impl Bytewise {
+    fn new(set: &ByteSet) -> Self {
+        let mut bitmap = [0; 32];
+        for byte in set.bytes() {
+            bitmap[usize::from(byte / 8)] |= 1 << (byte % 8);
+        }
+        Self { bitmap }
+    }
+
+    fn union(&self, other: &Self) -> Self {
+        let mut bitmap = [0; 32];
+        for ((word, a), b) in bitmap.iter_mut().zip(&self.bitmap).zip(&other.bitmap) {
+            *word = a | b;
+        }
+        Self { bitmap }
+    }
+
+    fn intersection(&self, other: &Self) -> Self {
+        let mut bitmap = [0; 32];
+        for ((word, a), b) in bitmap.iter_mut().zip(&self.bitmap).zip(&other.bitmap) {
+            *word = a & b;
+        }
+        Self { bitmap }
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn smallest(&self) -> Option<u8> {
+        // non-truncating as i < 32 and the trailing zeros < 8
+        self.bitmap.iter().position(|&word| word != 0).map(|i| 8 * i as u8 + self.bitmap[i].trailing_zeros() as u8)
+    }
+}
+
+fn time<S>(name: &str, sets: &[S], op: impl Fn(&S, &S) -> S) {
+    let now = Instant::now();
+    let mut acc = op(&sets[0], &sets[0]);
+    for i in 0..ITERATIONS {
+        acc = op(black_box(&sets[i % sets.len()]), black_box(&sets[(i * 7 + 1) % sets.len()]));
+    }
+    black_box(acc);
+    println!("  {:<23} {:?}", name, now.elapsed());
+}
+
+fn time_smallest<S>(name: &str, sets: &[S], smallest: impl Fn(&S) -> Option<u8>) {
+    let now = Instant::now();
+    let mut acc = 0_u32;
+    for i in 0..ITERATIONS {
+        acc = acc.wrapping_add(smallest(black_box(&sets[i % sets.len()])).map_or(0, u32::from));
+    }
+    black_box(acc);
+    println!("  {:<23} {:?}", name, now.elapsed());
+}
+
+fn main() {
+    let mut rng = Pcg32::seed_from_u64(0);
+    let sets: Vec<ByteSet> = (0..256)
+        .map(|_| (0..rng.next_u32() % 64).map(|_| rng.next_u32().to_le_bytes()[0]).collect())
+        .collect();
+    let old: Vec<Bytewise> = sets.iter().map(Bytewise::new).collect();
+    for (set, old) in sets.iter().zip(&old) {
+        assert_eq!(set.smallest(), old.smallest());
+    }
+
+    println!("ByteSet ops x {ITERATIONS}, [u64; 4] against the previous [u8; 32]:");
+    time("union:", &sets, ByteSet::union);
+    time("union, [u8; 32]:", &old, Bytewise::union);
+    time("intersection:", &sets, ByteSet::intersection);
+    time("intersection, [u8; 32]:", &old, Bytewise::intersection);
+    time_smallest("smallest:", &sets, ByteSet::smallest);
+    time_smallest("smallest, [u8; 32]:", &old, Bytewise::smallest);
+}
@@ -0,0 +1,37 @@
+use std::time::Instant;
+use regex_deriv::{RegEx, ByteSet, DerivCache};
+
+fn main() {
+    let lower = RegEx::set(ByteSet::range(b'a', b'z'));
+    let digit = RegEx::set(ByteSet::range(b'0', b'9'));
+    let space = RegEx::set(ByteSet::point(b' '));
+    let word  = lower.then(&lower.or(&digit).star());
+    let regex = word.then(&space.then(&word).star()).and(&digit.then(&digit).then(&digit).not());
+
+    let text = "lorem ipsum dolor sit amet consectetur adipiscing elit sed do eiusmod tempor ".repeat(2_000);
+    let text = text.trim_end();
+
+    let now = Instant::now();
+    let uncached = regex.is_fullmatch(text);
+    let uncached_time = now.elapsed();
+
+    let mut cache = DerivCache::new();
+    let now = Instant::now();
+    let cached = regex.is_fullmatch_cached(text, &mut cache);
+    let cached_time = now.elapsed();
+
+    assert!(uncached && cached);
+
+    // every derivative of the text is memoized already, so a second pass
+    // adds none
+    let memoized = cache.len();
+    let now = Instant::now();
+    assert!(regex.is_fullmatch_cached(text, &mut cache));
+    let warm_time = now.elapsed();
+    assert_eq!(cache.len(), memoized);
+
+    println!("is_fullmatch over {} bytes:", text.len());
+    println!("  uncached: {uncached_time:?}");
+    println!("  cached:   {cached_time:?} ({memoized} derivatives memoized)");
+    println!("  warm:     {warm_time:?}");
+}
@@ -0,0 +1,139 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::convert::TryFrom;
+use std::time::Instant;
+use regex_deriv::{RegEx, ByteSet, DFA, DenseDFA, Scan};
+
+/// Allocator keeping count of the bytes currently allocated, for measuring
+/// the size of a DFA.
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn literal(s: &str) -> RegEx {
+    s.bytes().fold(RegEx::empty(), |acc, x| acc.then(&RegEx::set(ByteSet::point(x))))
+}
+
+fn main() {
+    let lower = RegEx::set(ByteSet::range(b'a', b'z'));
+    let upper = RegEx::set(ByteSet::range(b'A', b'Z'));
+    let digit = RegEx::set(ByteSet::range(b'0', b'9'));
+    let alpha = lower.or(&upper).or(&RegEx::set(ByteSet::point(b'_')));
+    let quote = RegEx::set(ByteSet::point(b'"'));
+
+    let keywords = [
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod",
+        "move", "mut", "pub", "ref", "return", "self", "static", "struct", "super", "trait",
+        "true", "type", "unsafe", "use", "where", "while",
+    ];
+    let punctuation = ["+", "-", "*", "/", "==", "!=", "<=", ">=", "&&", "||", "::", "->", "=>"];
+
+    let mut rules: Vec<RegEx> = keywords.iter().chain(&punctuation).map(|s| literal(s)).collect();
+    let keyword = keywords.iter().fold(RegEx::none(), |acc, s| acc.or(&literal(s)));
+    rules.push(alpha.then(&alpha.or(&digit).star()).and(&keyword.not()));
+    rules.push(digit.plus());
+    rules.push(digit.plus().then(&literal(".")).then(&digit.plus()));
+    rules.push(quote.then(&RegEx::set(ByteSet::point(b'"').complement()).star()).then(&quote));
+
+    bench("lexer", &rules);
+
+    // with whitespace and the rest of the punctuation, so that every byte of
+    // the text is part of some token
+    rules.push(RegEx::set(ByteSet::point(b' ')).plus());
+    rules.extend(["(", ")", "{", "}", ";", ".", "=", ">"].iter().map(|s| literal(s)));
+    let text = "fn main() { let x_1 = 42 + y.0 * 3.14; if x_1 >= 7 { return \"done\"; } } ".repeat(10_000);
+    bench_scan("lexer with whitespace", &DFA::from(&rules).minimize(), &text);
+
+    // any sequence of tokens, over a few MB, so that matching runs to the end
+    let tokens = rules.iter().fold(RegEx::none(), |acc, re| acc.or(re)).star();
+    let text = text.repeat(4);
+    bench_matching("token sequences", &DFA::from(&[tokens]).minimize(), text.as_bytes());
+
+    // digits ending in z, of which there are none, though only minimization
+    // would tell: without stopping at dead states, every token of a single
+    // digit reads the rest of the text
+    let any = RegEx::set(ByteSet::universe()).star();
+    let never = digit.plus().and(&any.then(&literal("z")));
+    let text = "0123456789".repeat(2_000);
+    bench_scan("rule with a dead region", &DFA::from(&[never, digit.clone()]), &text);
+
+    // keywords sharing an identifier tail, so that most states share most of
+    // their subexpressions
+    let tail = alpha.or(&digit).star();
+    let rules: Vec<RegEx> = keywords.iter().map(|s| literal(s).then(&tail)).collect();
+    bench("keywords with a shared tail", &rules);
+
+    // 100 rules, for which deriving each state is the bulk of the work, and
+    // which the `rayon` feature spreads over threads
+    let syllables = ["ka", "lo", "mi", "nu", "pe", "ra", "si", "to", "vu", "ze"];
+    let mut rules: Vec<RegEx> = syllables.iter()
+        .flat_map(|a| syllables.iter().map(move |b| literal(&format!("{a}{b}"))))
+        .take(96)
+        .collect();
+    rules.push(alpha.then(&alpha.or(&digit).star()));
+    rules.push(digit.plus());
+    rules.push(digit.plus().then(&literal(".")).then(&digit.plus()));
+    rules.push(quote.then(&RegEx::set(ByteSet::point(b'"').complement()).star()).then(&quote));
+    bench("large lexer", &rules);
+}
+
+fn bench(name: &str, rules: &[RegEx]) {
+    let now = Instant::now();
+    let dfa = DFA::from(rules);
+    let build_time = now.elapsed();
+
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    let now = Instant::now();
+    let minimized = dfa.minimize();
+    let minimize_time = now.elapsed();
+    let size = ALLOCATED.load(Ordering::Relaxed) - before;
+
+    println!("DFA for {} ({} rules):", name, rules.len());
+    println!("  build:    {:?} ({} states)", build_time, dfa.states().len());
+    println!("  minimize: {:?} ({} states, {} bytes)", minimize_time, minimized.states().len(), size);
+}
+
+fn bench_scan(name: &str, dfa: &DFA, text: &str) {
+    let now = Instant::now();
+    let tokens = Scan::new(dfa, text).flatten().count();
+    let scan_time = now.elapsed();
+
+    let len = f64::from(u32::try_from(text.len()).unwrap());
+    println!("Scanning with the DFA for {} ({} bytes):", name, text.len());
+    println!("  scan:     {:?} ({} tokens, {:.2} ns/byte)", scan_time, tokens, scan_time.as_secs_f64() * 1e9 / len);
+}
+
+fn bench_matching(name: &str, dfa: &DFA, input: &[u8]) {
+    let now = Instant::now();
+    let sparse = dfa.matches_bytes(input);
+    let sparse_time = now.elapsed();
+
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    let dense: DenseDFA = dfa.to_dense();
+    let size = ALLOCATED.load(Ordering::Relaxed) - before;
+    let now = Instant::now();
+    let matched = dense.matches_bytes(input);
+    let dense_time = now.elapsed();
+    assert_eq!(sparse, matched);
+
+    let len = f64::from(u32::try_from(input.len()).unwrap());
+    println!("Matching with the DFA for {} ({} bytes):", name, input.len());
+    println!("  sparse:   {:?} ({:.2} ns/byte)", sparse_time, sparse_time.as_secs_f64() * 1e9 / len);
+    println!("  dense:    {:?} ({:.2} ns/byte, {} bytes)", dense_time, dense_time.as_secs_f64() * 1e9 / len, size);
+}
@@ -0,0 +1,79 @@
+use std::convert::TryFrom;
+use std::time::Instant;
+use regex_deriv::{RegEx, ByteSet, DFA, LexTable, NaiveLexTable, CompressedLexTable, ClassLexTable, SparseLexTable, Scan};
+
+fn literal(s: &str) -> RegEx {
+    s.bytes().fold(RegEx::empty(), |acc, x| acc.then(&RegEx::set(ByteSet::point(x))))
+}
+
+fn main() {
+    let alpha = RegEx::set(ByteSet::ASCII_ALPHA.union(&ByteSet::point(b'_')));
+    let digit = RegEx::set(ByteSet::ASCII_DIGIT);
+    let quote = RegEx::set(ByteSet::point(b'"'));
+
+    let keywords = ["fn", "let", "if", "else", "return", "while", "for", "in", "mut", "pub", "struct", "impl"];
+    let punctuation = [
+        "(", ")", "{", "}", "[", "]", ";", ",", ".", ":", "::", "->", "=>",
+        "=", "==", "!=", "<", "<=", ">", ">=", "+", "-", "*", "/", "&&", "||", "!",
+    ];
+    let mut rules: Vec<RegEx> = keywords.iter().chain(&punctuation).map(|s| literal(s)).collect();
+    rules.push(alpha.then(&alpha.or(&digit).star()));
+    rules.push(digit.plus());
+    rules.push(digit.plus().then(&literal(".")).then(&digit.plus()));
+    rules.push(quote.then(&RegEx::set(ByteSet::point(b'"').complement()).star()).then(&quote));
+    rules.push(RegEx::set(ByteSet::ASCII_WHITESPACE).plus());
+    let dfa = DFA::from(&rules).minimize();
+
+    let source = "pub fn main() {\n    let mut x_1 = 42 + y.0 * 3.14;\n    if x_1 >= 7 && !done { return \"done\"; }\n    for i in v.iter() { z = z::f(i) -> 1; }\n}\n";
+    let text = source.repeat(4_000_000 / source.len());
+
+    bench_tables("lexer", &dfa, &text);
+
+    // a thousand keywords, so that the naive table outgrows the cache
+    let syllables = ["ka", "lo", "mi", "nu", "pe", "ra", "si", "to", "vu", "ze"];
+    let mut words = Vec::new();
+    for a in &syllables {
+        for b in &syllables {
+            words.extend(syllables.iter().map(|c| format!("{a}{b}{c}")));
+        }
+    }
+    let mut rules: Vec<RegEx> = words.iter().map(|s| literal(s)).collect();
+    rules.push(alpha.then(&alpha.or(&digit).star()));
+    rules.push(RegEx::set(ByteSet::ASCII_WHITESPACE).plus());
+    let dfa = DFA::from(&rules).minimize();
+
+    let source = words.iter().step_by(7).fold(String::new(), |acc, word| acc + word + " x" + word + " ");
+    let text = source.repeat(4_000_000 / source.len());
+
+    bench_tables("large lexer", &dfa, &text);
+}
+
+fn bench_tables(name: &str, dfa: &DFA, text: &str) {
+    println!("Scanning with the tables of a {} ({} states, {} bytes):", name, dfa.states().len(), text.len());
+    let table = NaiveLexTable::<usize>::from_dfa(dfa).unwrap();
+    bench("naive (usize)", &table, table.memory_usage(), text);
+    let table = NaiveLexTable::new(dfa);
+    bench("naive (u32)", &table, table.memory_usage(), text);
+    let table = NaiveLexTable::<u16>::from_dfa(dfa).unwrap();
+    bench("naive (u16)", &table, table.memory_usage(), text);
+    let table = CompressedLexTable::new(dfa);
+    assert!(table.memory_usage() < NaiveLexTable::new(dfa).memory_usage());
+    bench("compressed", &table, table.memory_usage(), text);
+    let table = ClassLexTable::new(dfa);
+    bench(&format!("class ({})", table.byte_class_count()), &table, table.memory_usage(), text);
+    let table = SparseLexTable::new(dfa);
+    bench("sparse", &table, table.memory_usage(), text);
+}
+
+fn bench<T: LexTable>(name: &str, table: &T, memory: usize, text: &str) {
+    // the best of a few runs, as the first is slowed by warming up
+    let mut tokens = 0;
+    let time = (0..5).map(|_| {
+        let now = Instant::now();
+        tokens = Scan::new(table, text).flatten().count();
+        now.elapsed()
+    }).min().unwrap();
+
+    let len = f64::from(u32::try_from(text.len()).unwrap());
+    println!("  {:<13} {:?} ({} tokens, {:.2} ns/byte, {} KiB)", format!("{name}:"), time, tokens, time.as_secs_f64() * 1e9 / len, memory / 1024);
+}
@@ -1,5 +1,4 @@
-extern crate lalrpop;
-
 fn main() {
+    #[cfg(feature = "syntax")]
     lalrpop::process_root().unwrap();
-}
\ No newline at end of file
+}
@@ -1,10 +1,114 @@
+use std::iter::{FromIterator, Peekable};
+use std::marker::PhantomData;
+use std::ops::RangeInclusive;
+
 /// Compactly represents a set of 8-bit values. Internally uses a bitmap.
+///
+/// Sets can be combined with operators: `|` (union), `&` (intersection),
+/// `-` (difference), and `!` (complement).
+///
+/// # Examples
+///
+/// ```
+/// # use regex_deriv::ByteSet;
+/// const ALPHA: ByteSet = ByteSet::from_ranges(&[(b'a', b'z'), (b'A', b'Z')]);
+/// const DIGIT: ByteSet = ByteSet::range(b'0', b'9');
+///
+/// let word = ALPHA | DIGIT | ByteSet::point(b'_');
+/// assert_eq!(word, ALPHA.union(&DIGIT).union(&ByteSet::point(b'_')));
+/// assert_eq!(!&word - ByteSet::range(128, 255), ByteSet::range(0, 127) - &word);
+/// ```
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ByteSet {
-    bitmap: [u8; 32],
+    bitmap: [Word; WORDS],
 }
 
 impl ByteSet {
+    /// The ASCII decimal digits `0-9`.
+    pub const ASCII_DIGIT: Self = Self::range(b'0', b'9');
+
+    /// The ASCII uppercase letters `A-Z`.
+    pub const ASCII_UPPER: Self = Self::range(b'A', b'Z');
+
+    /// The ASCII lowercase letters `a-z`.
+    pub const ASCII_LOWER: Self = Self::range(b'a', b'z');
+
+    /// The ASCII letters `A-Za-z`.
+    pub const ASCII_ALPHA: Self = Self::ASCII_UPPER.union(&Self::ASCII_LOWER);
+
+    /// The ASCII letters and digits `0-9A-Za-z`.
+    pub const ASCII_ALNUM: Self = Self::ASCII_ALPHA.union(&Self::ASCII_DIGIT);
+
+    /// The ASCII whitespace bytes, as defined by `u8::is_ascii_whitespace`:
+    /// space, `\t`, `\n`, form feed and `\r`.
+    pub const ASCII_WHITESPACE: Self = Self::from_ranges(&[(b' ', b' '), (b'\t', b'\n'), (0x0c, b'\r')]);
+
+    /// The ASCII hexadecimal digits `0-9A-Fa-f`.
+    pub const ASCII_HEXDIGIT: Self = Self::from_ranges(&[(b'0', b'9'), (b'A', b'F'), (b'a', b'f')]);
+
+    /// The ASCII punctuation bytes, i.e. the graphic bytes that are not
+    /// letters or digits.
+    pub const ASCII_PUNCT: Self = Self::range(b'!', b'~').difference(&Self::ASCII_ALNUM);
+
+    /// The ASCII control bytes `0x00-0x1f` and `0x7f`.
+    pub const ASCII_CONTROL: Self = Self::from_ranges(&[(0x00, 0x1f), (0x7f, 0x7f)]);
+
+    /// The word bytes `0-9A-Za-z_`.
+    pub const WORD: Self = Self::ASCII_ALNUM.union(&Self::point(b'_'));
+
+    /// The UTF-8 continuation bytes `0x80-0xbf`.
+    pub const UTF8_CONT: Self = Self::range(0x80, 0xbf);
+
+    /// Returns the bytes that begin the UTF-8 encoding of some `char` that is
+    /// encoded in `len` bytes. Excludes bytes that only begin overlong
+    /// encodings (`0xc0`, `0xc1`) or encodings above U+10FFFF (`0xf5-0xff`).
+    /// The set is empty unless `1 <= len <= 4`.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use regex_deriv::ByteSet;
+    /// assert_eq!(ByteSet::utf8_lead(1), ByteSet::range(0x00, 0x7f));
+    /// assert_eq!(ByteSet::utf8_lead(2), ByteSet::range(0xc2, 0xdf));
+    /// assert!(ByteSet::utf8_lead(4).contains("𐍈".as_bytes()[0]));
+    /// ```
+    #[must_use]
+    pub const fn utf8_lead(len: u8) -> Self {
+        match len {
+            1 => Self::range(0x00, 0x7f),
+            2 => Self::range(0xc2, 0xdf),
+            3 => Self::range(0xe0, 0xef),
+            4 => Self::range(0xf0, 0xf4),
+            _ => Self::empty(),
+        }
+    }
+
+    /// Returns the bytes that can follow `lead` in a UTF-8 encoding. This is
+    /// `UTF8_CONT`, except after `0xe0` and `0xf0` (excluding overlong
+    /// encodings), `0xed` (excluding surrogates) and `0xf4` (excluding
+    /// encodings above U+10FFFF). The set is empty if `lead` does not begin a
+    /// multi-byte encoding.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use regex_deriv::ByteSet;
+    /// assert_eq!(ByteSet::utf8_second(0xc3), ByteSet::UTF8_CONT);
+    /// assert_eq!(ByteSet::utf8_second(0xed), ByteSet::range(0x80, 0x9f));
+    /// assert!(ByteSet::utf8_second(b'a').is_empty());
+    /// ```
+    #[must_use]
+    pub const fn utf8_second(lead: u8) -> Self {
+        match lead {
+            0xe0 => Self::range(0xa0, 0xbf),
+            0xed => Self::range(0x80, 0x9f),
+            0xf0 => Self::range(0x90, 0xbf),
+            0xf4 => Self::range(0x80, 0x8f),
+            0xc2..=0xf4 => Self::UTF8_CONT,
+            _ => Self::empty(),
+        }
+    }
+
     /// Returns the empty set {}.
     /// 
     /// # Examples
@@ -18,7 +122,7 @@ impl ByteSet {
     /// ```
     #[must_use]
     pub const fn empty() -> Self {
-        Self { bitmap: [0; 32] }
+        Self { bitmap: [0; WORDS] }
     }
 
     /// Returns the set {0, ..., 255}.
@@ -34,7 +138,7 @@ impl ByteSet {
     /// ```
     #[must_use]
     pub const fn universe() -> Self {
-        Self { bitmap: [u8::MAX; 32] }
+        Self { bitmap: [Word::MAX; WORDS] }
     }
 
     /// Returns the set {`value`}.
@@ -56,7 +160,7 @@ impl ByteSet {
         set
     }
 
-    /// Returns the set {`from`, ..., `to`}.
+    /// Returns the set {`from`, ..., `to`}, which is empty if `from > to`.
     /// 
     /// # Examples
     /// 
@@ -66,9 +170,14 @@ impl ByteSet {
     /// for x in 0..=255 {
     ///     assert_eq!((83..=149).contains(&x), set.contains(x))
     /// }
+    /// assert!(ByteSet::range(149, 83).is_empty());
     /// ```
     #[must_use]
     pub const fn range(from: u8, to: u8) -> Self {
+        if from > to {
+            return Self::empty()
+        }
+
         let (from_index, a) = encode(from);
         let (to_index, b) = encode(to);
 
@@ -82,7 +191,7 @@ impl ByteSet {
             set.bitmap[from_index] = first_word;
             let mut i = from_index + 1;
             while i < to_index {
-                set.bitmap[i] = u8::MAX;
+                set.bitmap[i] = Word::MAX;
                 i += 1;
             }
             set.bitmap[to_index] = last_word;
@@ -90,7 +199,80 @@ impl ByteSet {
         set
     }
 
-    /// Returns `true` if `self` is the empty set.
+    /// Returns the union of the sets {`from`, ..., `to`} for each `(from, to)`
+    /// in `ranges`. Ranges where `from > to` are empty.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use regex_deriv::ByteSet;
+    /// let set = ByteSet::from_ranges(&[(b'a', b'z'), (b'0', b'9'), (b'_', b'_')]);
+    /// assert!(set.contains(b'q') && set.contains(b'5') && set.contains(b'_'));
+    /// assert!(!set.contains(b'A'));
+    /// ```
+    #[must_use]
+    pub const fn from_ranges(ranges: &[(u8, u8)]) -> Self {
+        let mut set = Self::empty();
+        let mut i = 0;
+        while i < ranges.len() {
+            let (from, to) = ranges[i];
+            set.union_assign(&Self::range(from, to));
+            i += 1;
+        }
+        set
+    }
+
+    /// Returns the complement of `ByteSet::from_ranges(ranges)`, i.e. the set
+    /// of values not in any of `ranges`.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use regex_deriv::ByteSet;
+    /// static PRINTABLE: ByteSet = ByteSet::range(b' ', b'~').intersection(&ByteSet::except_ranges(&[(b'"', b'"'), (b'\'', b'\''), (b'\\', b'\\')]));
+    /// assert!(PRINTABLE.contains(b'a') && PRINTABLE.contains(b' '));
+    /// assert!(!PRINTABLE.contains(b'"') && !PRINTABLE.contains(b'\\') && !PRINTABLE.contains(b'\n'));
+    /// ```
+    #[must_use]
+    pub const fn except_ranges(ranges: &[(u8, u8)]) -> Self {
+        Self::from_ranges(ranges).complement()
+    }
+
+    /// Returns the set of values `x` for which `f(x)` is `true`.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use regex_deriv::ByteSet;
+    /// let query = ByteSet::from_fn(|x| x.is_ascii_alphanumeric() || b"-._~!$'()*+,;=:@/?".contains(&x));
+    /// assert!(query.contains(b'?') && !query.contains(b'#'));
+    /// ```
+    pub fn from_fn<F: Fn(u8) -> bool>(f: F) -> Self {
+        (0..=255).filter(|&x| f(x)).collect()
+    }
+
+    /// Returns the number of values in `self`.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use regex_deriv::ByteSet;
+    /// assert_eq!(0, ByteSet::empty().len());
+    /// assert_eq!(256, ByteSet::universe().len());
+    /// assert_eq!(11, ByteSet::range(10, 20).len());
+    /// ```
+    #[must_use]
+    pub const fn len(&self) -> u32 {
+        let mut len = 0;
+        let mut i = 0;
+        while i < WORDS {
+            len += self.bitmap[i].count_ones();
+            i += 1;
+        }
+        len
+    }
+
+    /// Returns `true` if `self` is the empty set, i.e. iff `self.len() == 0`.
     /// 
     /// # Examples
     /// 
@@ -103,7 +285,7 @@ impl ByteSet {
     #[must_use]
     pub const fn is_empty(&self) -> bool {
         let mut i = 0;
-        while i < 32 {
+        while i < WORDS {
             if self.bitmap[i] != 0 {
                 return false
             }
@@ -125,8 +307,8 @@ impl ByteSet {
     #[must_use]
     pub const fn is_universe(&self) -> bool {
         let mut i = 0;
-        while i < 32 {
-            if self.bitmap[i] == 0 {
+        while i < WORDS {
+            if self.bitmap[i] != Word::MAX {
                 return false
             }
             i += 1;
@@ -153,6 +335,133 @@ impl ByteSet {
         }
     }
 
+    /// Returns the largest value in `self`. If set is empty, `None` is
+    /// returned.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use regex_deriv::ByteSet;
+    /// assert_eq!(Some(149), ByteSet::range(83, 149).largest());
+    /// assert_eq!(None, ByteSet::empty().largest());
+    /// ```
+    #[must_use]
+    pub const fn largest(&self) -> Option<u8> {
+        let mut i = WORDS;
+        while i > 0 {
+            i -= 1;
+            if self.bitmap[i] != 0 {
+                return Some(decode_last(i, self.bitmap[i]))
+            }
+        }
+        None
+    }
+
+    /// Returns the sole value in `self` if `self` has exactly one value.
+    /// Otherwise, `None` is returned.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use regex_deriv::ByteSet;
+    /// assert_eq!(Some(b'a'), ByteSet::point(b'a').is_single());
+    /// assert_eq!(None, ByteSet::range(b'a', b'b').is_single());
+    /// assert_eq!(None, ByteSet::empty().is_single());
+    /// ```
+    #[must_use]
+    pub const fn is_single(&self) -> Option<u8> {
+        let mut single = None;
+        let mut i = 0;
+        while i < WORDS {
+            let word = self.bitmap[i];
+            if word != 0 {
+                if single.is_some() || word & (word - 1) != 0 {
+                    return None
+                }
+                single = Some(decode(i, word));
+            }
+            i += 1;
+        }
+        single
+    }
+
+    /// Returns the smallest and largest values in `self` if `self` is
+    /// nonempty and contains every value in between. Otherwise, `None` is
+    /// returned.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use regex_deriv::ByteSet;
+    /// assert_eq!(Some((b'a', b'z')), ByteSet::range(b'a', b'z').is_range());
+    /// assert_eq!(Some((b'a', b'a')), ByteSet::point(b'a').is_range());
+    /// assert_eq!(None, ByteSet::from_ranges(&[(b'a', b'b'), (b'd', b'e')]).is_range());
+    /// assert_eq!(None, ByteSet::empty().is_range());
+    /// ```
+    #[must_use]
+    pub const fn is_range(&self) -> Option<(u8, u8)> {
+        match (self.smallest(), self.largest()) {
+            (Some(from), Some(to)) if (to - from) as u32 + 1 == self.len() => Some((from, to)),
+            _ => None,
+        }
+    }
+
+    /// Returns the `k`-th smallest value in `self`, counting from 0. If
+    /// `self` has at most `k` values, `None` is returned.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use regex_deriv::ByteSet;
+    /// let set = ByteSet::from_ranges(&[(b'0', b'9'), (b'a', b'z')]);
+    /// assert_eq!(Some(b'0'), set.nth(0));
+    /// assert_eq!(Some(b'b'), set.nth(11));
+    /// assert_eq!(None, set.nth(36));
+    /// ```
+    #[must_use]
+    pub const fn nth(&self, k: u32) -> Option<u8> {
+        let mut k = k;
+        let mut i = 0;
+        while i < WORDS {
+            let mut word = self.bitmap[i];
+            let count = word.count_ones();
+            if k < count {
+                while k > 0 {
+                    word &= word - 1;
+                    k -= 1;
+                }
+                return Some(decode(i, word))
+            }
+            k -= count;
+            i += 1;
+        }
+        None
+    }
+
+    /// Returns the number of values in `self` that are less than `value`,
+    /// i.e. the index of `value` in `self` if it is contained.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use regex_deriv::ByteSet;
+    /// let set = ByteSet::from_ranges(&[(b'0', b'9'), (b'a', b'z')]);
+    /// assert_eq!(0, set.rank(b'0'));
+    /// assert_eq!(10, set.rank(b'_'));
+    /// assert_eq!(Some(b'q'), set.nth(set.rank(b'q')));
+    /// ```
+    #[must_use]
+    pub const fn rank(&self, value: u8) -> u32 {
+        let (index, word) = encode(value);
+        let mut rank = (self.bitmap[index] & (word - 1)).count_ones();
+        let mut i = 0;
+        while i < index {
+            rank += self.bitmap[i].count_ones();
+            i += 1;
+        }
+        rank
+    }
+
     /// Returns `true` if `value` is contained in `self`.
     /// 
     /// # Examples
@@ -169,6 +478,193 @@ impl ByteSet {
         self.bitmap[index] & word != 0
     }
 
+    /// Adds `value` to `self`. Returns `true` if `value` was not already
+    /// contained in `self`.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use regex_deriv::ByteSet;
+    /// let mut set = ByteSet::empty();
+    /// assert!(set.insert(255));
+    /// assert!(!set.insert(255));
+    /// assert_eq!(ByteSet::point(255), set);
+    /// ```
+    pub const fn insert(&mut self, value: u8) -> bool {
+        let (index, word) = encode(value);
+        let inserted = self.bitmap[index] & word == 0;
+        self.bitmap[index] |= word;
+        inserted
+    }
+
+    /// Removes `value` from `self`. Returns `true` if `value` was contained
+    /// in `self`.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use regex_deriv::ByteSet;
+    /// let mut set = ByteSet::range(0, 9);
+    /// assert!(set.remove(0));
+    /// assert!(!set.remove(0));
+    /// assert_eq!(ByteSet::range(1, 9), set);
+    /// ```
+    pub const fn remove(&mut self, value: u8) -> bool {
+        let (index, word) = encode(value);
+        let removed = self.bitmap[index] & word != 0;
+        self.bitmap[index] &= !word;
+        removed
+    }
+
+    /// Adds `value` to `self` if it is not contained in `self`, and removes
+    /// it otherwise. Returns `true` if `value` is contained in `self`
+    /// afterwards.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use regex_deriv::ByteSet;
+    /// let mut set = ByteSet::empty();
+    /// assert!(set.toggle(b'a'));
+    /// assert!(!set.toggle(b'a'));
+    /// assert!(set.is_empty());
+    /// ```
+    pub const fn toggle(&mut self, value: u8) -> bool {
+        let (index, word) = encode(value);
+        self.bitmap[index] ^= word;
+        self.bitmap[index] & word != 0
+    }
+
+    /// Removes and returns the smallest value in `self`. If set is empty,
+    /// `None` is returned.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use regex_deriv::ByteSet;
+    /// let mut set = ByteSet::from_ranges(&[(3, 4), (200, 200)]);
+    /// assert_eq!(Some(3), set.pop_smallest());
+    /// assert_eq!(Some(4), set.pop_smallest());
+    /// assert_eq!(Some(200), set.pop_smallest());
+    /// assert_eq!(None, set.pop_smallest());
+    /// ```
+    pub const fn pop_smallest(&mut self) -> Option<u8> {
+        let mut i = 0;
+        while i < WORDS {
+            let word = self.bitmap[i];
+            if word != 0 {
+                self.bitmap[i] &= word - 1;
+                return Some(decode(i, word))
+            }
+            i += 1;
+        }
+        None
+    }
+
+    /// Adds {`from`, ..., `to`} to `self`. Does nothing if `from > to`.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use regex_deriv::ByteSet;
+    /// let mut set = ByteSet::point(b'_');
+    /// set.insert_range(b'a', b'z');
+    /// assert_eq!(ByteSet::from_ranges(&[(b'_', b'_'), (b'a', b'z')]), set);
+    /// ```
+    pub const fn insert_range(&mut self, from: u8, to: u8) {
+        self.union_assign(&Self::range(from, to));
+    }
+
+    /// Removes every value `x` from `self` for which `f(x)` is `false`.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use regex_deriv::ByteSet;
+    /// let mut set = ByteSet::range(0, 9);
+    /// set.retain(|x| x % 2 == 0);
+    /// assert_eq!(ByteSet::from_ranges(&[(0, 0), (2, 2), (4, 4), (6, 6), (8, 8)]), set);
+    /// ```
+    pub fn retain<F: Fn(u8) -> bool>(&mut self, f: F) {
+        for x in self.clone().bytes() {
+            if !f(x) {
+                self.remove(x);
+            }
+        }
+    }
+
+    /// Returns `true` if every value in `self` is contained in `other`.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use regex_deriv::ByteSet;
+    /// assert!(ByteSet::range(10, 20).is_subset(&ByteSet::range(0, 20)));
+    /// assert!(!ByteSet::range(10, 21).is_subset(&ByteSet::range(0, 20)));
+    /// assert!(ByteSet::empty().is_subset(&ByteSet::empty()));
+    /// ```
+    #[must_use]
+    pub const fn is_subset(&self, other: &Self) -> bool {
+        let mut i = 0;
+        while i < WORDS {
+            if self.bitmap[i] & !other.bitmap[i] != 0 {
+                return false
+            }
+            i += 1;
+        }
+        true
+    }
+
+    /// Returns `true` if every value in `other` is contained in `self`.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use regex_deriv::ByteSet;
+    /// assert!(ByteSet::universe().is_superset(&ByteSet::range(10, 20)));
+    /// assert!(!ByteSet::point(10).is_superset(&ByteSet::range(10, 20)));
+    /// ```
+    #[must_use]
+    pub const fn is_superset(&self, other: &Self) -> bool {
+        other.is_subset(self)
+    }
+
+    /// Returns `true` if `self` and `other` have no values in common.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use regex_deriv::ByteSet;
+    /// assert!(ByteSet::range(0, 9).is_disjoint(&ByteSet::range(10, 20)));
+    /// assert!(!ByteSet::range(0, 10).is_disjoint(&ByteSet::range(10, 20)));
+    /// ```
+    #[must_use]
+    pub const fn is_disjoint(&self, other: &Self) -> bool {
+        let mut i = 0;
+        while i < WORDS {
+            if self.bitmap[i] & other.bitmap[i] != 0 {
+                return false
+            }
+            i += 1;
+        }
+        true
+    }
+
+    /// Returns `true` if `self` and `other` have some value in common, i.e.
+    /// iff they are not disjoint.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use regex_deriv::ByteSet;
+    /// assert!(ByteSet::range(0, 10).overlaps(&ByteSet::range(10, 20)));
+    /// assert!(!ByteSet::empty().overlaps(&ByteSet::universe()));
+    /// ```
+    #[must_use]
+    pub const fn overlaps(&self, other: &Self) -> bool {
+        !self.is_disjoint(other)
+    }
+
     /// Returns the complement of `self`.
     /// 
     /// # Examples
@@ -184,7 +680,7 @@ impl ByteSet {
     pub const fn complement(&self) -> Self {
         let mut set = Self::empty();
         let mut i = 0;
-        while i < 32 {
+        while i < WORDS {
             set.bitmap[i] = !self.bitmap[i];
             i += 1;
         }
@@ -208,7 +704,7 @@ impl ByteSet {
     pub const fn intersection(&self, other: &Self) -> Self {
         let mut set = Self::empty();
         let mut i = 0;
-        while i < 32 {
+        while i < WORDS {
             set.bitmap[i] = self.bitmap[i] & other.bitmap[i];
             i += 1;
         }
@@ -232,33 +728,150 @@ impl ByteSet {
     pub const fn union(&self, other: &Self) -> Self {
         let mut set = Self::empty();
         let mut i = 0;
-        while i < 32 {
+        while i < WORDS {
             set.bitmap[i] = self.bitmap[i] | other.bitmap[i];
             i += 1;
         }
         set
     }
 
-    /// Returns an iterator over all values in `self`.
+    /// Returns the set of values in `self` but not in `other`.
     /// 
     /// # Examples
     /// 
     /// ```
     /// # use regex_deriv::ByteSet;
-    /// for (x, y) in ByteSet::range(83, 149).bytes().zip(83..=149) {
-    ///     assert_eq!(y, x);
+    /// let set = ByteSet::range(83, 149).difference(&ByteSet::range(59, 113));
+    /// for x in 0..=255 {
+    ///     assert_eq!((114..=149).contains(&x), set.contains(x));
     /// }
     /// ```
     #[must_use]
-    pub const fn bytes(&self) -> Bytes {
-        Bytes::new(self)
-    }
+    pub const fn difference(&self, other: &Self) -> Self {
+        let mut set = Self::empty();
+        let mut i = 0;
+        while i < WORDS {
+            set.bitmap[i] = self.bitmap[i] & !other.bitmap[i];
+            i += 1;
+        }
+        set
+    }
+
+    /// Returns the set of values in exactly one of `self` and `other`.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use regex_deriv::ByteSet;
+    /// let set = ByteSet::range(83, 149).symmetric_difference(&ByteSet::range(59, 113));
+    /// for x in 0..=255 {
+    ///     assert_eq!((59..=82).contains(&x) || (114..=149).contains(&x), set.contains(x));
+    /// }
+    /// ```
+    #[must_use]
+    pub const fn symmetric_difference(&self, other: &Self) -> Self {
+        let mut set = Self::empty();
+        let mut i = 0;
+        while i < WORDS {
+            set.bitmap[i] = self.bitmap[i] ^ other.bitmap[i];
+            i += 1;
+        }
+        set
+    }
+
+    /// Replaces `self` with its intersection with `other`.
+    pub const fn intersection_assign(&mut self, other: &Self) {
+        *self = self.intersection(other);
+    }
+
+    /// Replaces `self` with its union with `other`.
+    pub const fn union_assign(&mut self, other: &Self) {
+        *self = self.union(other);
+    }
+
+    /// Replaces `self` with its difference with `other`.
+    pub const fn difference_assign(&mut self, other: &Self) {
+        *self = self.difference(other);
+    }
+
+    /// Replaces `self` with its symmetric difference with `other`.
+    pub const fn symmetric_difference_assign(&mut self, other: &Self) {
+        *self = self.symmetric_difference(other);
+    }
+
+    /// Returns an iterator over all values in `self`.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use regex_deriv::ByteSet;
+    /// for (x, y) in ByteSet::range(83, 149).bytes().zip(83..=149) {
+    ///     assert_eq!(y, x);
+    /// }
+    /// ```
+    #[must_use]
+    pub const fn bytes(&self) -> Bytes {
+        Bytes::new(self)
+    }
+
+    /// Returns an iterator over the maximal runs of contiguous values in
+    /// `self`, in ascending order.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use regex_deriv::ByteSet;
+    /// let set = ByteSet::range(b'a', b'z').union(&ByteSet::range(b'0', b'9'));
+    /// assert_eq!(set.ranges().collect::<Vec<_>>(), [b'0'..=b'9', b'a'..=b'z']);
+    /// assert_eq!(ByteSet::universe().ranges().collect::<Vec<_>>(), [0..=255]);
+    /// ```
+    pub fn ranges(&self) -> impl Iterator<Item = RangeInclusive<u8>> + '_ {
+        Ranges { bytes: self.bytes().peekable() }
+    }
+
+    /// Parses a bracket-free character class, such as `a-zA-Z0-9_`, into a
+    /// set. A leading `^` negates the class. Chars must be ASCII; other bytes
+    /// can be written with the escapes `\xNN`, `\n`, `\r` and `\t`, and `-`,
+    /// `^` and `\` are escaped with a backslash.
+    /// 
+    /// # Errors
+    /// 
+    /// Returns a `ClassParseError` locating the first offending part of
+    /// `class`.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use regex_deriv::ByteSet;
+    /// let word = ByteSet::parse("a-zA-Z0-9_").unwrap();
+    /// assert_eq!(word, ByteSet::from_ranges(&[(b'a', b'z'), (b'A', b'Z'), (b'0', b'9'), (b'_', b'_')]));
+    /// assert_eq!(ByteSet::parse(r"^\x00-\x1f\-").unwrap(), ByteSet::from_ranges(&[(0x00, 0x1f), (b'-', b'-')]).complement());
+    /// assert_eq!(ByteSet::parse("a-").unwrap_err().pos(), 1);
+    /// ```
+    pub fn parse(class: &str) -> Result<Self, ClassParseError> {
+        let (negated, offset) = if class.starts_with('^') { (true, 1) } else { (false, 0) };
+        let mut chars = class[offset..].char_indices().map(|(pos, c)| (pos + offset, c)).peekable();
+
+        let mut set = Self::empty();
+        while let Some((pos, from)) = parse_class_byte(&mut chars)? {
+            match chars.next_if(|&(_, c)| c == '-') {
+                Some((hyphen, _)) => match parse_class_byte(&mut chars)? {
+                    Some((_, to)) if from <= to => set.insert_range(from, to),
+                    Some(_) => return Err(ClassParseError { pos, kind: ClassParseErrorKind::ReversedRange }),
+                    None => return Err(ClassParseError { pos: hyphen, kind: ClassParseErrorKind::DanglingHyphen }),
+                },
+                None => { set.insert(from); },
+            }
+        }
+
+        Ok(if negated { set.complement() } else { set })
+    }
 
     /// Returns encoding of first char in set.
     #[must_use]
-    const fn first(&self) -> Option<(usize, u8)> {
+    const fn first(&self) -> Option<(usize, Word)> {
         let mut i = 0;
-        while i < 32 {
+        while i < WORDS {
             if self.bitmap[i] != 0 {
                 return Some((i, self.bitmap[i]))
             }
@@ -268,28 +881,296 @@ impl ByteSet {
     }
 }
 
+macro_rules! binop_impl {
+    ($trait:ident, $method:ident, $assign_trait:ident, $assign_method:ident, $op:ident, $assign_op:ident) => {
+        impl std::ops::$trait<&ByteSet> for &ByteSet {
+            type Output = ByteSet;
+            fn $method(self, other: &ByteSet) -> ByteSet { self.$op(other) }
+        }
+
+        impl std::ops::$trait<ByteSet> for &ByteSet {
+            type Output = ByteSet;
+            fn $method(self, other: ByteSet) -> ByteSet { self.$op(&other) }
+        }
+
+        impl std::ops::$trait<&ByteSet> for ByteSet {
+            type Output = ByteSet;
+            fn $method(self, other: &ByteSet) -> ByteSet { self.$op(other) }
+        }
+
+        impl std::ops::$trait<ByteSet> for ByteSet {
+            type Output = ByteSet;
+            fn $method(self, other: ByteSet) -> ByteSet { self.$op(&other) }
+        }
+
+        impl std::ops::$assign_trait<&ByteSet> for ByteSet {
+            fn $assign_method(&mut self, other: &ByteSet) { self.$assign_op(other) }
+        }
+
+        impl std::ops::$assign_trait<ByteSet> for ByteSet {
+            fn $assign_method(&mut self, other: ByteSet) { self.$assign_op(&other) }
+        }
+    };
+}
+
+binop_impl!(BitOr, bitor, BitOrAssign, bitor_assign, union, union_assign);
+binop_impl!(BitAnd, bitand, BitAndAssign, bitand_assign, intersection, intersection_assign);
+binop_impl!(Sub, sub, SubAssign, sub_assign, difference, difference_assign);
+
+impl std::ops::Not for &ByteSet {
+    type Output = ByteSet;
+    fn not(self) -> ByteSet { self.complement() }
+}
+
+impl std::ops::Not for ByteSet {
+    type Output = ByteSet;
+    fn not(self) -> ByteSet { self.complement() }
+}
+
+impl FromIterator<u8> for ByteSet {
+    fn from_iter<I: IntoIterator<Item = u8>>(iter: I) -> Self {
+        let mut set = Self::empty();
+        set.extend(iter);
+        set
+    }
+}
+
+impl FromIterator<RangeInclusive<u8>> for ByteSet {
+    fn from_iter<I: IntoIterator<Item = RangeInclusive<u8>>>(iter: I) -> Self {
+        let mut set = Self::empty();
+        for range in iter.into_iter().filter(|range| !range.is_empty()) {
+            set.union_assign(&Self::range(*range.start(), *range.end()));
+        }
+        set
+    }
+}
+
+impl Extend<u8> for ByteSet {
+    fn extend<I: IntoIterator<Item = u8>>(&mut self, iter: I) {
+        for value in iter {
+            let (index, word) = encode(value);
+            self.bitmap[index] |= word;
+        }
+    }
+}
+
+// `bytes` is the corresponding `iter` method
+#[allow(clippy::into_iter_without_iter)]
+impl<'a> IntoIterator for &'a ByteSet {
+    type Item = u8;
+    type IntoIter = Bytes<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.bytes()
+    }
+}
+
+impl std::str::FromStr for ByteSet {
+    type Err = ClassParseError;
+
+    fn from_str(class: &str) -> Result<Self, Self::Err> {
+        Self::parse(class)
+    }
+}
+
+/// Error produced when a character class fails to parse.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ClassParseError {
+    pos: usize,
+    kind: ClassParseErrorKind,
+}
+
+/// The ways in which a character class can fail to parse.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ClassParseErrorKind {
+    /// A `-` that is not between two chars.
+    DanglingHyphen,
+    /// A range whose first char is greater than its last.
+    ReversedRange,
+    /// A backslash that does not begin a valid escape sequence.
+    InvalidEscape,
+    /// A char that does not fit in a single byte.
+    NonAscii(char),
+}
+
+impl ClassParseError {
+    /// Returns the byte offset into the class at which the error occurred.
+    #[must_use]
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns what went wrong.
+    #[must_use]
+    pub fn kind(&self) -> &ClassParseErrorKind {
+        &self.kind
+    }
+}
+
+impl std::fmt::Display for ClassParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ClassParseErrorKind::DanglingHyphen => write!(f, "expected a char on both sides of '-' at {}", self.pos),
+            ClassParseErrorKind::ReversedRange  => write!(f, "expected range start to be at most its end at {}", self.pos),
+            ClassParseErrorKind::InvalidEscape  => write!(f, "expected one of \\-, \\^, \\\\, \\n, \\r, \\t or \\xNN at {}", self.pos),
+            ClassParseErrorKind::NonAscii(c)    => write!(f, "expected an ASCII char, found {:?} at {}", c, self.pos),
+        }
+    }
+}
+
+impl std::error::Error for ClassParseError {}
+
+/// Bytes that are escaped when written within a set.
+const SPECIAL: &[u8] = b"[]^-\\";
+
+impl std::fmt::Display for ByteSet {
+    /// Writes `self` as a bracketed list of ranges, negated if `self` has more
+    /// than 128 elements.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let (set, open) = if self.len() > 128 {
+            (self.complement(), "[^")
+        } else {
+            (self.clone(), "[")
+        };
+
+        f.write_str(open)?;
+        for range in set.ranges() {
+            let (from, to) = (*range.start(), *range.end());
+            write_escaped(f, from, SPECIAL)?;
+            if to > from {
+                if to > from + 1 {
+                    f.write_str("-")?;
+                }
+                write_escaped(f, to, SPECIAL)?;
+            }
+        }
+        f.write_str("]")
+    }
+}
+
 impl std::fmt::Debug for ByteSet {
+    /// Writes `self` as a list of maximal runs, e.g.
+    /// `ByteSet[0x00-0x08, '\n', 'a'-'z']`. The alternate form additionally
+    /// writes the number of elements.
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
-        for word in self.bitmap {
-            f.write_str(&format!("{:#010b} ", word)).unwrap();
+        f.write_str("ByteSet")?;
+        write_runs(f, self)?;
+        if f.alternate() {
+            write!(f, " ({} bytes)", self.len())?;
         }
         Ok(())
     }
 }
 
+/// Writes `byte` as a printable ASCII character, escaped with a backslash if
+/// it is one of `special`, or otherwise as a `\xNN` escape sequence.
+pub(crate) fn write_escaped(f: &mut std::fmt::Formatter<'_>, byte: u8, special: &[u8]) -> Result<(), std::fmt::Error> {
+    if special.contains(&byte) {
+        write!(f, "\\{}", byte as char)
+    } else if byte.is_ascii_graphic() {
+        write!(f, "{}", byte as char)
+    } else {
+        write!(f, "\\x{byte:02x}")
+    }
+}
+
+/// Writes `set` as a bracketed list of maximal runs, e.g.
+/// `[0x00-0x08, '\n', 'a'-'z']`.
+pub(crate) fn write_runs(f: &mut std::fmt::Formatter<'_>, set: &ByteSet) -> Result<(), std::fmt::Error> {
+    f.write_str("[")?;
+    for (i, range) in set.ranges().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        let (from, to) = (*range.start(), *range.end());
+        write_debug(f, from)?;
+        if to > from {
+            f.write_str("-")?;
+            write_debug(f, to)?;
+        }
+    }
+    f.write_str("]")
+}
+
+/// Writes `byte` as a quoted char if it is printable ASCII or has a common
+/// escape sequence, or otherwise in hexadecimal.
+fn write_debug(f: &mut std::fmt::Formatter<'_>, byte: u8) -> Result<(), std::fmt::Error> {
+    match byte {
+        b'\t'  => f.write_str("'\\t'"),
+        b'\n'  => f.write_str("'\\n'"),
+        b'\r'  => f.write_str("'\\r'"),
+        b'\''  => f.write_str("'\\''"),
+        b'\\'  => f.write_str("'\\\\'"),
+        b' ' | b'!'..=b'~' => write!(f, "'{}'", byte as char),
+        _ => write!(f, "{byte:#04x}"),
+    }
+}
+
+/// Parses the next (possibly escaped) byte of a character class, returning it
+/// with its offset, or `None` at the end of the class.
+fn parse_class_byte<I>(chars: &mut Peekable<I>) -> Result<Option<(usize, u8)>, ClassParseError>
+where
+    I: Iterator<Item = (usize, char)>,
+{
+    let error = |pos, kind| Err(ClassParseError { pos, kind });
+
+    let Some((pos, c)) = chars.next() else {
+        return Ok(None)
+    };
+    let byte = match c {
+        '-' => return error(pos, ClassParseErrorKind::DanglingHyphen),
+        '\\' => match chars.next().map(|(_, c)| c) {
+            Some(c @ ('-' | '^' | '\\')) => c as u8,
+            Some('n') => b'\n',
+            Some('r') => b'\r',
+            Some('t') => b'\t',
+            Some('x') => {
+                let hi = chars.next().and_then(|(_, c)| c.to_digit(16));
+                let lo = chars.next().and_then(|(_, c)| c.to_digit(16));
+                match (hi, lo) {
+                    #[allow(clippy::cast_possible_truncation)] // non-truncating as hi, lo < 16
+                    (Some(hi), Some(lo)) => (16 * hi + lo) as u8,
+                    _ => return error(pos, ClassParseErrorKind::InvalidEscape),
+                }
+            },
+            _ => return error(pos, ClassParseErrorKind::InvalidEscape),
+        },
+        c if c.is_ascii() => c as u8,
+        c => return error(pos, ClassParseErrorKind::NonAscii(c)),
+    };
+    Ok(Some((pos, byte)))
+}
+
+struct Ranges<'a> {
+    bytes: Peekable<Bytes<'a>>,
+}
+
+impl Iterator for Ranges<'_> {
+    type Item = RangeInclusive<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let from = self.bytes.next()?;
+        let mut to = from;
+        // to < 255 whenever there is a next value, so to + 1 cannot overflow
+        while let Some(next) = self.bytes.next_if(|&next| next == to + 1) {
+            to = next;
+        }
+        Some(from..=to)
+    }
+}
+
 pub struct Bytes<'a> {
-    set: &'a ByteSet,
-    index: usize,
-    word: u8,
+    // remaining values, all of which lie in words front..=back
+    bitmap: [Word; WORDS],
+    front: usize,
+    back: usize,
+    len: usize,
+    set: PhantomData<&'a ByteSet>,
 }
 
 impl<'a> Bytes<'a> {
     const fn new(set: &'a ByteSet) -> Self {
-        if let Some((index, word)) = set.first() {
-            Self { set, index, word }
-        } else {
-            Self { set, index: set.bitmap.len(), word: 0 }
-        }
+        Self { bitmap: set.bitmap, front: 0, back: WORDS - 1, len: set.len() as usize, set: PhantomData }
     }
 }
 
@@ -297,37 +1178,87 @@ impl Iterator for Bytes<'_> {
     type Item = u8;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.word == 0 {
-            if self.index < 32 - 1 {
-                self.index += 1;
-                self.word = self.set.bitmap[self.index];
-                self.next()
-            } else {
-                None
-            }
-        } else {
-            let retval = decode(self.index, self.word);
-            self.word &= self.word - 1;
-            Some(retval)
+        if self.len == 0 {
+            return None
         }
+        while self.bitmap[self.front] == 0 {
+            self.front += 1;
+        }
+        let word = self.bitmap[self.front];
+        self.bitmap[self.front] &= word - 1;
+        self.len -= 1;
+        Some(decode(self.front, word))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+
+    fn last(mut self) -> Option<Self::Item> {
+        self.next_back()
     }
 }
+
+impl DoubleEndedIterator for Bytes<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None
+        }
+        while self.bitmap[self.back] == 0 {
+            self.back -= 1;
+        }
+        let value = decode_last(self.back, self.bitmap[self.back]);
+        self.bitmap[self.back] &= !encode(value).1;
+        self.len -= 1;
+        Some(value)
+    }
+}
+
+impl ExactSizeIterator for Bytes<'_> {}
  
-const fn encode(value: u8) -> (usize, u8) {
+/// Each word of the bitmap holds `Word::BITS` values.
+type Word = u64;
+const WORDS: usize = 256 / Word::BITS as usize;
+
+const fn encode(value: u8) -> (usize, Word) {
     let x = value as usize;
-    (x / 8, 1 << (x % 8))
+    (x / Word::BITS as usize, 1 << (x % Word::BITS as usize))
+}
+
+#[allow(clippy::cast_possible_truncation)]
+const fn decode(index: usize, word: Word) -> u8 {
+    let index = index as u8; // non-truncating as index < WORDS
+    let trailing = word.trailing_zeros() as u8; // non-truncating as trailing <= Word::BITS
+    Word::BITS as u8 * index + trailing
 }
 
+/// Like `decode`, but decodes the last rather than the first value in `word`.
 #[allow(clippy::cast_possible_truncation)]
-const fn decode(index: usize, word: u8) -> u8 {
-    let index = index as u8; // non-truncating as index < 32
-    let trailing = word.trailing_zeros() as u8; // non-truncating as trailing <= 8
-    8 * index + trailing
+const fn decode_last(index: usize, word: Word) -> u8 {
+    let index = index as u8; // non-truncating as index < WORDS
+    let leading = word.leading_zeros() as u8; // non-truncating as leading < Word::BITS
+    Word::BITS as u8 * index + (Word::BITS - 1) as u8 - leading
 }
 
 #[cfg(test)]
 mod tests {
     use super::ByteSet;
+    use rand_core::{RngCore, SeedableRng};
+    use rand_pcg::Pcg32;
+
+    fn random_set(rng: &mut Pcg32) -> ByteSet {
+        let mut set = ByteSet::empty();
+        for i in 0..32 {
+            // biased towards long runs
+            let byte = match rng.next_u32() % 4 {
+                0 => 0,
+                1 => u8::MAX,
+                _ => rng.next_u32().to_le_bytes()[0],
+            };
+            set.bitmap[i / 8] |= u64::from(byte) << (8 * (i % 8));
+        }
+        set
+    }
 
     #[test]
     fn contains() {
@@ -372,6 +1303,407 @@ mod tests {
         assert_eq!(union, ByteSet::range(10, 20).union(&ByteSet::range(60, 200)));
     }
 
+    #[test]
+    fn ranges() {
+        assert_eq!(ByteSet::empty().ranges().count(), 0);
+        assert_eq!(ByteSet::universe().ranges().collect::<Vec<_>>(), [0..=255]);
+        assert_eq!(ByteSet::point(255).ranges().collect::<Vec<_>>(), [255..=255]);
+        assert_eq!(ByteSet::point(0).complement().ranges().collect::<Vec<_>>(), [1..=255]);
+        assert_eq!(ByteSet::from_ranges(&[(5, 3)]), ByteSet::empty());
+        assert_eq!(ByteSet::from_ranges(&[(0, 10), (5, 20), (21, 21)]), ByteSet::range(0, 21));
+
+        let mut rng = Pcg32::seed_from_u64(1053);
+        for _ in 0..1000 {
+            let set = random_set(&mut rng);
+            let ranges: Vec<_> = set.ranges().map(|range| (*range.start(), *range.end())).collect();
+            assert_eq!(ByteSet::from_ranges(&ranges), set);
+            // maximal runs are separated by at least one value
+            assert!(ranges.windows(2).all(|w| w[0].1 + 1 < w[1].0));
+        }
+    }
+
+    #[test]
+    fn len() {
+        assert_eq!(ByteSet::empty().len(), 0);
+        assert_eq!(ByteSet::universe().len(), 256);
+        assert_eq!(ByteSet::range(10, 20).len(), 11);
+        assert_eq!(ByteSet::point(255).len(), 1);
+        assert!(!ByteSet::universe().intersection(&ByteSet::point(7).complement()).is_universe());
+
+        let mut rng = Pcg32::seed_from_u64(1054);
+        for _ in 0..1000 {
+            let (set1, set2) = (random_set(&mut rng), random_set(&mut rng));
+            let count = |f: &dyn Fn(u8) -> bool| (0..=255).filter(|&x| f(x)).count();
+            assert_eq!(set1.len() as usize, set1.bytes().count());
+            assert_eq!(set1.union(&set2).len() as usize, count(&|x| set1.contains(x) || set2.contains(x)));
+            assert_eq!(set1.intersection(&set2).len() as usize, count(&|x| set1.contains(x) && set2.contains(x)));
+            assert_eq!(set1.is_empty(), set1.bytes().next().is_none());
+            assert_eq!(set1.is_universe(), set1.len() == 256);
+            assert!(!set1.union(&ByteSet::range(0, 254)).is_universe() || set1.contains(255));
+        }
+    }
+
+    #[test]
+    fn difference() {
+        let mut rng = Pcg32::seed_from_u64(1055);
+        for _ in 0..1000 {
+            let (a, b) = (random_set(&mut rng), random_set(&mut rng));
+            assert_eq!(a.difference(&b).union(&a.intersection(&b)), a);
+            assert_eq!(a.difference(&b), a.intersection(&b.complement()));
+            assert_eq!(a.symmetric_difference(&b), a.difference(&b).union(&b.difference(&a)));
+            for x in 0..=255 {
+                assert_eq!(a.symmetric_difference(&b).contains(x), a.contains(x) != b.contains(x));
+            }
+
+            let mut c = a.clone();
+            c.difference_assign(&b);
+            assert_eq!(c, a.difference(&b));
+            c.symmetric_difference_assign(&a);
+            assert_eq!(c, a.intersection(&b));
+            c.union_assign(&b);
+            assert_eq!(c, b);
+            c.intersection_assign(&a);
+            assert_eq!(c, a.intersection(&b));
+        }
+        assert_eq!(ByteSet::universe().difference(&ByteSet::universe()), ByteSet::empty());
+        assert_eq!(ByteSet::empty().symmetric_difference(&ByteSet::universe()), ByteSet::universe());
+    }
+
+    #[test]
+    fn subset() {
+        let mut rng = Pcg32::seed_from_u64(1056);
+        let mut sets: Vec<_> = (0..200).map(|_| random_set(&mut rng)).collect();
+        sets.extend([ByteSet::empty(), ByteSet::universe(), ByteSet::range(0, 127), ByteSet::point(255)]);
+
+        for a in &sets {
+            for b in &sets {
+                assert_eq!(a.is_subset(b), a.bytes().all(|x| b.contains(x)));
+                assert_eq!(a.is_superset(b), b.bytes().all(|x| a.contains(x)));
+                assert_eq!(a.is_disjoint(b), a.bytes().all(|x| !b.contains(x)));
+                assert_eq!(a.overlaps(b), !a.is_disjoint(b));
+            }
+            assert!(a.is_subset(a) && a.is_superset(a));
+            assert!(ByteSet::empty().is_subset(a) && a.is_subset(&ByteSet::universe()));
+            assert!(a.is_disjoint(&a.complement()));
+        }
+    }
+
+    #[test]
+    fn from_iter() {
+        assert_eq!((b'a'..=b'z').collect::<ByteSet>(), ByteSet::range(b'a', b'z'));
+        assert_eq!((0..=255).collect::<ByteSet>(), ByteSet::universe());
+        assert_eq!(std::iter::empty::<u8>().collect::<ByteSet>(), ByteSet::empty());
+        let (from, to) = (9, 3);
+        assert_eq!(vec![b'0'..=b'9', b'a'..=b'f', from..=to].into_iter().collect::<ByteSet>(),
+                   ByteSet::range(b'0', b'9').union(&ByteSet::range(b'a', b'f')));
+
+        let mut set = ByteSet::point(0);
+        set.extend([255, 0, 128]);
+        assert_eq!(set.bytes().collect::<Vec<_>>(), [0, 128, 255]);
+
+        let mut rng = Pcg32::seed_from_u64(1057);
+        for _ in 0..100 {
+            let set = random_set(&mut rng);
+            assert_eq!(set.bytes().collect::<ByteSet>(), set);
+            assert_eq!(set.ranges().collect::<ByteSet>(), set);
+            let mut count = 0;
+            for x in &set {
+                assert!(set.contains(x));
+                count += 1;
+            }
+            assert_eq!(count, set.len());
+        }
+    }
+
+    #[test]
+    fn operators() {
+        let mut rng = Pcg32::seed_from_u64(1058);
+        for _ in 0..100 {
+            let (a, b) = (random_set(&mut rng), random_set(&mut rng));
+            assert_eq!(&a | &b, a.union(&b));
+            assert_eq!(&a & b.clone(), a.intersection(&b));
+            assert_eq!(a.clone() - &b, a.difference(&b));
+            assert_eq!(!a.clone(), a.complement());
+            assert_eq!(a.clone() - b.clone(), a.clone() & !&b);
+
+            let mut c = a.clone();
+            c |= &b;
+            assert_eq!(c, &a | &b);
+            c &= b.clone();
+            assert_eq!(c, b);
+            c -= &a;
+            assert_eq!(c, &b - &a);
+        }
+    }
+
+    #[test]
+    fn insert_remove() {
+        let mut set = ByteSet::empty();
+        for &x in &[0, 255] {
+            assert!(set.insert(x));
+            assert!(!set.insert(x));
+            assert!(set.contains(x));
+        }
+        assert_eq!(set, ByteSet::from_ranges(&[(0, 0), (255, 255)]));
+
+        for &x in &[0, 255] {
+            assert!(set.remove(x));
+            assert!(!set.remove(x));
+            assert!(!set.contains(x));
+        }
+        assert!(set.is_empty());
+
+        assert!(set.toggle(0));
+        assert!(set.toggle(255));
+        assert!(!set.toggle(0));
+        assert_eq!(set, ByteSet::point(255));
+
+        set.insert_range(0, 255);
+        assert!(set.is_universe());
+        let (from, to) = (1, 0);
+        set = ByteSet::empty();
+        set.insert_range(from, to);
+        assert!(set.is_empty());
+
+        let mut rng = Pcg32::seed_from_u64(1059);
+        for _ in 0..100 {
+            let (a, b) = (random_set(&mut rng), random_set(&mut rng));
+            let mut c = a.clone();
+            for x in b.bytes() {
+                assert_eq!(c.insert(x), !a.contains(x));
+            }
+            assert_eq!(c, a.union(&b));
+            for x in b.bytes() {
+                assert!(c.remove(x));
+            }
+            assert_eq!(c, a.difference(&b));
+            for x in b.bytes() {
+                c.toggle(x);
+            }
+            assert_eq!(c, a.union(&b));
+        }
+    }
+
+    #[test]
+    fn ascii() {
+        type Predicate = fn(&u8) -> bool;
+        let cases: [(ByteSet, Predicate); 10] = [
+            (ByteSet::ASCII_DIGIT,      u8::is_ascii_digit),
+            (ByteSet::ASCII_UPPER,      u8::is_ascii_uppercase),
+            (ByteSet::ASCII_LOWER,      u8::is_ascii_lowercase),
+            (ByteSet::ASCII_ALPHA,      u8::is_ascii_alphabetic),
+            (ByteSet::ASCII_ALNUM,      u8::is_ascii_alphanumeric),
+            (ByteSet::ASCII_WHITESPACE, u8::is_ascii_whitespace),
+            (ByteSet::ASCII_HEXDIGIT,   u8::is_ascii_hexdigit),
+            (ByteSet::ASCII_PUNCT,      u8::is_ascii_punctuation),
+            (ByteSet::ASCII_CONTROL,    u8::is_ascii_control),
+            (ByteSet::WORD,             |x| x.is_ascii_alphanumeric() || *x == b'_'),
+        ];
+        for (set, predicate) in &cases {
+            for x in 0..=255 {
+                assert_eq!(set.contains(x), predicate(&x), "{set:?} wrt {x}");
+            }
+        }
+    }
+
+    #[test]
+    fn from_fn() {
+        assert_eq!(ByteSet::from_fn(|x| x.is_ascii_alphanumeric()), ByteSet::ASCII_ALNUM);
+        assert_eq!(ByteSet::from_fn(|_| false), ByteSet::empty());
+        assert_eq!(ByteSet::from_fn(|_| true), ByteSet::universe());
+
+        let mut rng = Pcg32::seed_from_u64(1066);
+        for _ in 0..100 {
+            let (a, b) = (random_set(&mut rng), random_set(&mut rng));
+            assert_eq!(ByteSet::from_fn(|x| a.contains(x)), a);
+            let mut c = a.clone();
+            c.retain(|x| b.contains(x));
+            assert_eq!(c, a.intersection(&b));
+        }
+    }
+
+    #[test]
+    fn nth_rank() {
+        let mut rng = Pcg32::seed_from_u64(1067);
+        let mut sets: Vec<_> = (0..100).map(|_| random_set(&mut rng)).collect();
+        sets.extend(vec![ByteSet::empty(), ByteSet::universe(), ByteSet::point(0), ByteSet::point(255)]);
+
+        for set in &sets {
+            let bytes: Vec<u8> = set.bytes().collect();
+            for k in 0..=256 {
+                assert_eq!(set.nth(k), bytes.get(k as usize).copied(), "{set:?} wrt {k}");
+            }
+            for x in 0..=255 {
+                let rank = bytes.iter().filter(|&&y| y < x).count();
+                assert_eq!(set.rank(x) as usize, rank, "{set:?} wrt {x}");
+            }
+        }
+    }
+
+    #[test]
+    fn pop_smallest() {
+        let mut set = ByteSet::from_ranges(&[(0, 1), (63, 64), (127, 128), (254, 255)]);
+        let mut popped = Vec::new();
+        while let Some(x) = set.pop_smallest() {
+            popped.push(x);
+        }
+        assert_eq!(popped, [0, 1, 63, 64, 127, 128, 254, 255]);
+        assert!(set.is_empty());
+        assert_eq!(set.pop_smallest(), None);
+
+        let mut rng = Pcg32::seed_from_u64(1068);
+        for _ in 0..100 {
+            let mut set = random_set(&mut rng);
+            let expected: Vec<u8> = set.bytes().collect();
+            let popped: Vec<u8> = std::iter::from_fn(|| set.pop_smallest()).collect();
+            assert_eq!(popped, expected);
+            assert!(set.is_empty());
+        }
+    }
+
+    #[test]
+    fn from_ranges() {
+        type Pairs = &'static [(u8, u8)];
+
+        let (from, to) = (b'z', b'a');
+        assert!(ByteSet::range(from, to).is_empty());
+        assert!(ByteSet::range(200, 10).is_empty());
+        assert!(ByteSet::from_ranges(&[(200, 10), (from, to)]).is_empty());
+        assert!(ByteSet::except_ranges(&[(200, 10)]).is_universe());
+        assert!(ByteSet::except_ranges(&[(0, 255)]).is_empty());
+
+        let cases: [(Pairs, Pairs); 5] = [
+            (&[(b'a', b'f'), (b'g', b'z')], &[(b'a', b'z')]),
+            (&[(b'a', b'm'), (b'f', b'z')], &[(b'a', b'z')]),
+            (&[(b'f', b'z'), (b'a', b'm'), (b'0', b'9')], &[(b'0', b'9'), (b'a', b'z')]),
+            (&[(0, 63), (64, 127), (128, 128)], &[(0, 128)]),
+            (&[(10, 20), (12, 14), (20, 20), (22, 30)], &[(10, 20), (22, 30)]),
+        ];
+        for (ranges, expected) in &cases {
+            let set = ByteSet::from_ranges(ranges);
+            let actual: Vec<_> = set.ranges().map(|range| (*range.start(), *range.end())).collect();
+            assert_eq!(&actual, expected, "{ranges:?}");
+            assert_eq!(ByteSet::except_ranges(ranges), set.complement());
+        }
+    }
+
+    #[test]
+    fn utf8() {
+        let mut leads = vec![ByteSet::empty(); 5];
+        let mut seconds = vec![ByteSet::empty(); 256];
+        for c in (0..=0x10_ffff).filter_map(std::char::from_u32) {
+            let mut buf = [0; 4];
+            let bytes = c.encode_utf8(&mut buf).as_bytes();
+            leads[bytes.len()].insert(bytes[0]);
+            if let Some(&second) = bytes.get(1) {
+                seconds[bytes[0] as usize].insert(second);
+            }
+        }
+
+        for len in 0..=8 {
+            assert_eq!(ByteSet::utf8_lead(len), leads.get(len as usize).cloned().unwrap_or_else(ByteSet::empty), "{len}");
+        }
+        for lead in 0..=255 {
+            assert_eq!(ByteSet::utf8_second(lead), seconds[lead as usize], "{lead:#04x}");
+            assert!(ByteSet::utf8_second(lead).is_subset(&ByteSet::UTF8_CONT));
+        }
+    }
+
+    #[test]
+    fn is_single() {
+        for x in 0..=255 {
+            assert_eq!(ByteSet::point(x).is_single(), Some(x));
+            assert_eq!(ByteSet::point(x).is_range(), Some((x, x)));
+        }
+        for &(a, b) in &[(0, 1), (0, 255), (63, 64), (10, 200), (127, 128), (0, 128)] {
+            let pair = ByteSet::from_ranges(&[(a, a), (b, b)]);
+            assert_eq!(pair.is_single(), None);
+            assert_eq!(pair.is_range(), if b == a + 1 { Some((a, b)) } else { None });
+            assert_eq!(ByteSet::range(a, b).is_single(), None);
+            assert_eq!(ByteSet::range(a, b).is_range(), Some((a, b)));
+        }
+        assert_eq!(ByteSet::empty().is_single(), None);
+        assert_eq!(ByteSet::empty().is_range(), None);
+        assert_eq!(ByteSet::universe().is_range(), Some((0, 255)));
+        assert_eq!(ByteSet::from_ranges(&[(60, 70), (72, 80)]).is_range(), None);
+
+        let mut rng = Pcg32::seed_from_u64(1072);
+        for _ in 0..100 {
+            let set = random_set(&mut rng);
+            let ranges: Vec<_> = set.ranges().map(|range| (*range.start(), *range.end())).collect();
+            assert_eq!(set.is_range(), if ranges.len() == 1 { Some(ranges[0]) } else { None });
+        }
+    }
+
+    #[test]
+    fn parse() {
+        use super::ClassParseErrorKind;
+
+        let cases = [
+            ("", ByteSet::empty()),
+            ("^", ByteSet::universe()),
+            ("a", ByteSet::point(b'a')),
+            ("a-zA-Z0-9_", ByteSet::from_ranges(&[(b'a', b'z'), (b'A', b'Z'), (b'0', b'9'), (b'_', b'_')])),
+            ("^a-z", ByteSet::range(b'a', b'z').complement()),
+            ("a^", ByteSet::from_ranges(&[(b'a', b'a'), (b'^', b'^')])),
+            (r"\^", ByteSet::point(b'^')),
+            (r"\-\\", ByteSet::from_ranges(&[(b'-', b'-'), (b'\\', b'\\')])),
+            (r" \t\n\r", ByteSet::from_ranges(&[(b' ', b' '), (b'\t', b'\n'), (b'\r', b'\r')])),
+            (r"\x00-\x1F\x7f", ByteSet::from_ranges(&[(0x00, 0x1f), (0x7f, 0x7f)])),
+            (r"\x80-\xff", ByteSet::range(0x80, 0xff)),
+            (r"!-\-", ByteSet::range(b'!', b'-')),
+            ("a-a", ByteSet::point(b'a')),
+        ];
+        for (class, expected) in &cases {
+            assert_eq!(ByteSet::parse(class).as_ref(), Ok(expected), "{class:?}");
+            assert_eq!(class.parse::<ByteSet>().as_ref(), Ok(expected), "{class:?}");
+        }
+
+        let errors = [
+            ("-", 0, ClassParseErrorKind::DanglingHyphen),
+            ("a-", 1, ClassParseErrorKind::DanglingHyphen),
+            ("a--b", 2, ClassParseErrorKind::DanglingHyphen),
+            ("^-a", 1, ClassParseErrorKind::DanglingHyphen),
+            ("z-a", 0, ClassParseErrorKind::ReversedRange),
+            (r"\", 0, ClassParseErrorKind::InvalidEscape),
+            (r"ab\d", 2, ClassParseErrorKind::InvalidEscape),
+            (r"\x4", 0, ClassParseErrorKind::InvalidEscape),
+            (r"\xg0", 0, ClassParseErrorKind::InvalidEscape),
+            ("aé", 1, ClassParseErrorKind::NonAscii('é')),
+            ("a-é", 2, ClassParseErrorKind::NonAscii('é')),
+        ];
+        for (class, pos, kind) in &errors {
+            let error = ByteSet::parse(class).unwrap_err();
+            assert_eq!((error.pos(), error.kind()), (*pos, kind), "{class:?}");
+        }
+        assert_eq!(ByteSet::parse("aé").unwrap_err().to_string(), "expected an ASCII char, found 'é' at 1");
+    }
+
+    #[test]
+    fn display() {
+        let set = ByteSet::range(b'a', b'z').union(&ByteSet::point(b'_')).union(&ByteSet::range(b'0', b'9'));
+        assert_eq!(format!("{set}"), "[0-9_a-z]");
+        assert_eq!(format!("{}", ByteSet::range(0x00, 0x08).union(&ByteSet::range(0x0e, 0x1f))), "[\\x00-\\x08\\x0e-\\x1f]");
+        assert_eq!(format!("{}", ByteSet::range(b'x', b'y').union(&ByteSet::point(b'-'))), "[\\-xy]");
+        assert_eq!(format!("{}", ByteSet::point(b' ')), "[\\x20]");
+        assert_eq!(format!("{}", ByteSet::point(b'\n').complement()), "[^\\x0a]");
+        assert_eq!(format!("{}", ByteSet::empty()), "[]");
+        assert_eq!(format!("{}", ByteSet::universe()), "[^]");
+    }
+
+    #[test]
+    fn debug() {
+        assert_eq!(format!("{:?}", ByteSet::empty()), "ByteSet[]");
+        assert_eq!(format!("{:?}", ByteSet::universe()), "ByteSet[0x00-0xff]");
+        assert_eq!(format!("{:?}", ByteSet::point(b'a')), "ByteSet['a']");
+        assert_eq!(format!("{:?}", ByteSet::point(0x7f)), "ByteSet[0x7f]");
+
+        let set = ByteSet::from_ranges(&[(0x00, 0x08), (b'\n', b'\n'), (b' ', b' '), (b'\'', b'\''), (b'\\', b'\\'), (b'a', b'z'), (0x80, 0xff)]);
+        assert_eq!(format!("{set:?}"), r"ByteSet[0x00-0x08, '\n', ' ', '\'', '\\', 'a'-'z', 0x80-0xff]");
+        assert_eq!(format!("{set:#?}"), r"ByteSet[0x00-0x08, '\n', ' ', '\'', '\\', 'a'-'z', 0x80-0xff] (167 bytes)");
+        assert_eq!(format!("{:#?}", ByteSet::empty()), "ByteSet[] (0 bytes)");
+    }
+
     #[test]
     fn bytes() {
         let set = ByteSet::range(1, 3).union(&ByteSet::range(5, 7));
@@ -384,4 +1716,40 @@ mod tests {
         assert_eq!(iter.next(), Some(7_u8));
         assert_eq!(iter.next(), None);
     }
+
+    #[test]
+    fn bytes_double_ended() {
+        let mut rng = Pcg32::seed_from_u64(1063);
+        let mut sets: Vec<_> = (0..100).map(|_| random_set(&mut rng)).collect();
+        sets.extend(vec![ByteSet::empty(), ByteSet::universe(), ByteSet::point(0), ByteSet::point(255)]);
+        sets.push(ByteSet::from_ranges(&[(0, 3), (b'0', b'9'), (b'a', b'z'), (250, 255)]));
+
+        for set in &sets {
+            let expected: Vec<u8> = (0..=255).filter(|&x| set.contains(x)).collect();
+            assert_eq!(set.bytes().len(), expected.len());
+            assert_eq!(set.bytes().collect::<Vec<_>>(), expected);
+            assert_eq!(set.bytes().rev().collect::<Vec<_>>(), expected.iter().rev().copied().collect::<Vec<_>>());
+            assert_eq!(set.bytes().last(), expected.last().copied());
+            assert_eq!(set.largest(), expected.last().copied());
+            assert_eq!(set.smallest(), expected.first().copied());
+
+            // alternate ends, checking the remaining length
+            let (mut iter, mut lo, mut hi) = (set.bytes(), 0, expected.len());
+            for i in 0.. {
+                assert_eq!(iter.size_hint(), (hi - lo, Some(hi - lo)));
+                let next = if i % 3 == 0 { iter.next_back() } else { iter.next() };
+                if lo == hi {
+                    assert_eq!(next, None);
+                    assert_eq!(iter.next(), None);
+                    break;
+                } else if i % 3 == 0 {
+                    hi -= 1;
+                    assert_eq!(next, Some(expected[hi]));
+                } else {
+                    assert_eq!(next, Some(expected[lo]));
+                    lo += 1;
+                }
+            }
+        }
+    }
 }
\ No newline at end of file
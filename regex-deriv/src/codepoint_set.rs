@@ -0,0 +1,333 @@
+use std::ops::RangeInclusive;
+use super::{ByteSet, RegEx};
+
+/// Represents a set of unicode scalar values (i.e. `char`s) as a sorted list
+/// of disjoint, non-adjacent ranges.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct CodepointSet {
+    ranges: Vec<RangeInclusive<char>>,
+}
+
+impl CodepointSet {
+    /// Returns the empty set {}.
+    #[must_use]
+    pub fn empty() -> Self {
+        Self { ranges: Vec::new() }
+    }
+
+    /// Returns the set of all `char`s.
+    #[must_use]
+    pub fn universe() -> Self {
+        Self { ranges: vec!['\0'..=char::MAX] }
+    }
+
+    /// Returns the set {`value`}.
+    #[must_use]
+    pub fn point(value: char) -> Self {
+        Self { ranges: vec![value..=value] }
+    }
+
+    /// Returns the set {`from`, ..., `to`}, which is empty if `from > to`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::CodepointSet;
+    /// let greek = CodepointSet::range('\u{370}', '\u{3ff}');
+    /// assert!(greek.contains('λ'));
+    /// assert!(!greek.contains('a'));
+    /// ```
+    #[must_use]
+    pub fn range(from: char, to: char) -> Self {
+        if from > to { Self::empty() } else { Self { ranges: vec![from..=to] } }
+    }
+
+    /// Returns the union of the sets {`from`, ..., `to`} for each `from..=to`
+    /// in `ranges`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::CodepointSet;
+    /// let set = CodepointSet::from_ranges(vec!['a'..='m', 'n'..='z', '0'..='9']);
+    /// assert_eq!(set.ranges(), ['0'..='9', 'a'..='z']);
+    /// ```
+    #[must_use]
+    pub fn from_ranges<I: IntoIterator<Item = RangeInclusive<char>>>(ranges: I) -> Self {
+        let mut ranges: Vec<_> = ranges.into_iter().filter(|range| !range.is_empty()).collect();
+        ranges.sort_by_key(|range| *range.start());
+
+        let mut coalesced: Vec<RangeInclusive<char>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match coalesced.last_mut() {
+                Some(last) if successor(*last.end()).is_none_or(|next| *range.start() <= next) => {
+                    if range.end() > last.end() {
+                        *last = *last.start()..=*range.end();
+                    }
+                },
+                _ => coalesced.push(range),
+            }
+        }
+        Self { ranges: coalesced }
+    }
+
+    /// Returns the maximal ranges of `self`, in ascending order.
+    #[must_use]
+    pub fn ranges(&self) -> &[RangeInclusive<char>] {
+        &self.ranges
+    }
+
+    /// Returns `true` if `self` is the empty set.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Returns `true` if `value` is contained in `self`.
+    #[must_use]
+    pub fn contains(&self, value: char) -> bool {
+        // index of the first range ending at or after value
+        let i = self.ranges.partition_point(|range| *range.end() < value);
+        self.ranges.get(i).is_some_and(|range| range.contains(&value))
+    }
+
+    /// Returns the set of `char`s not in `self`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::CodepointSet;
+    /// let set = CodepointSet::range('\0', '\x7f').complement();
+    /// assert!(set.contains('é') && !set.contains('a'));
+    /// assert_eq!(set.complement(), CodepointSet::range('\0', '\x7f'));
+    /// ```
+    #[must_use]
+    pub fn complement(&self) -> Self {
+        let mut ranges = Vec::with_capacity(self.ranges.len() + 1);
+        let mut next = Some('\0');
+        for range in &self.ranges {
+            if let (Some(from), Some(to)) = (next, predecessor(*range.start())) {
+                if from <= to {
+                    ranges.push(from..=to);
+                }
+            }
+            next = successor(*range.end());
+        }
+        if let Some(from) = next {
+            ranges.push(from..=char::MAX);
+        }
+        Self { ranges }
+    }
+
+    /// Returns the set of `char`s in both `self` and `other`.
+    #[must_use]
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while let (Some(a), Some(b)) = (self.ranges.get(i), other.ranges.get(j)) {
+            let from = *a.start().max(b.start());
+            let to = *a.end().min(b.end());
+            if from <= to {
+                ranges.push(from..=to);
+            }
+            if a.end() < b.end() { i += 1; } else { j += 1; }
+        }
+        Self { ranges }
+    }
+
+    /// Returns the set of `char`s in `self` or `other`.
+    #[must_use]
+    pub fn union(&self, other: &Self) -> Self {
+        Self::from_ranges(self.ranges.iter().chain(&other.ranges).cloned())
+    }
+
+    /// Returns the set of `char`s in `self` but not in `other`.
+    #[must_use]
+    pub fn difference(&self, other: &Self) -> Self {
+        self.intersection(&other.complement())
+    }
+
+    /// Returns a `RegEx` recognizing the UTF-8 encodings of the `char`s in
+    /// `self`. Invalid UTF-8 (such as overlong encodings or encoded
+    /// surrogates) is never recognized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::CodepointSet;
+    /// let regex = CodepointSet::range('α', 'ω').to_regex();
+    /// assert!(regex.is_fullmatch("λ"));
+    /// assert!(!regex.is_fullmatch("a"));
+    /// ```
+    #[must_use]
+    pub fn to_regex(&self) -> RegEx {
+        let mut sequences = Vec::new();
+        for range in &self.ranges {
+            utf8_sequences(u32::from(*range.start()), u32::from(*range.end()), &mut sequences);
+        }
+        sequences.iter().fold(RegEx::none(), |acc, sequence| {
+            acc.or(&sequence.iter().fold(RegEx::empty(), |r, &(from, to)| {
+                r.then(&RegEx::set(ByteSet::range(from, to)))
+            }))
+        })
+    }
+}
+
+// =================
+// === INTERNALS ===
+// =================
+
+fn successor(c: char) -> Option<char> {
+    match c {
+        '\u{d7ff}' => Some('\u{e000}'),
+        c => std::char::from_u32(u32::from(c) + 1),
+    }
+}
+
+fn predecessor(c: char) -> Option<char> {
+    match c {
+        '\0' => None,
+        '\u{e000}' => Some('\u{d7ff}'),
+        c => std::char::from_u32(u32::from(c) - 1),
+    }
+}
+
+/// Appends to `sequences` the sequences of byte ranges that together
+/// recognize exactly the UTF-8 encodings of the `char`s in `from..=to`. The
+/// range is split until each part encodes to the same number of bytes, and
+/// differs only in a suffix of bytes that each span their full range of
+/// continuation bytes, so it is the cross product of its per-byte ranges.
+fn utf8_sequences(from: u32, to: u32, sequences: &mut Vec<Vec<(u8, u8)>>) {
+    let mut stack = vec![(from, to)];
+    'split: while let Some((from, to)) = stack.pop() {
+        // never span the surrogates, nor a change of encoded length
+        for &(max, min) in &[(0xd7ff, 0xe000), (0x7f, 0x80), (0x7ff, 0x800), (0xffff, 0x1_0000)] {
+            if from <= max && min <= to {
+                stack.push((min, to));
+                stack.push((from, max));
+                continue 'split;
+            }
+        }
+
+        for i in 1..4 {
+            let mask = (1 << (6 * i)) - 1;
+            if from & !mask != to & !mask {
+                if from & mask != 0 {
+                    stack.push(((from | mask) + 1, to));
+                    stack.push((from, from | mask));
+                    continue 'split;
+                }
+                if to & mask != mask {
+                    stack.push((to & !mask, to));
+                    stack.push((from, (to & !mask) - 1));
+                    continue 'split;
+                }
+            }
+        }
+
+        let (mut a, mut b) = ([0; 4], [0; 4]);
+        let a = std::char::from_u32(from).unwrap().encode_utf8(&mut a).as_bytes();
+        let b = std::char::from_u32(to).unwrap().encode_utf8(&mut b).as_bytes();
+        sequences.push(a.iter().copied().zip(b.iter().copied()).collect());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CodepointSet;
+    use crate::DFA;
+    use rand_core::{RngCore, SeedableRng};
+    use rand_pcg::Pcg32;
+
+    fn random_set(rng: &mut Pcg32) -> CodepointSet {
+        let random_char = |rng: &mut Pcg32| loop {
+            // biased towards the shorter encodings
+            let max = [0x80, 0x800, 0x1_0000, 0x11_0000][rng.next_u32() as usize % 4];
+            if let Some(c) = std::char::from_u32(rng.next_u32() % max) {
+                break c;
+            }
+        };
+        CodepointSet::from_ranges((0..rng.next_u32() % 5).map(|_| {
+            let (a, b) = (random_char(rng), random_char(rng));
+            a.min(b)..=a.max(b)
+        }))
+    }
+
+    /// Returns `true` iff `bytes` is the UTF-8 encoding of a single char in
+    /// `set`.
+    fn encodes_one_of(bytes: &[u8], set: &CodepointSet) -> bool {
+        let mut chars = std::str::from_utf8(bytes).ok().into_iter().flat_map(str::chars);
+        matches!((chars.next(), chars.next()), (Some(c), None) if set.contains(c))
+    }
+
+    #[test]
+    fn from_ranges() {
+        let set = CodepointSet::from_ranges(vec!['d'..='f', 'a'..='c', 'x'..='z', 'y'..='y', 'z'..='a']);
+        assert_eq!(set.ranges(), ['a'..='f', 'x'..='z']);
+
+        // no chars lie between the ranges on either side of the surrogates
+        let set = CodepointSet::from_ranges(vec!['\u{d000}'..='\u{d7ff}', '\u{e000}'..='\u{efff}']);
+        assert_eq!(set.ranges(), ['\u{d000}'..='\u{efff}']);
+        assert_eq!(CodepointSet::range('\u{e000}', char::MAX).complement(), CodepointSet::range('\0', '\u{d7ff}'));
+    }
+
+    #[test]
+    fn set_operations() {
+        let mut rng = Pcg32::seed_from_u64(1070);
+        for _ in 0..200 {
+            let (a, b) = (random_set(&mut rng), random_set(&mut rng));
+            let (union, intersection, difference) = (a.union(&b), a.intersection(&b), a.difference(&b));
+            let complement = a.complement();
+            assert_eq!(complement.complement(), a);
+
+            let interesting = a.ranges().iter().chain(b.ranges())
+                .flat_map(|range| vec![*range.start(), *range.end()])
+                .flat_map(|c| vec![super::predecessor(c), Some(c), super::successor(c)])
+                .flatten();
+            for c in interesting {
+                assert_eq!(union.contains(c), a.contains(c) || b.contains(c));
+                assert_eq!(intersection.contains(c), a.contains(c) && b.contains(c));
+                assert_eq!(difference.contains(c), a.contains(c) && !b.contains(c));
+                assert_eq!(complement.contains(c), !a.contains(c));
+            }
+        }
+    }
+
+    #[test]
+    fn to_regex() {
+        let non_ascii = CodepointSet::range('\0', '\x7f').complement();
+        let regex = non_ascii.to_regex();
+        assert!(!regex.is_fullmatch("a"));
+        assert!(regex.is_fullmatch("é"));
+        assert!(regex.is_fullmatch("𐍈"));
+        assert!(!regex.is_fullmatch("é𐍈"));
+
+        let dfa = DFA::from(&regex);
+        for a in 0..=255 {
+            assert_eq!(dfa.matches_iter(vec![a]), encodes_one_of(&[a], &non_ascii));
+            for b in 0..=255 {
+                assert_eq!(dfa.matches_iter(vec![a, b]), encodes_one_of(&[a, b], &non_ascii), "{:?}", [a, b]);
+            }
+        }
+        // overlong encodings and surrogates
+        for bytes in &[&[0xe0, 0x80, 0x80][..], &[0xf0, 0x80, 0x80, 0x80], &[0xed, 0xa0, 0x80], &[0xf4, 0x90, 0x80, 0x80]] {
+            assert!(!dfa.matches_iter(bytes.iter().copied()), "{:?}", bytes);
+        }
+
+        let mut rng = Pcg32::seed_from_u64(1070);
+        for _ in 0..20 {
+            let set = random_set(&mut rng);
+            let dfa = DFA::from(&set.to_regex());
+            for _ in 0..2_000 {
+                // mostly well-formed encodings, with an occasional corrupt byte
+                let c = std::char::from_u32(rng.next_u32() % 0x11_0000).unwrap_or('\u{fffd}');
+                let mut bytes = c.to_string().into_bytes();
+                if rng.next_u32() % 4 == 0 {
+                    let i = rng.next_u32() as usize % bytes.len();
+                    bytes[i] = rng.next_u32().to_le_bytes()[0];
+                }
+                assert_eq!(dfa.matches_iter(bytes.iter().copied()), encodes_one_of(&bytes, &set), "{set:?} wrt {bytes:?}");
+            }
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use std::convert::{TryFrom, TryInto};
+use std::fmt;
+use std::io::{self, Write};
+use super::DFA;
+use crate::table::LexTable;
+
+/// Identifies the binary table format.
+const MAGIC: &[u8; 4] = b"RDFA";
+
+/// Incremented whenever the binary table format changes.
+const FORMAT_VERSION: u32 = 1;
+
+/// Magic, version, state count and class count.
+const HEADER_LEN: usize = 16;
+
+/// Encodes a missing class in the accept-class array.
+const NO_CLASS: u32 = u32::MAX;
+
+impl DFA {
+    /// Writes `self` to `writer` as a dense table, which can be used without
+    /// deserialization via `DfaRef::from_bytes`. All values are little-endian
+    /// `u32`s, and the layout is:
+    ///
+    /// - a header of the magic bytes `RDFA`, the format version (currently
+    ///   1), the number of states `n`, and the number of classes `c`;
+    /// - `256 * n` transitions, where the transition from state `i` on byte
+    ///   `b` is at index `256 * i + b`;
+    /// - `n` accept classes, where `u32::MAX` marks a non-accepting state.
+    ///
+    /// As in the `DFA`, state 0 is the sink and state 1 the start.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error encountered whilst writing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self` has `u32::MAX` or more states or classes.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let n = self.states.len();
+        let c = self.class_count();
+        let word = |x: usize| u32::try_from(x).ok().filter(|&x| x != NO_CLASS).expect("DFA too large").to_le_bytes();
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        writer.write_all(&word(n))?;
+        writer.write_all(&word(c))?;
+        for id in 0..n {
+            for symbol in 0..=255 {
+                writer.write_all(&word(self.step(id, symbol)))?;
+            }
+        }
+        for state in &self.states {
+            writer.write_all(&state.class.map_or(NO_CLASS.to_le_bytes(), word))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the bytes written by `write_to`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self` has `u32::MAX` or more states or classes.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.write_to(&mut bytes).unwrap();
+        bytes
+    }
+}
+
+/// A DFA borrowed from bytes in the format written by `DFA::write_to`, e.g.
+/// embedded with `include_bytes!`. Transitions are read straight out of the
+/// bytes, which need not be aligned.
+///
+/// # Examples
+///
+/// ```
+/// # use regex_deriv::{RegEx, ByteSet, DFA, DfaRef, LexTable};
+/// let dfa = DFA::from(&RegEx::set(ByteSet::range(b'a', b'z')).plus()).minimize();
+/// let bytes = dfa.to_bytes();
+/// let table = DfaRef::from_bytes(&bytes).unwrap();
+/// let state = table.step(table.start_state(), b'q');
+/// assert_eq!(table.class(state), Some(0));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct DfaRef<'a> {
+    transitions: &'a [u8],
+    classes: &'a [u8],
+    class_count: usize,
+}
+
+impl<'a> DfaRef<'a> {
+    /// Validates `bytes` and borrows them as a DFA.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `FormatError` if `bytes` are not a well-formed table: if the
+    /// header is wrong, if the length disagrees with the header, if a
+    /// transition or class is out of range, or if state 0 is not a
+    /// non-accepting sink.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, FormatError> {
+        if bytes.len() < HEADER_LEN || &bytes[..4] != MAGIC {
+            return Err(FormatError::BadMagic)
+        }
+        let version = read(bytes, 1);
+        if version != FORMAT_VERSION {
+            return Err(FormatError::UnsupportedVersion(version))
+        }
+        let (n, c) = (read(bytes, 2) as usize, read(bytes, 3) as usize);
+        let expected = n.checked_mul(257 * 4).and_then(|len| len.checked_add(HEADER_LEN));
+        if n < 2 || expected != Some(bytes.len()) {
+            return Err(FormatError::BadLength)
+        }
+
+        let (transitions, classes) = bytes[HEADER_LEN..].split_at(256 * n * 4);
+        let table = Self { transitions, classes, class_count: c };
+        for id in 0..n {
+            for symbol in 0..=255 {
+                let dest = read(transitions, 256 * id + symbol as usize) as usize;
+                if dest >= n || (id == 0 && dest != 0) {
+                    return Err(FormatError::BadTransition { state: id, symbol })
+                }
+            }
+            match read(classes, id) {
+                NO_CLASS => (),
+                class if id == 0 || class as usize >= c => return Err(FormatError::BadClass { state: id }),
+                _ => (),
+            }
+        }
+        Ok(table)
+    }
+
+    /// Returns the number of states, including the sink.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.classes.len() / 4
+    }
+
+    /// Always `false`, as a table has at least a sink and start state.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
+
+impl LexTable for DfaRef<'_> {
+    fn start_state(&self) -> usize {
+        DFA::START
+    }
+
+    fn step(&self, state: usize, symbol: u8) -> usize {
+        read(self.transitions, 256 * state + symbol as usize) as usize
+    }
+
+    fn class(&self, state: usize) -> Option<usize> {
+        match read(self.classes, state) {
+            NO_CLASS => None,
+            class => Some(class as usize),
+        }
+    }
+
+    fn sink(&self) -> usize {
+        0
+    }
+
+    fn class_count(&self) -> usize {
+        self.class_count
+    }
+}
+
+/// Error produced when bytes are not a well-formed table.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum FormatError {
+    /// The bytes do not begin with the magic bytes `RDFA`.
+    BadMagic,
+    /// The format version is not supported.
+    UnsupportedVersion(u32),
+    /// The length of the bytes disagrees with the header, or there are fewer
+    /// than 2 states.
+    BadLength,
+    /// A transition leads out of range, or out of the sink.
+    BadTransition {
+        /// The source of the transition.
+        state: usize,
+        /// The byte labelling the transition.
+        symbol: u8,
+    },
+    /// A class is out of range, or the sink is accepting.
+    BadClass {
+        /// The state with the class.
+        state: usize,
+    },
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::BadMagic                         => write!(f, "not a DFA table"),
+            FormatError::UnsupportedVersion(version)      => write!(f, "unsupported DFA table version {version}"),
+            FormatError::BadLength                        => write!(f, "DFA table length disagrees with its header"),
+            FormatError::BadTransition { state, symbol }  => write!(f, "transition from state {state} on {symbol} is invalid"),
+            FormatError::BadClass { state }               => write!(f, "class of state {state} is invalid"),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+// =================
+// === INTERNALS ===
+// =================
+
+/// Returns the length in bytes of the table of a DFA with `states` states.
+pub(crate) fn table_len(states: usize) -> usize {
+    HEADER_LEN + 257 * 4 * states
+}
+
+/// Reads the `index`th little-endian `u32` of `bytes`.
+fn read(bytes: &[u8], index: usize) -> u32 {
+    u32::from_le_bytes(bytes[4 * index..4 * index + 4].try_into().unwrap())
+}
@@ -0,0 +1,110 @@
+use std::convert::TryFrom;
+use super::DFA;
+use crate::table::{heap_size, LexTable};
+
+/// A DFA with its transitions in one flat table, as returned by
+/// `DFA::to_dense`, where the transition from state `i` on byte `b` is at
+/// index `256 * i + b`. Each step is then a single lookup, rather than a
+/// search of the state's transitions, which makes it the fast path for
+/// matching. States are numbered as in the `DFA`.
+///
+/// # Examples
+///
+/// ```
+/// # use regex_deriv::{RegEx, ByteSet, DFA, Scan};
+/// let word = RegEx::set(ByteSet::range(b'a', b'z')).plus();
+/// let space = RegEx::set(ByteSet::point(b' '));
+/// let dense = DFA::from(&[word.clone(), space]).minimize().to_dense();
+/// assert!(dense.matches("hello"));
+/// assert_eq!(Scan::new(&dense, "hi there").count(), 3);
+/// ```
+#[derive(Clone, Debug)]
+pub struct DenseDFA {
+    next: Vec<u32>,
+    classes: Vec<Option<usize>>,
+    class_count: usize,
+    dead: Vec<bool>,
+}
+
+impl DenseDFA {
+    pub(crate) fn new(dfa: &DFA) -> Self {
+        let n = dfa.states.len();
+        let word = |x: usize| u32::try_from(x).expect("DFA too large");
+        let mut next = vec![0; 256 * n];
+        for (id, state) in dfa.states.iter().enumerate() {
+            for &(lo, hi, dest) in &state.next {
+                next[256 * id + usize::from(lo)..=256 * id + usize::from(hi)].fill(word(dest));
+            }
+        }
+        Self {
+            next,
+            classes: dfa.states.iter().map(|state| state.class).collect(),
+            class_count: dfa.class_count(),
+            dead: dfa.dead.clone(),
+        }
+    }
+
+    /// Returns the number of states, including the sink.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.classes.len()
+    }
+
+    /// Returns the number of bytes `self` holds on the heap, as in
+    /// `DFA::memory_usage`: 1 KiB per state, for the transitions.
+    #[must_use]
+    pub fn memory_usage(&self) -> usize {
+        heap_size(&self.next) + heap_size(&self.classes) + heap_size(&self.dead)
+    }
+
+    /// Always `false`, as a DFA has at least a sink and start state.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Returns `true` iff `text` is accepted. Same as `matches_bytes`.
+    #[must_use]
+    pub fn matches(&self, text: &str) -> bool {
+        self.matches_bytes(text.as_bytes())
+    }
+
+    /// Returns `true` iff `input` is accepted. `input` need not be UTF-8.
+    #[must_use]
+    pub fn matches_bytes(&self, input: &[u8]) -> bool {
+        self.class(self.run(DFA::START, input)).is_some()
+    }
+
+    /// Returns the state reached by stepping from `state` over `bytes`,
+    /// stopping early if a dead state (see `DFA::is_dead`) is reached.
+    #[must_use]
+    pub fn run(&self, state: usize, bytes: &[u8]) -> usize {
+        LexTable::run(self, state, bytes)
+    }
+}
+
+impl LexTable for DenseDFA {
+    fn start_state(&self) -> usize {
+        DFA::START
+    }
+
+    fn step(&self, state: usize, symbol: u8) -> usize {
+        usize::try_from(self.next[256 * state + usize::from(symbol)]).unwrap()
+    }
+
+    fn class(&self, state: usize) -> Option<usize> {
+        self.classes[state]
+    }
+
+    fn sink(&self) -> usize {
+        0
+    }
+
+    fn class_count(&self) -> usize {
+        self.class_count
+    }
+
+    fn is_dead(&self, state: usize) -> bool {
+        self.dead[state]
+    }
+}
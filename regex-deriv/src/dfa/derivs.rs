@@ -0,0 +1,52 @@
+#[cfg(feature = "rayon")]
+use std::sync::Mutex;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use super::{ByteSet, DerivCache, RegExVec};
+
+/// Derivatives of the states of a DFA under construction.
+///
+/// With the `rayon` feature, the derivatives of a state by each of its
+/// classes are computed in parallel, each thread of the pool memoizing its
+/// own. Either way, derivatives are hash-consed structurally, so the DFA
+/// built is the same.
+pub struct Derivs {
+    #[cfg(not(feature = "rayon"))]
+    cache: DerivCache,
+    // by thread index
+    #[cfg(feature = "rayon")]
+    caches: Vec<Mutex<DerivCache>>,
+}
+
+impl Derivs {
+    #[cfg(not(feature = "rayon"))]
+    pub fn new() -> Self {
+        Self { cache: DerivCache::new() }
+    }
+
+    #[cfg(feature = "rayon")]
+    pub fn new() -> Self {
+        Self { caches: (0..rayon::current_num_threads()).map(|_| Mutex::new(DerivCache::new())).collect() }
+    }
+
+    /// Returns the derivative of `q` by each of `classes`, in order, as
+    /// every byte of a class has the same derivative.
+    #[cfg(not(feature = "rayon"))]
+    pub fn of(&mut self, q: &RegExVec, classes: &[ByteSet]) -> Vec<RegExVec> {
+        classes.iter().map(|set| q.deriv(set.smallest().unwrap(), &mut self.cache)).collect()
+    }
+
+    /// Returns the derivative of `q` by each of `classes`, in order, as
+    /// every byte of a class has the same derivative.
+    #[cfg(feature = "rayon")]
+    pub fn of(&mut self, q: &RegExVec, classes: &[ByteSet]) -> Vec<RegExVec> {
+        let caches = &self.caches;
+        classes.par_iter().map(|set| {
+            // a thread only ever locks its own cache, unless it is not of
+            // the pool that the caches were made for
+            let index = rayon::current_thread_index().unwrap_or(0) % caches.len();
+            let mut cache = caches[index].lock().unwrap();
+            q.deriv(set.smallest().unwrap(), &mut cache)
+        }).collect()
+    }
+}
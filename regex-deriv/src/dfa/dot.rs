@@ -0,0 +1,69 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use super::{ByteSet, DFA, RegEx};
+
+/// Options controlling the output of `DFA::to_dot_with`.
+#[derive(Clone, Debug)]
+pub struct DotOptions {
+    /// Whether to draw the sink state and the transitions into it.
+    pub show_sink: bool,
+    /// Graphviz `rankdir` of the graph, e.g. `"LR"` or `"TB"`.
+    pub rankdir: &'static str,
+    /// Whether to label states with their ids. Accepting states are always
+    /// labelled with their class.
+    pub state_labels: bool,
+}
+
+impl Default for DotOptions {
+    fn default() -> Self {
+        Self { show_sink: false, rankdir: "LR", state_labels: true }
+    }
+}
+
+pub fn to_dot(dfa: &DFA, options: &DotOptions) -> String {
+    let first = usize::from(!options.show_sink);
+
+    let mut dot = String::new();
+    writeln!(dot, "digraph DFA {{").unwrap();
+    writeln!(dot, "    rankdir={};", options.rankdir).unwrap();
+    writeln!(dot, "    node [shape=circle];").unwrap();
+    writeln!(dot, "    start [shape=none, label=\"\"];").unwrap();
+    writeln!(dot, "    start -> 1;").unwrap();
+
+    for (id, state) in dfa.states.iter().enumerate().skip(first) {
+        let label = if options.state_labels { id.to_string() } else { String::new() };
+        match state.class {
+            Some(class) if options.state_labels => writeln!(dot, "    {id} [shape=doublecircle, label=\"{label}/{class}\"];"),
+            Some(class) => writeln!(dot, "    {id} [shape=doublecircle, label=\"{class}\"];"),
+            None if id == 0 => writeln!(dot, "    {id} [style=dashed, color=gray, label=\"{label}\"];"),
+            None => writeln!(dot, "    {id} [label=\"{label}\"];"),
+        }.unwrap();
+    }
+
+    for (id, state) in dfa.states.iter().enumerate().skip(first) {
+        let mut sets: BTreeMap<usize, ByteSet> = state.transitions_grouped().map(|(set, dest)| (dest, set)).collect();
+        if options.show_sink {
+            // every other byte leads to the sink
+            let missing = sets.values().fold(ByteSet::universe(), |missing, set| missing.difference(set));
+            if !missing.is_empty() && id != 0 {
+                sets.insert(0, missing);
+            }
+        }
+        for (dest, set) in sets.into_iter().skip_while(|&(dest, _)| dest < first) {
+            let label = escape(&RegEx::set(set).to_string());
+            writeln!(dot, "    {id} -> {dest} [label=\"{label}\"];").unwrap();
+        }
+    }
+
+    writeln!(dot, "}}").unwrap();
+    dot
+}
+
+// =================
+// === INTERNALS ===
+// =================
+
+/// Escapes `s` for use within a double-quoted dot string.
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,188 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt;
+use std::iter::once;
+use super::{DFA, RegEx, Operator};
+
+/// Error produced when converting a DFA into a regex would exceed a limit on
+/// the number of nodes of the regex.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TooLarge {
+    limit: usize,
+}
+
+impl TooLarge {
+    /// Returns the limit that was exceeded.
+    #[must_use]
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+}
+
+impl fmt::Display for TooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "regex exceeds the limit of {} nodes", self.limit)
+    }
+}
+
+impl std::error::Error for TooLarge {}
+
+/// Converts `dfa` into a regex recognizing the strings it accepts with class
+/// `class`, via state elimination. States are numbered in breadth-first order
+/// from the start state (taking bytes in ascending order) and then eliminated
+/// in a fixed order, so isomorphic DFAs produce identical regexes.
+pub(crate) fn to_regex(dfa: &DFA, class: usize) -> RegEx {
+    match to_regex_limited(dfa, class, None) {
+        Ok(regex) => regex,
+        Err(_) => unreachable!(),
+    }
+}
+
+/// Same as `to_regex`, but fails as soon as any intermediate regex has more
+/// than `limit` nodes, if there is a limit.
+pub(crate) fn to_regex_limited(dfa: &DFA, class: usize, limit: Option<usize>) -> Result<RegEx, TooLarge> {
+    let order = live_bfs_order(dfa, class);
+    if order.is_empty() {
+        return Ok(RegEx::none())
+    }
+
+    let mut index = vec![None; dfa.states.len()];
+    for (i, &id) in order.iter().enumerate() {
+        index[id] = Some(i);
+    }
+
+    // nodes 0..n are the live states, n is a new start node and n + 1 a new
+    // final node, connected to the live states by epsilon transitions
+    let n = order.len();
+    let (start, fin) = (n, n + 1);
+    let mut graph = Graph::new(n + 2, limit);
+    graph.add_edge(start, 0, &RegEx::empty())?;
+
+    for (i, &id) in order.iter().enumerate() {
+        let mut sets: BTreeMap<usize, super::ByteSet> = BTreeMap::new();
+        for symbol in 0..=255 {
+            if let Some(j) = index[dfa.step(id, symbol)] {
+                sets.entry(j).or_insert_with(super::ByteSet::empty).extend(once(symbol));
+            }
+        }
+        for (j, set) in sets {
+            graph.add_edge(i, j, &RegEx::set(set))?;
+        }
+        if dfa.class(id) == Some(class) {
+            graph.add_edge(i, fin, &RegEx::empty())?;
+        }
+    }
+
+    let mut remaining: BTreeSet<usize> = (0..n).collect();
+    while let Some(&q) = remaining.iter().min_by_key(|&&q| (graph.degree(q), q)) {
+        remaining.remove(&q);
+        graph.eliminate(q)?;
+    }
+
+    Ok(graph.edge(start, fin).cloned().unwrap_or_else(RegEx::none))
+}
+
+// =================
+// === INTERNALS ===
+// =================
+
+/// Returns the states that are reachable from the start state, and from
+/// which an accept state of `class` is reachable, in breadth-first order.
+fn live_bfs_order(dfa: &DFA, class: usize) -> Vec<usize> {
+    let n = dfa.states.len();
+    if n < 2 {
+        return Vec::new()
+    }
+
+    let coreachable = dfa.live(|accepted| accepted == Some(class));
+
+    let mut order = Vec::new();
+    let mut visited = vec![false; n];
+    let mut queue = VecDeque::new();
+    if coreachable[DFA::START] {
+        visited[DFA::START] = true;
+        queue.push_back(DFA::START);
+    }
+    while let Some(id) = queue.pop_front() {
+        order.push(id);
+        for symbol in 0..=255 {
+            let next = dfa.step(id, symbol);
+            if !visited[next] && coreachable[next] {
+                visited[next] = true;
+                queue.push_back(next);
+            }
+        }
+    }
+    order
+}
+
+/// Generalized transition graph, where edges are labelled by regexes.
+struct Graph {
+    out: Vec<BTreeMap<usize, RegEx>>,
+    into: Vec<BTreeSet<usize>>,
+    limit: Option<usize>,
+}
+
+impl Graph {
+    fn new(n: usize, limit: Option<usize>) -> Self {
+        Self { out: vec![BTreeMap::new(); n], into: vec![BTreeSet::new(); n], limit }
+    }
+
+    fn edge(&self, p: usize, q: usize) -> Option<&RegEx> {
+        self.out[p].get(&q)
+    }
+
+    fn add_edge(&mut self, p: usize, q: usize, regex: &RegEx) -> Result<(), TooLarge> {
+        let label = self.out[p].entry(q).or_insert_with(RegEx::none);
+        *label = label.or(regex);
+        if let Some(limit) = self.limit {
+            if size(label, limit) > limit {
+                return Err(TooLarge { limit })
+            }
+        }
+        self.into[q].insert(p);
+        Ok(())
+    }
+
+    fn degree(&self, q: usize) -> usize {
+        self.into[q].len() * self.out[q].len()
+    }
+
+    /// Removes `q`, rerouting every path p -> q -> r via a direct edge.
+    fn eliminate(&mut self, q: usize) -> Result<(), TooLarge> {
+        let looped = self.out[q].remove(&q).map_or_else(RegEx::empty, |regex| regex.star());
+        self.into[q].remove(&q);
+
+        let out = std::mem::take(&mut self.out[q]);
+        let into = std::mem::take(&mut self.into[q]);
+
+        for &r in out.keys() {
+            self.into[r].remove(&q);
+        }
+        for &p in &into {
+            let head = self.out[p].remove(&q).unwrap().then(&looped);
+            for (&r, tail) in &out {
+                self.add_edge(p, r, &head.then(tail))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the number of nodes of `regex`, counting shared subexpressions
+/// once per use, or some number greater than `limit` if that is exceeded.
+fn size(regex: &RegEx, limit: usize) -> usize {
+    let mut total = 0;
+    let mut stack = vec![regex];
+    while let Some(regex) = stack.pop() {
+        total += 1;
+        if total > limit {
+            break;
+        }
+        match regex.operator() {
+            Operator::None | Operator::Epsilon | Operator::Set(_) => (),
+            Operator::Star(re) | Operator::Not(re) => stack.push(re),
+            Operator::Cat(res) | Operator::Or(res) | Operator::And(res) | Operator::Interleave(res) => stack.extend(res),
+        }
+    }
+    total
+}
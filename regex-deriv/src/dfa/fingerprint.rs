@@ -0,0 +1,61 @@
+use std::collections::VecDeque;
+use super::DFA;
+
+/// Hashes the structure of the states of `dfa` reachable from the start,
+/// renumbered in breadth-first order with transitions visited in order of
+/// symbol. Isomorphic DFAs are renumbered alike, so for DFAs minimized on the
+/// class alone (as by `DFA::minimize`) the hash depends only on the classes
+/// they assign to strings.
+pub fn fingerprint(dfa: &DFA) -> u64 {
+    // sink = 0, start = 1, then the rest in order of discovery
+    let mut ids = vec![None; dfa.states.len()];
+    ids[0] = Some(0);
+    ids[DFA::START] = Some(1);
+    let mut order = vec![DFA::START];
+    let mut queue = VecDeque::from(vec![DFA::START]);
+    while let Some(id) = queue.pop_front() {
+        for &(_, _, dest) in &dfa.states[id].next {
+            if ids[dest].is_none() {
+                ids[dest] = Some(order.len() + 1);
+                order.push(dest);
+                queue.push_back(dest);
+            }
+        }
+    }
+
+    let mut hash = Fnv::new();
+    hash.write(order.len());
+    for &id in &order {
+        let state = &dfa.states[id];
+        hash.write(state.class.map_or(0, |class| class + 1));
+        hash.write(state.next.len());
+        for &(lo, hi, dest) in &state.next {
+            hash.write(usize::from(lo));
+            hash.write(usize::from(hi));
+            hash.write(ids[dest].unwrap());
+        }
+    }
+    hash.0
+}
+
+// =================
+// === INTERNALS ===
+// =================
+
+/// 64-bit FNV-1a, which unlike `DefaultHasher` is fixed across releases, so
+/// that fingerprints can be stored and compared between builds.
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    /// Hashes `value` as 8 little-endian bytes, whatever the width of `usize`.
+    fn write(&mut self, value: usize) {
+        for byte in (value as u64).to_le_bytes() {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+}
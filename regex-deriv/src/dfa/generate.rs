@@ -0,0 +1,95 @@
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use rand_core::RngCore;
+use super::DFA;
+
+/// Options for `DFA::generate`.
+#[derive(Clone, Debug)]
+pub struct GenOptions {
+    /// Maximum length of a generated string.
+    pub max_len: usize,
+    /// Class with which generated strings must be accepted, or `None` for
+    /// any class.
+    pub class: Option<usize>,
+    /// Probability, from 0 to 1, of heading straight for the end at each
+    /// step: stopping if the string is accepted, and otherwise taking a
+    /// transition that brings an accepting state closer. The larger it is,
+    /// the shorter generated strings tend to be.
+    pub termination: f64,
+}
+
+impl Default for GenOptions {
+    /// Strings of up to 64 bytes of any class, ending with probability 1/8
+    /// at each step.
+    fn default() -> Self {
+        Self { max_len: 64, class: None, termination: 0.125 }
+    }
+}
+
+pub fn generate<R: RngCore>(dfa: &DFA, rng: &mut R, options: &GenOptions) -> Option<Vec<u8>> {
+    let is_goal = |id: usize| match options.class {
+        Some(class) => dfa.states[id].class == Some(class),
+        None => dfa.states[id].class.is_some(),
+    };
+    let dist = distances(dfa, &is_goal);
+
+    let mut id = DFA::START;
+    let mut string = Vec::new();
+    if dist[id].is_none_or(|d| d > options.max_len) {
+        return None;
+    }
+
+    loop {
+        let remaining = options.max_len - string.len();
+        let hasten = chance(rng) < options.termination;
+        if is_goal(id) && (remaining == 0 || hasten) {
+            return Some(string);
+        }
+
+        // dist[id] <= remaining is kept, so unless id is a goal there is
+        // always a symbol leading closer
+        let edges: Vec<(u8, usize)> = dfa.states[id].edges()
+            .filter(|&(_, dest)| dist[dest].is_some_and(|d| d < remaining))
+            .filter(|&(_, dest)| !hasten || dist[dest] < dist[id])
+            .collect();
+        if edges.is_empty() {
+            return Some(string);
+        }
+        let (symbol, dest) = edges[usize::try_from(rng.next_u32()).unwrap() % edges.len()];
+        string.push(symbol);
+        id = dest;
+    }
+}
+
+// =================
+// === INTERNALS ===
+// =================
+
+/// Returns, for each state, the length of a shortest string leading from it
+/// to a goal state, if any.
+fn distances(dfa: &DFA, is_goal: &dyn Fn(usize) -> bool) -> Vec<Option<usize>> {
+    let n = dfa.states.len();
+    let mut prev = vec![Vec::new(); n];
+    for (id, state) in dfa.states.iter().enumerate() {
+        for &(_, _, dest) in &state.next {
+            prev[dest].push(id);
+        }
+    }
+
+    let mut dist: Vec<Option<usize>> = (0..n).map(|id| if is_goal(id) { Some(0) } else { None }).collect();
+    let mut queue: VecDeque<usize> = (0..n).filter(|&id| is_goal(id)).collect();
+    while let Some(id) = queue.pop_front() {
+        for &source in &prev[id] {
+            if dist[source].is_none() {
+                dist[source] = dist[id].map(|d| d + 1);
+                queue.push_back(source);
+            }
+        }
+    }
+    dist
+}
+
+/// Returns a number from 0 to 1.
+fn chance<R: RngCore>(rng: &mut R) -> f64 {
+    f64::from(rng.next_u32()) / f64::from(u32::MAX)
+}
@@ -1,38 +1,92 @@
-use std::collections::{BTreeSet, HashSet, HashMap, hash_map::Entry};
+use std::collections::{BTreeMap, BTreeSet, HashMap, btree_map::Entry};
 use super::{DFA, State};
 
-pub fn minimize(dfa: &DFA) -> DFA {
+/// Returns the block of each state of `dfa` in the Myhill-Nerode partition,
+/// numbered in order of the smallest state of each block. If `keep_classes`,
+/// states are also kept apart unless they accept the same list of classes
+/// (see `DFA::classes_at`), rather than just the same class.
+pub fn state_equivalence(dfa: &DFA, keep_classes: bool) -> Vec<usize> {
+    // Thanks to lexicographical ordering of BTreeSet elements, and since the
+    // sets are disjoint, they are ordered by their smallest state.
+    let mut blocks = vec![0; dfa.states.len()];
+    for (block, set) in equivalence_classes(dfa, keep_classes).iter().enumerate() {
+        for &id in set {
+            blocks[id] = block;
+        }
+    }
+    blocks
+}
+
+/// Minimized DFA along with where each state went, as returned by
+/// `DFA::minimize_with_ids`.
+pub struct MinimizeReport {
+    /// The minimized DFA.
+    pub dfa: DFA,
+    /// For each state of the DFA minimized, its id in `dfa`, or `None` for
+    /// states dropped along with a dead start state.
+    pub ids: Vec<Option<usize>>,
+}
+
+/// Returns the minimized DFA, along with the id in it of each state of `dfa`.
+/// Each state of the result accepts the classes of the states merged into it
+/// if `keep_classes`, and otherwise just their class.
+pub fn minimize(dfa: &DFA, keep_classes: bool) -> MinimizeReport {
     // Since the accept state must always be reachable by some input string
     // from the start state in any well-formed DFA, the equivalence class
     // containing the start state must be distinct from the equivalence
     // class containing the sink state. Hence, a minimised DFA must have
     // a distinct start and sink state.
 
-    // Thanks to lexicographical ordering of BTreeSet elements,
-    // the first set in the partition is guaranteed to be the sink class
-    // and the second set is guaranteed to be the start class.
-    let partition = equivalence_classes(dfa);
+    // Blocks are numbered by their smallest state, so the first block is
+    // guaranteed to be the sink class and the second block is guaranteed
+    // to be the start class.
+    let blocks = state_equivalence(dfa, keep_classes);
+
+    // The exception is a DFA accepting no strings at all (e.g. the complement
+    // of one accepting every string), whose start state is itself a sink.
+    if blocks[DFA::START] == 0 {
+        let mut ids: Vec<_> = blocks.iter().map(|&block| if block == 0 { Some(0) } else { None }).collect();
+        ids[DFA::START] = Some(DFA::START);
+        return MinimizeReport { dfa: DFA::from_states(vec![State::sink(), State::sink()], dfa.class_count), ids }
+    }
+
+    let mut sets = vec![Vec::new(); blocks.iter().max().map_or(0, |&max| max + 1)];
+    for (id, &block) in blocks.iter().enumerate() {
+        sets[block].push(id);
+    }
 
-    let mut states = Vec::with_capacity(partition.len());
+    let mut states = Vec::with_capacity(sets.len());
     states.push(State::sink());
 
-    for set in partition.iter().skip(1) {
-        let mut next = HashMap::new();
+    for set in sets.iter().skip(1) {
+        let mut next = BTreeMap::new();
 
         for &source in set {
-            for (&symbol, dest) in &dfa.states[source].next {
+            for (symbol, dest) in dfa.states[source].edges() {
                 if let Entry::Vacant(e) = next.entry(symbol) {
-                    if let Some(id) = partition.iter().skip(1).position(|set| set.contains(&dest)) {
-                        e.insert(id + 1);
+                    if blocks[dest] != 0 {
+                        e.insert(blocks[dest]);
                     }
                 }
             }
         }
 
-        states.push(State::new(next, set.iter().filter_map(|&id| dfa.states[id].class).min()));
+        // the coarse partition keeps states accepting different classes
+        // apart, and refinement only ever splits sets, so a set whose states
+        // disagree on their classes is a bug, rather than a choice to make
+        let state = &dfa.states[set[0]];
+        assert!(
+            set.iter().all(|&id| dfa.states[id].class == state.class && (!keep_classes || dfa.states[id].classes == state.classes)),
+            "states {:?} accepting different classes were merged", set,
+        );
+        if keep_classes {
+            states.push(State::with_classes(next, state.class, state.classes.clone()));
+        } else {
+            states.push(State::new(next, state.class));
+        }
     }
 
-    DFA { states }
+    MinimizeReport { dfa: DFA::from_states(states, dfa.class_count), ids: blocks.into_iter().map(Some).collect() }
 }
 
 // =================
@@ -47,7 +101,7 @@ type Partition = BTreeSet<Ids>;
 /// Myhill-Nerode equivalence relation (i.e. starting from either
 /// state, all remaining substrings will result in the same accept
 /// or reject behaviour)
-fn equivalence_classes(dfa: &DFA) -> Partition {
+fn equivalence_classes(dfa: &DFA, keep_classes: bool) -> Partition {
     // while the alphabet technically consists of all possible chars,
     // we are only required to consider those that appear in transitions
     // for this DFA.
@@ -56,7 +110,7 @@ fn equivalence_classes(dfa: &DFA) -> Partition {
     // precompute inverse of transition function
     let idfa = InvDFA::new(&dfa, &alph); // O(kn)
     
-    let mut partition = coarse_partition(&dfa); // O(n)
+    let mut partition = coarse_partition(dfa, keep_classes); // O(n)
     let mut waiting   = all_but_largest(&partition);
 
     while !waiting.is_empty() {
@@ -105,7 +159,7 @@ impl InvDFA {
         for (source_id, state) in dfa.states.iter().enumerate() {
             // for (&symbol, &dest_id) in &state.next {
             for &symbol in alph {
-                let dest_id = if let Some(&dest_id) = state.next.get(&symbol) { dest_id } else { 0 };
+                let dest_id = state.step(symbol);
                 states[dest_id].entry(symbol).or_default().insert(source_id);
             }
         }
@@ -123,13 +177,10 @@ impl InvDFA {
     }
 }
 
+/// Returns, in ascending order, the symbols of any transition of `states`.
 fn alphabet(states: &[State]) -> Vec<u8> {
-    let mut iter = states.iter().map(|state| state.next.keys());
-    let alph = iter.next().unwrap().copied().collect::<HashSet<_>>();
-    let alph = iter.fold(alph, |mut alph, keys| {
-        alph.extend(keys); alph
-    });
-    alph.into_iter().collect() // TODO: should we bother copying into vector?
+    let alph: BTreeSet<u8> = states.iter().flat_map(|state| state.edges().map(|(symbol, _)| symbol)).collect();
+    alph.into_iter().collect()
 }
 
 /// Produces an initial partition of states such that all pairs of 
@@ -137,12 +188,13 @@ fn alphabet(states: &[State]) -> Vec<u8> {
 /// initial partitioning is coarse; the sets of the partition may contain
 /// pairs of nodes that aren't equivalent. The goal of the Hopcroft
 /// algorithm is to repeatedly refine the paritioning such that each set
-/// strictly contains equivalent nodes.
-fn coarse_partition(dfa: &DFA) -> Partition {
+/// strictly contains equivalent nodes. States are keyed by their class, and
+/// also by the list of classes they accept if `keep_classes`.
+fn coarse_partition(dfa: &DFA, keep_classes: bool) -> Partition {
     let mut partition: HashMap<_, Ids> = HashMap::new();
     for (id, state) in dfa.states.iter().enumerate() {
-        let class = state.class.map_or(0, |class| class + 1);
-        partition.entry(class).or_default().insert(id);
+        let classes: &[usize] = if keep_classes { &state.classes } else { &[] };
+        partition.entry((state.class, classes)).or_default().insert(id);
     }
     partition.values().cloned().collect()
 }
@@ -0,0 +1,262 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use super::{DFA, RegEx, RegExVec, DerivCache, LexTable};
+
+/// Which states a `LazyDFA` evicts on reaching its state limit.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Eviction {
+    /// Evict the least recently entered state.
+    Lru,
+    /// Evict every state but the sink and the start.
+    Flush,
+}
+
+/// Builder for a `LazyDFA` with a bounded number of states.
+#[derive(Clone, Debug)]
+pub struct LazyDFABuilder {
+    limit: Option<usize>,
+    eviction: Eviction,
+}
+
+impl Default for LazyDFABuilder {
+    fn default() -> Self {
+        Self { limit: None, eviction: Eviction::Lru }
+    }
+}
+
+impl LazyDFABuilder {
+    /// Constructs a builder with no state limit, evicting least recently
+    /// used states once one is set.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of states, including the sink and the start,
+    /// to keep at once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `limit < 3`, as there must be room for a state besides the
+    /// sink and the start.
+    #[must_use]
+    pub fn state_limit(mut self, limit: usize) -> Self {
+        assert!(limit >= 3, "state limit must be at least 3, got {}", limit);
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Sets which states to evict on reaching the state limit.
+    #[must_use]
+    pub fn eviction(mut self, eviction: Eviction) -> Self {
+        self.eviction = eviction;
+        self
+    }
+
+    /// Constructs the lazy DFA of `regexes`, where the class of a state is
+    /// the index of the first regex it accepts, as for `DFA::from`.
+    pub fn build<'a, T: IntoIterator<Item = &'a RegEx>>(&self, regexes: T) -> LazyDFA {
+        LazyDFA::with_options(RegExVec::new(regexes.into_iter().cloned().collect()), self.limit, self.eviction)
+    }
+}
+
+/// DFA whose states are constructed on demand, as `step` first takes each
+/// transition, rather than all up front. State 0 is the sink and state 1 the
+/// start, as for `DFA`.
+///
+/// With a state limit, states are evicted to make room for new ones, and a
+/// state's id may then be reused. Besides the sink and the start, only the
+/// state most recently returned by `step` is guaranteed to stay valid, which
+/// is all that `Scan` needs.
+pub struct LazyDFA {
+    cache: RefCell<Cache>,
+    class_count: usize,
+    limit: Option<usize>,
+    eviction: Eviction,
+}
+
+impl LazyDFA {
+    /// Constructs the lazy DFA of `regexes`, with no state limit.
+    pub fn new<'a, T: IntoIterator<Item = &'a RegEx>>(regexes: T) -> Self {
+        LazyDFABuilder::new().build(regexes)
+    }
+
+    /// Returns a builder, for setting a state limit.
+    #[must_use]
+    pub fn builder() -> LazyDFABuilder {
+        LazyDFABuilder::new()
+    }
+
+    /// Returns the number of states currently constructed, including the sink
+    /// and the start.
+    #[must_use]
+    pub fn state_count(&self) -> usize {
+        self.cache.borrow().states.len()
+    }
+
+    fn with_options(start: RegExVec, limit: Option<usize>, eviction: Eviction) -> Self {
+        let mut cache = Cache {
+            states: Vec::new(),
+            re2idx: HashMap::new(),
+            lru: BTreeMap::new(),
+            clock: 0,
+            evicted: 0,
+            derivs: DerivCache::new(),
+        };
+        // s0 = sink state, s1 = start state
+        let class_count = start.0.len();
+        cache.push(RegExVec::sink(class_count));
+        cache.push(start);
+        Self { cache: RefCell::new(cache), class_count, limit, eviction }
+    }
+}
+
+impl LexTable for LazyDFA {
+    fn start_state(&self) -> usize {
+        DFA::START
+    }
+
+    fn step(&self, state: usize, symbol: u8) -> usize {
+        if state == 0 {
+            return 0;
+        }
+
+        let cache = &mut *self.cache.borrow_mut();
+        let dest = match cache.states[state].next[usize::from(symbol)] {
+            Some((dest, stamp)) if cache.is_current(dest, stamp) => dest,
+            _ => {
+                let stamp = cache.states[state].stamp;
+                let q = cache.states[state].regexes.deriv(symbol, &mut cache.derivs);
+                let dest = match cache.re2idx.get(&q) {
+                    Some(&dest) => dest,
+                    None => cache.add(q, self.limit, self.eviction),
+                };
+                // unless state itself was evicted to make room for dest
+                if cache.is_current(state, stamp) {
+                    cache.states[state].next[usize::from(symbol)] = Some((dest, cache.states[dest].stamp));
+                }
+                dest
+            },
+        };
+        cache.touch(dest);
+        dest
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `state` has been evicted, such that its id is out of range.
+    fn class(&self, state: usize) -> Option<usize> {
+        self.cache.borrow().states[state].class
+    }
+
+    fn sink(&self) -> usize {
+        0
+    }
+
+    /// Returns the number of regexes, as the classes of states yet to be
+    /// constructed aren't known.
+    fn class_count(&self) -> usize {
+        self.class_count
+    }
+}
+
+// =================
+// === INTERNALS ===
+// =================
+
+struct Cache {
+    states: Vec<Slot>,
+    re2idx: HashMap<RegExVec, usize>,
+    // last use -> id, of every state but the sink and the start
+    lru: BTreeMap<u64, usize>,
+    clock: u64,
+    // states evicted since derivs was last cleared
+    evicted: usize,
+    derivs: DerivCache,
+}
+
+struct Slot {
+    regexes: RegExVec,
+    class: Option<usize>,
+    // transitions taken so far, to a state id and the stamp it had
+    next: Box<[Option<(usize, u64)>; 256]>,
+    // clock when the state was constructed, so that transitions into a
+    // state since evicted, whose id may have been reused, can be told apart
+    stamp: u64,
+    used: u64,
+}
+
+impl Cache {
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    fn is_current(&self, id: usize, stamp: u64) -> bool {
+        self.states.get(id).is_some_and(|slot| slot.stamp == stamp)
+    }
+
+    fn push(&mut self, q: RegExVec) -> usize {
+        let id = self.states.len();
+        let slot = self.slot(q.clone());
+        self.re2idx.insert(q, id);
+        self.states.push(slot);
+        if id > 1 {
+            self.lru.insert(self.states[id].used, id);
+        }
+        id
+    }
+
+    fn slot(&mut self, regexes: RegExVec) -> Slot {
+        let stamp = self.tick();
+        Slot { class: regexes.class(), regexes, next: Box::new([None; 256]), stamp, used: stamp }
+    }
+
+    /// Adds a state for `q`, first evicting states if there are `limit`.
+    fn add(&mut self, q: RegExVec, limit: Option<usize>, eviction: Eviction) -> usize {
+        if limit.is_none_or(|limit| self.states.len() < limit) {
+            return self.push(q);
+        }
+
+        // the derivative cache keeps every node it has seen alive, so clear
+        // it each time as many states have been evicted as are kept
+        let evicted = match eviction {
+            Eviction::Lru => 1,
+            Eviction::Flush => self.states.len() - 2,
+        };
+        self.evicted += evicted;
+        if self.evicted >= self.states.len() {
+            self.derivs.clear();
+            self.evicted = 0;
+        }
+
+        match eviction {
+            Eviction::Lru => {
+                let id = *self.lru.values().next().unwrap();
+                let slot = self.slot(q.clone());
+                let old = std::mem::replace(&mut self.states[id], slot);
+                self.lru.remove(&old.used);
+                self.re2idx.remove(&old.regexes);
+                self.re2idx.insert(q, id);
+                self.lru.insert(self.states[id].used, id);
+                id
+            },
+            Eviction::Flush => {
+                for old in self.states.drain(2..) {
+                    self.re2idx.remove(&old.regexes);
+                }
+                self.lru.clear();
+                self.push(q)
+            },
+        }
+    }
+
+    fn touch(&mut self, id: usize) {
+        if id > 1 {
+            let used = self.tick();
+            let old = std::mem::replace(&mut self.states[id].used, used);
+            self.lru.remove(&old);
+            self.lru.insert(used, id);
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use std::fmt;
+
+/// Limits on the size of a DFA under construction, as given to
+/// `DFA::try_from_rules`.
+#[derive(Clone, Debug)]
+pub struct BuildLimits {
+    /// Maximum number of states, including the sink.
+    pub max_states: usize,
+    /// Maximum number of transitions to states other than the sink, as
+    /// counted by `DfaStats::transitions`.
+    pub max_transitions: usize,
+}
+
+impl Default for BuildLimits {
+    /// No limits at all.
+    fn default() -> Self {
+        Self { max_states: usize::MAX, max_transitions: usize::MAX }
+    }
+}
+
+/// Error produced when constructing a DFA would exceed its `BuildLimits`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum BuildError {
+    /// More states were needed than `BuildLimits::max_states`.
+    StateLimitExceeded {
+        /// Number of states built, which is the limit.
+        built: usize,
+    },
+    /// More transitions were needed than `BuildLimits::max_transitions`.
+    TransitionLimitExceeded {
+        /// Number of transitions built, which is beyond the limit.
+        built: usize,
+    },
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::StateLimitExceeded { built } => write!(f, "DFA exceeds the limit of {built} states"),
+            Self::TransitionLimitExceeded { built } => write!(f, "DFA exceeds the transition limit, with {built} transitions built"),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
@@ -0,0 +1,58 @@
+use super::DFA;
+
+/// Matcher of input arriving in chunks against a DFA, as returned by
+/// `DFA::matcher`. Unlike `Scan`, it does not tokenize: it only tracks the
+/// state reached by the input fed so far.
+#[derive(Clone)]
+pub struct DfaMatcher<'a> {
+    dfa: &'a DFA,
+    state: usize,
+}
+
+impl<'a> DfaMatcher<'a> {
+    /// Constructs a matcher of `dfa` at its start state.
+    #[must_use]
+    pub fn new(dfa: &'a DFA) -> Self {
+        Self { dfa, state: DFA::START }
+    }
+
+    /// Steps through `bytes`, stopping as soon as a dead state (see
+    /// `DFA::is_dead`) is reached. Once a dead state is reached, further input
+    /// is ignored until `reset`.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        if self.is_dead() {
+            return;
+        }
+        for &byte in bytes {
+            self.state = self.dfa.step(self.state, byte);
+            if self.is_dead() {
+                return;
+            }
+        }
+    }
+
+    /// Returns `true` iff a dead state has been reached, so that no further
+    /// input can be accepted.
+    #[must_use]
+    pub fn is_dead(&self) -> bool {
+        self.dfa.is_dead(self.state)
+    }
+
+    /// Returns the class with which the input fed so far is accepted, if it
+    /// is.
+    #[must_use]
+    pub fn is_accepting(&self) -> Option<usize> {
+        self.dfa.class(self.state)
+    }
+
+    /// Returns the state reached by the input fed so far.
+    #[must_use]
+    pub fn state(&self) -> usize {
+        self.state
+    }
+
+    /// Returns to the start state, forgetting the input fed so far.
+    pub fn reset(&mut self) {
+        self.state = DFA::START;
+    }
+}
@@ -1,20 +1,48 @@
-use std::collections::{HashSet, HashMap, BTreeMap};
+use std::collections::{BTreeMap, HashSet, HashMap, VecDeque};
+use std::convert::TryFrom;
+use std::cmp::Reverse;
+use std::fmt;
 use std::iter::once;
+use std::io::{self, Read};
+use std::rc::Rc;
 
-use super::{ByteSet, RegEx, Operator};
+use itertools::Itertools;
+use rand_core::RngCore;
+use super::{ByteSet, RegEx, Operator, DerivCache, LexTable};
+use super::table::heap_size;
+use super::byte_set::write_runs;
 
+/// Deterministic finite automaton over bytes, where state 0 is the sink and
+/// state `DFA::START` = 1 the start. Tables built from a DFA, such as
+/// `NaiveLexTable`, may number states differently; drive them from their
+/// `LexTable::start_state` instead. Construction is deterministic: building (and then
+/// minimizing) a DFA from the same regexes always numbers its states the same
+/// way, so tables generated from it are reproducible.
 pub struct DFA {
     states: Vec<State>,
+    // by state, whether no accepting state is reachable from it
+    dead: Vec<bool>,
+    // the number of rules built from, some of which may never be accepted
+    class_count: usize,
 }
 
 pub struct State {
-    pub next: HashMap<u8, usize>,
+    // transitions as disjoint inclusive ranges of symbols, in ascending
+    // order, with adjacent ranges to the same state merged; symbols outside
+    // every range lead to the sink
+    pub(crate) next: Vec<(u8, u8, usize)>,
     pub class: Option<usize>,
+    // every class accepted, in ascending order, of which class is the one
+    // with the highest priority
+    classes: Vec<usize>,
 }
 
+/// Priority of a rule in `DFA::from_rules`, where a higher priority wins.
+pub type Priority = u32;
+
 impl From<&RegEx> for DFA {
     fn from(regex: &RegEx) -> Self {
-        DFABuilder::build(&RegExVec::new(vec![regex.clone()]))
+        DFABuilder::build_unlimited(&RegExVec::new(vec![regex.clone()]), vec![0])
     }
 }
 
@@ -23,33 +51,547 @@ where
     T: IntoIterator<Item = &'a RegEx>,
 {
     fn from(regexes: T) -> Self {
-        DFABuilder::build(&RegExVec::new(regexes.into_iter().cloned().collect()))
+        Self::from_owned(regexes.into_iter().cloned().collect())
     }
 }
 
 impl DFA {
-    /// Constructs the equivalent, minimized DFA via Hopcroft's algorithm.
+    /// The id of the start state. The sink is always state 0.
+    pub const START: usize = 1;
+
+    /// Same as `DFA::from` a slice of `regexes`, but takes ownership of them
+    /// rather than cloning them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA};
+    /// let rules = vec![RegEx::set(ByteSet::range(b'a', b'z')).plus(), RegEx::set(ByteSet::range(b'0', b'9')).plus()];
+    /// let dfa = DFA::from_owned(rules);
+    /// assert_eq!(dfa.longest_match(b"42x"), Some((2, 1)));
+    /// ```
+    ///
+    /// whereas `DFA::from` only takes regexes by reference:
+    ///
+    /// ```compile_fail
+    /// # use regex_deriv::{RegEx, DFA};
+    /// let dfa = DFA::from(vec![RegEx::empty()]);
+    /// ```
+    #[must_use]
+    pub fn from_owned(regexes: Vec<RegEx>) -> Self {
+        let priorities = vec![0; regexes.len()];
+        DFABuilder::build_unlimited(&RegExVec::new(regexes), priorities)
+    }
+
+    /// Constructs the DFA of `rules`, where the class of a state is the index
+    /// of the rule it accepts with the highest priority, ties going to the
+    /// earliest such rule. With equal priorities, this is the same as
+    /// `DFA::from` the regexes alone.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA};
+    /// let lit = |s: &[u8]| s.iter().fold(RegEx::empty(), |re, &c| re.then(&RegEx::set(ByteSet::point(c))));
+    /// let ident = RegEx::set(ByteSet::range(b'a', b'z')).plus();
+    /// // the keyword beats the identifier, though it comes later
+    /// let dfa = DFA::from_rules(&[(ident, 0), (lit(b"if"), 1)]);
+    /// assert_eq!(dfa.longest_match(b"if"), Some((2, 1)));
+    /// assert_eq!(dfa.longest_match(b"iffy"), Some((4, 0)));
+    /// ```
+    #[must_use]
+    pub fn from_rules(rules: &[(RegEx, Priority)]) -> Self {
+        let (regexes, priorities) = rules.iter().cloned().unzip();
+        DFABuilder::build_unlimited(&RegExVec::new(regexes), priorities)
+    }
+
+    /// Same as `from_rules`, but gives up as soon as the DFA would exceed
+    /// `limits`, rather than using up all available memory on rules whose
+    /// DFA blows up.
+    ///
+    /// # Errors
+    ///
+    /// Returns which limit was exceeded, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA, BuildLimits, BuildError};
+    /// // a DFA for (a|b)*a(a|b){19} must remember the last 20 bytes
+    /// let ab = RegEx::set(ByteSet::range(b'a', b'b'));
+    /// let a = RegEx::set(ByteSet::point(b'a'));
+    /// let regex = (0..19).fold(ab.star().then(&a), |regex, _| regex.then(&ab));
+    /// let limits = BuildLimits { max_states: 1000, ..BuildLimits::default() };
+    /// let err = DFA::try_from_rules(&[(regex, 0)], &limits).err().unwrap();
+    /// assert_eq!(err, BuildError::StateLimitExceeded { built: 1000 });
+    /// ```
+    pub fn try_from_rules(rules: &[(RegEx, Priority)], limits: &BuildLimits) -> Result<Self, BuildError> {
+        let (regexes, priorities) = rules.iter().cloned().unzip();
+        DFABuilder::build(&RegExVec::new(regexes), priorities, limits.clone(), &mut Unobserved)
+    }
+
+    /// Same as `try_from_rules`, but reports on the construction to
+    /// `observer` as it goes.
+    ///
+    /// # Errors
+    ///
+    /// Returns which limit was exceeded, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA, BuildLimits, BuildObserver};
+    /// struct Count(usize);
+    ///
+    /// impl BuildObserver for Count {
+    ///     fn on_state_added(&mut self, _regexes: &[RegEx], _id: usize) {
+    ///         self.0 += 1;
+    ///     }
+    /// }
+    ///
+    /// let digits = RegEx::set(ByteSet::range(b'0', b'9')).plus();
+    /// let mut count = Count(0);
+    /// let dfa = DFA::try_from_rules_observed(&[(digits, 0)], &BuildLimits::default(), &mut count).unwrap();
+    /// // every state but the sink
+    /// assert_eq!(count.0, dfa.states().len() - 1);
+    /// ```
+    pub fn try_from_rules_observed(rules: &[(RegEx, Priority)], limits: &BuildLimits, observer: &mut dyn BuildObserver) -> Result<Self, BuildError> {
+        let (regexes, priorities) = rules.iter().cloned().unzip();
+        match DFABuilder::build(&RegExVec::new(regexes), priorities, limits.clone(), observer) {
+            Ok(dfa) => {
+                observer.on_finished(&dfa.stats());
+                Ok(dfa)
+            },
+            Err(error) => {
+                observer.on_limit_exceeded(&error);
+                Err(error)
+            },
+        }
+    }
+
+    /// Returns, for each state, its block in the partition of states into
+    /// Myhill-Nerode equivalence classes: two states share a block iff every
+    /// string leads from both to the same class, or lack thereof. Blocks are
+    /// numbered from 0 in order of their smallest state, so the sink is in
+    /// block 0 and, unless it accepts nothing, the start in block 1.
+    ///
+    /// `minimize` merges exactly the states of each block, so unless `self`
+    /// accepts nothing, the block of a state is its id in the minimized DFA.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA};
+    /// let a = RegEx::set(ByteSet::point(b'a'));
+    /// let b = RegEx::set(ByteSet::point(b'b'));
+    /// let dfa = DFA::from(&a.then(&b).or(&b.then(&b)));
+    /// let blocks = dfa.state_equivalence();
+    /// assert_eq!(blocks[dfa.run(DFA::START, b"a")], blocks[dfa.run(DFA::START, b"b")]);
+    /// assert_ne!(blocks[dfa.run(DFA::START, b"a")], blocks[dfa.run(DFA::START, b"ab")]);
+    /// ```
+    #[must_use]
+    pub fn state_equivalence(&self) -> Vec<usize> {
+        hopcroft::state_equivalence(self, false)
+    }
+
+    /// Constructs the equivalent, minimized DFA via Hopcroft's algorithm:
+    /// the DFA of fewest states giving every string the same class.
+    ///
+    /// Only states of the same class are ever merged, so there is no choice
+    /// of class to make. Each state of the result accepts just its class, as
+    /// given by `classes_at`; see `minimize_keeping_classes` to keep the
+    /// others.
+    ///
+    /// # Panics
+    ///
+    /// Panics if states of different classes would be merged, which would be
+    /// a bug.
     #[must_use]
     pub fn minimize(&self) -> Self {
-        hopcroft::minimize(self)
+        hopcroft::minimize(self, false).dfa
+    }
+
+    /// Same as `minimize`, except that states are only merged if they also
+    /// accept the same classes, as given by `classes_at`, which the result
+    /// keeps. This may leave more states than `minimize`, apart only by the
+    /// classes of shadowed rules.
+    ///
+    /// # Panics
+    ///
+    /// Panics if states accepting different classes would be merged, which
+    /// would be a bug.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA};
+    /// let lit = |s: &[u8]| s.iter().fold(RegEx::empty(), |re, &c| re.then(&RegEx::set(ByteSet::point(c))));
+    /// let ident = RegEx::set(ByteSet::range(b'a', b'z')).plus();
+    /// let dfa = DFA::from(&[ident, lit(b"if")]);
+    /// let (minimal, keeping) = (dfa.minimize(), dfa.minimize_keeping_classes());
+    /// assert!(minimal.states().len() < keeping.states().len());
+    /// assert_eq!(keeping.classes_at(keeping.run(DFA::START, b"if")), [0, 1]);
+    /// assert_eq!(minimal.classes_at(minimal.run(DFA::START, b"if")), [0]);
+    /// ```
+    #[must_use]
+    pub fn minimize_keeping_classes(&self) -> Self {
+        hopcroft::minimize(self, true).dfa
+    }
+
+    /// Same as `minimize`, but also reports, for each state of `self`, its id
+    /// in the result, for carrying over tables keyed by state. If `self`
+    /// accepts nothing, the result is just the sink and the start, and any
+    /// (unreachable) states that could accept have no id; otherwise every
+    /// state has one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if states accepting different classes would be merged, which
+    /// would be a bug.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA};
+    /// let digit = RegEx::set(ByteSet::range(b'0', b'9'));
+    /// let dfa = DFA::from(&digit.then(&digit.star()));
+    /// let report = dfa.minimize_with_ids();
+    /// let end = dfa.run(DFA::START, b"42");
+    /// assert_eq!(report.ids[end], Some(report.dfa.run(DFA::START, b"42")));
+    /// ```
+    #[must_use]
+    pub fn minimize_with_ids(&self) -> MinimizeReport {
+        hopcroft::minimize(self, false)
+    }
+
+    /// Constructs the equivalent DFA without unreachable states, and with
+    /// every transition into a dead state (from which no accepting state is
+    /// reachable) redirected to the sink, so that runs stop as soon as they
+    /// can no longer accept. Also returns, for each state of `self`, its id in
+    /// the result: `Some(0)` if it was dead, or `None` if it was unreachable.
+    /// The remaining states keep their relative order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA};
+    /// let digits = RegEx::set(ByteSet::range(b'0', b'9')).plus();
+    /// // the accept-all state added by the first complement is dead after the
+    /// // second, and the one added by the second is unreachable
+    /// let dfa = DFA::from(&digits).complement().complement();
+    /// let (trimmed, ids) = dfa.trim();
+    /// assert_eq!(trimmed.states().len(), dfa.states().len() - 2);
+    /// assert_eq!(ids[ids.len() - 2..], [Some(0), None]);
+    /// assert!(trimmed.equivalent(&dfa));
+    /// ```
+    #[must_use]
+    pub fn trim(&self) -> (Self, Vec<Option<usize>>) {
+        let reachable = self.reachable_from(Self::START);
+        let live = self.live(|class| class.is_some());
+
+        let mut ids = vec![None; self.states.len()];
+        let mut count = 1;
+        for id in 0..self.states.len() {
+            if id == Self::START || (reachable[id] && live[id]) {
+                ids[id] = Some(count);
+                count += 1;
+            } else if reachable[id] {
+                ids[id] = Some(0);
+            }
+        }
+        ids[0] = Some(0);
+
+        let mut states = Vec::with_capacity(count);
+        states.push(State::sink());
+        for (id, state) in self.states.iter().enumerate().skip(1) {
+            if ids[id].is_some_and(|new| new != 0) {
+                let next = state.edges()
+                    .filter_map(|(symbol, dest)| match ids[dest] {
+                        Some(0) | None => None,
+                        Some(new) => Some((symbol, new)),
+                    })
+                    .collect();
+                states.push(State::with_classes(next, state.class, state.classes.clone()));
+            }
+        }
+
+        (Self::from_states(states, self.class_count), ids)
+    }
+
+    /// Constructs the DFA accepting, with class 0, exactly the strings that
+    /// `self` rejects. If `self` has several classes, they are not told
+    /// apart: the result accepts the strings matched by none of them.
+    ///
+    /// The sink of `self` rejects every remaining input, so its counterpart
+    /// accepts every remaining input. Since state 0 must stay a non-accepting
+    /// sink, that counterpart is a new accept-all state appended to the end,
+    /// and every transition that led to the sink leads there instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA};
+    /// let digits = RegEx::set(ByteSet::range(b'0', b'9')).plus();
+    /// let dfa = DFA::from(&digits).complement();
+    /// assert!(!dfa.matches("42"));
+    /// assert!(dfa.matches("4x2"));
+    /// assert!(dfa.matches(""));
+    /// ```
+    #[must_use]
+    pub fn complement(&self) -> Self {
+        let all = self.states.len();
+        let flip = |class: Option<usize>| if class.is_some() { None } else { Some(0) };
+
+        let mut states = Vec::with_capacity(all + 1);
+        states.push(State::sink());
+        for state in &self.states[1..] {
+            let next = (0..=255).map(|symbol| {
+                let dest = state.step(symbol);
+                (symbol, if dest == 0 { all } else { dest })
+            }).collect();
+            states.push(State::new(next, flip(state.class)));
+        }
+        states.push(State::new((0..=255).map(|symbol| (symbol, all)).collect(), Some(0)));
+
+        Self::from_states(states, 1)
+    }
+
+    /// Constructs the DFA accepting the strings accepted by both `self` and
+    /// `other`, via the product construction. Strings keep their class in
+    /// `self`.
+    #[must_use]
+    pub fn intersect(&self, other: &DFA) -> Self {
+        product::product(self, other, product::Op::Intersect)
+    }
+
+    /// Constructs the DFA accepting the strings accepted by either `self` or
+    /// `other`, via the product construction. Strings accepted by `self` keep
+    /// their class in `self`, and the rest their class in `other`, so classes
+    /// of the two should be numbered apart if they are to be told apart.
+    #[must_use]
+    pub fn union(&self, other: &DFA) -> Self {
+        product::product(self, other, product::Op::Union)
+    }
+
+    /// Constructs the DFA accepting the strings accepted by `self` but not by
+    /// `other`, via the product construction. Strings keep their class in
+    /// `self`.
+    #[must_use]
+    pub fn difference(&self, other: &DFA) -> Self {
+        product::product(self, other, product::Op::Difference)
     }
 
+    /// Returns `true` iff `self` and `other` assign every string the same
+    /// class (or lack thereof).
+    #[must_use]
+    pub fn equivalent(&self, other: &DFA) -> bool {
+        self.counterexample(other).is_none()
+    }
+
+    /// Returns a shortest string to which `self` and `other` assign different
+    /// classes (or lack thereof), or `None` if they are equivalent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA};
+    /// let digits = RegEx::set(ByteSet::range(b'0', b'9'));
+    /// let dfa1 = DFA::from(&digits.plus());
+    /// let dfa2 = DFA::from(&digits.star());
+    /// assert_eq!(dfa1.counterexample(&dfa2), Some(vec![]));
+    /// assert!(dfa1.equivalent(&dfa1.minimize()));
+    /// ```
+    #[must_use]
+    pub fn counterexample(&self, other: &DFA) -> Option<Vec<u8>> {
+        product::counterexample(self, other)
+    }
+
+    /// Returns a hash of the canonical form of the minimized `self`, such
+    /// that equivalent DFAs, however built and numbered, have the same
+    /// fingerprint, and others differ with overwhelming probability. Only the
+    /// class of each state is hashed, not the other classes it accepts, so
+    /// rules that are shadowed differently but never matched don't count. The
+    /// hash is fixed across platforms and releases, so fingerprints can be
+    /// stored, e.g. to tell whether a regenerated lexer has changed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA};
+    /// let digit = RegEx::set(ByteSet::range(b'0', b'9'));
+    /// let dfa1 = DFA::from(&digit.plus());
+    /// let dfa2 = DFA::from(&digit.then(&digit.star()));
+    /// assert_eq!(dfa1.fingerprint(), dfa2.fingerprint());
+    /// assert_ne!(dfa1.fingerprint(), DFA::from(&digit.star()).fingerprint());
+    /// ```
+    #[must_use]
+    pub fn fingerprint(&self) -> u64 {
+        fingerprint::fingerprint(&self.minimize())
+    }
+
+    /// Converts `self` into a regex recognizing the strings it accepts with
+    /// class 0, via state elimination. This is the language of `self` if it
+    /// has a single class. The result is built through the usual
+    /// constructors, and is correct but not necessarily minimal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA};
+    /// let digits = RegEx::set(ByteSet::range(b'0', b'9')).plus();
+    /// let dfa = DFA::from(&digits);
+    /// assert!(DFA::from(&dfa.to_regex()).equivalent(&dfa));
+    /// ```
+    #[must_use]
+    pub fn to_regex(&self) -> RegEx {
+        eliminate::to_regex(self, 0)
+    }
+
+    /// Converts `self` into one regex per class, where the `i`th recognizes
+    /// the strings that `self` accepts with class `i`. See `to_regex`.
+    #[must_use]
+    pub fn to_regexes(&self) -> Vec<RegEx> {
+        (0..self.class_count()).map(|class| eliminate::to_regex(self, class)).collect()
+    }
+
+    /// Same as `to_regex`, but gives up as soon as any intermediate regex has
+    /// more than `limit` nodes, since state elimination can blow up
+    /// exponentially.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TooLarge` if the limit is exceeded.
+    pub fn to_regex_with_limit(&self, limit: usize) -> Result<RegEx, TooLarge> {
+        eliminate::to_regex_limited(self, 0, Some(limit))
+    }
+
+    /// Same as `to_regexes`, but gives up as soon as any intermediate regex
+    /// has more than `limit` nodes.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TooLarge` if the limit is exceeded.
+    pub fn to_regexes_with_limit(&self, limit: usize) -> Result<Vec<RegEx>, TooLarge> {
+        (0..self.class_count()).map(|class| eliminate::to_regex_limited(self, class, Some(limit))).collect()
+    }
+
+    /// Returns the Graphviz dot representation of `self`, with the default
+    /// `DotOptions`.
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        self.to_dot_with(&DotOptions::default())
+    }
+
+    /// Returns the Graphviz dot representation of `self`. Accepting states
+    /// are drawn as double circles labelled with their class, and transitions
+    /// to the same state are merged into one edge labelled with their bytes.
+    #[must_use]
+    pub fn to_dot_with(&self, options: &DotOptions) -> String {
+        dot::to_dot(self, options)
+    }
+
+    /// Returns `true` iff `text` is accepted. Same as `matches_bytes`.
     #[must_use]
     pub fn matches(&self, text: &str) -> bool {
-        // Note: start index is always 1.
-        self.class(text.bytes().fold(1, |id, byte| { self.step(id, byte) })).is_some()
+        self.matches_bytes(text.as_bytes())
     }
 
+    /// Returns `true` iff `input` is accepted. `input` need not be UTF-8.
     #[must_use]
-    pub fn step(&self, id: usize, symbol: u8) -> usize {
-        match self.states[id].next.get(&symbol) {
-            Some(&next_id) => next_id,
-            None => 0,
+    pub fn matches_bytes(&self, input: &[u8]) -> bool {
+        self.class(self.run(Self::START, input)).is_some()
+    }
+
+    /// Returns `true` iff the string of bytes yielded by `bytes` is accepted.
+    /// Stops consuming `bytes` as soon as the sink state is reached.
+    #[must_use]
+    pub fn matches_iter<I: IntoIterator<Item = u8>>(&self, bytes: I) -> bool {
+        let mut id = Self::START;
+        for byte in bytes {
+            id = self.step(id, byte);
+            if self.is_dead(id) {
+                return false;
+            }
         }
+        self.class(id).is_some()
     }
 
+    /// Returns `true` iff the contents of `reader` are accepted. `reader` is
+    /// read in chunks rather than all at once, and reading stops as soon as
+    /// the sink state is reached.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error encountered whilst reading, other than interrupts.
+    pub fn matches_read<R: Read>(&self, mut reader: R) -> io::Result<bool> {
+        let mut id = Self::START;
+        let mut buf = [0; 8 * 1024];
+        loop {
+            let len = match reader.read(&mut buf) {
+                Ok(0) => return Ok(self.class(id).is_some()),
+                Ok(len) => len,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            for &byte in &buf[..len] {
+                id = self.step(id, byte);
+                if self.is_dead(id) {
+                    return Ok(false);
+                }
+            }
+        }
+    }
+
+    /// Returns `self` with its transitions in one flat table of `256` per
+    /// state, which is the fast path for matching, at the cost of memory.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self` has `u32::MAX` or more states.
+    #[must_use]
+    pub fn to_dense(&self) -> DenseDFA {
+        DenseDFA::new(self)
+    }
+
+    /// Returns a matcher of `self` at its start state, for input arriving in
+    /// chunks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA};
+    /// let digits = RegEx::set(ByteSet::range(b'0', b'9')).plus();
+    /// let dfa = DFA::from(&digits);
+    /// let mut matcher = dfa.matcher();
+    /// matcher.feed(b"12");
+    /// assert_eq!(matcher.is_accepting(), Some(0));
+    /// matcher.feed(b"3x4");
+    /// assert!(matcher.is_dead());
+    /// ```
+    #[must_use]
+    pub fn matcher(&self) -> DfaMatcher<'_> {
+        DfaMatcher::new(self)
+    }
+
+    /// Returns the state reached from state `id` on `symbol`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is not the id of a state.
+    #[must_use]
+    pub fn step(&self, id: usize, symbol: u8) -> usize {
+        debug_assert!(id < self.states.len(), "state {} out of range for a DFA of {} states", id, self.states.len());
+        self.states[id].step(symbol)
+    }
+
+    /// Returns the class of state `id` if it is accepting.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is not the id of a state.
     #[must_use]
     pub fn class(&self, id: usize) -> Option<usize> {
+        debug_assert!(id < self.states.len(), "state {} out of range for a DFA of {} states", id, self.states.len());
         self.states[id].class
     }
 
@@ -57,26 +599,624 @@ impl DFA {
     pub fn states(&self) -> &[State] {
         &self.states
     }
+
+    /// Returns every transition to a state other than the sink, as a source,
+    /// the set of bytes taking it, and a destination, in order of source and
+    /// then destination. Bytes taking no transition lead to the sink.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA};
+    /// let digits = RegEx::set(ByteSet::range(b'0', b'9')).plus();
+    /// let dfa = DFA::from(&digits).minimize();
+    /// let transitions: Vec<_> = dfa.transitions().collect();
+    /// assert_eq!(transitions, [(1, ByteSet::range(b'0', b'9'), 2), (2, ByteSet::range(b'0', b'9'), 2)]);
+    /// ```
+    pub fn transitions(&self) -> impl Iterator<Item = (usize, ByteSet, usize)> + '_ {
+        self.states.iter().enumerate().flat_map(|(id, state)| {
+            state.transitions_grouped().map(move |(set, dest)| (id, set, dest))
+        })
+    }
+
+    /// Returns the state reached by stepping from `state` over `bytes`,
+    /// stopping early if a dead state (see `is_dead`) is reached.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA};
+    /// let digits = RegEx::set(ByteSet::range(b'0', b'9')).plus();
+    /// let dfa = DFA::from(&digits);
+    /// assert!(dfa.class(dfa.run(DFA::START, b"123")).is_some());
+    /// assert_eq!(dfa.run(DFA::START, b"1x3"), 0);
+    /// ```
+    #[must_use]
+    pub fn run(&self, state: usize, bytes: &[u8]) -> usize {
+        LexTable::run(self, state, bytes)
+    }
+
+    /// Same as `run`, but also returns the offset into `bytes` and class of
+    /// the last accepting state visited (including `state` itself, at offset
+    /// 0), if any.
+    #[must_use]
+    pub fn run_with_last_accept(&self, state: usize, bytes: &[u8]) -> (usize, Option<(usize, usize)>) {
+        LexTable::run_with_last_accept(self, state, bytes)
+    }
+
+    /// Returns the length and class of the longest accepted prefix of `input`
+    /// (i.e. the maximal munch), or `None` if not even the empty prefix is
+    /// accepted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA};
+    /// let digits = RegEx::set(ByteSet::range(b'0', b'9')).plus();
+    /// let dfa = DFA::from(&digits);
+    /// assert_eq!(dfa.longest_match(b"123abc"), Some((3, 0)));
+    /// assert_eq!(dfa.longest_match(b"abc"), None);
+    /// ```
+    #[must_use]
+    pub fn longest_match(&self, input: &[u8]) -> Option<(usize, usize)> {
+        self.longest_match_at(input, 0)
+    }
+
+    /// Same as `longest_match`, but for the prefix of `input[start..]`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `start > input.len()`.
+    #[must_use]
+    pub fn longest_match_at(&self, input: &[u8], start: usize) -> Option<(usize, usize)> {
+        LexTable::longest_match_at(self, input, start)
+    }
+
+    /// Constructs a searcher for the leftmost-longest matches of `regex`
+    /// starting anywhere in a haystack, rather than only at its start.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA};
+    /// let word = RegEx::set(ByteSet::range(b'a', b'z')).plus();
+    /// let searcher = DFA::searcher(&word);
+    /// assert_eq!(searcher.find(b"12 ab 3"), Some(3..5));
+    /// ```
+    #[must_use]
+    pub fn searcher(regex: &RegEx) -> SearchDFA {
+        SearchDFA::new(regex)
+    }
+
+    /// Returns, for each class, a shortest string accepted with that class
+    /// (taking the smallest bytes first), or `None` if no string is, as for
+    /// a rule shadowed by earlier rules.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA};
+    /// let digit = RegEx::set(ByteSet::range(b'0', b'9'));
+    /// let dfa = DFA::from(&[digit.plus(), digit.then(&digit)]);
+    /// assert_eq!(dfa.shortest_accepted(), [Some(b"0".to_vec()), None]);
+    /// ```
+    #[must_use]
+    pub fn shortest_accepted(&self) -> Vec<Option<Vec<u8>>> {
+        let mut shortest = vec![None; self.class_count()];
+        let mut remaining = shortest.len();
+
+        // parents[id] = the state and byte from which id was first reached
+        let mut parents = vec![None; self.states.len()];
+        let mut visited = vec![false; self.states.len()];
+        let mut queue = VecDeque::new();
+        visited[0] = true;
+        visited[Self::START] = true;
+        queue.push_back(Self::START);
+
+        while let Some(id) = queue.pop_front() {
+            if remaining == 0 {
+                break;
+            }
+            if let Some(class) = self.class(id) {
+                if shortest[class].is_none() {
+                    let mut string = Vec::new();
+                    let mut node = id;
+                    while let Some((parent, symbol)) = parents[node] {
+                        string.push(symbol);
+                        node = parent;
+                    }
+                    string.reverse();
+                    shortest[class] = Some(string);
+                    remaining -= 1;
+                }
+            }
+            for symbol in 0..=255 {
+                let next = self.step(id, symbol);
+                if !visited[next] {
+                    visited[next] = true;
+                    parents[next] = Some((id, symbol));
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        shortest
+    }
+
+    /// Returns an iterator over every string of length at most `max_len`
+    /// accepted by `self`, with its class, in shortlex order (i.e. ordered by
+    /// length, then lexicographically). Strings are generated lazily, and
+    /// only bytes that avoid the sink are branched on.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA};
+    /// let [a, b] = [b'a', b'b'].map(|x| RegEx::set(ByteSet::point(x)));
+    /// let dfa = DFA::from(&[a.plus(), b.star()]);
+    /// let strings: Vec<_> = dfa.strings_up_to(2).collect();
+    /// assert_eq!(strings, [(b"".to_vec(), 1), (b"a".to_vec(), 0), (b"b".to_vec(), 1), (b"aa".to_vec(), 0), (b"bb".to_vec(), 1)]);
+    /// ```
+    pub fn strings_up_to(&self, max_len: usize) -> impl Iterator<Item = (Vec<u8>, usize)> + '_ {
+        strings::Strings::new(self, max_len)
+    }
+
+    /// Returns a random string accepted by `self`, found by a random walk
+    /// from the start that never strays beyond reach of an accepting state
+    /// (of class `options.class`, if given) within `options.max_len` bytes,
+    /// or `None` if there is no such string. Generation is driven entirely by
+    /// `rng`, so a seeded `rng` gives reproducible output.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA, GenOptions};
+    /// # use rand_pcg::Pcg32;
+    /// # use rand_core::SeedableRng;
+    /// let word = RegEx::set(ByteSet::range(b'a', b'z')).plus();
+    /// let number = RegEx::set(ByteSet::range(b'0', b'9')).plus();
+    /// let dfa = DFA::from(&[word, number]).minimize();
+    /// let options = GenOptions { max_len: 8, class: Some(1), ..GenOptions::default() };
+    /// let string = dfa.generate(&mut Pcg32::seed_from_u64(0), &options).unwrap();
+    /// assert!((1..=8).contains(&string.len()));
+    /// assert!(string.iter().all(u8::is_ascii_digit));
+    /// ```
+    pub fn generate<R: RngCore>(&self, rng: &mut R, options: &GenOptions) -> Option<Vec<u8>> {
+        generate::generate(self, rng, options)
+    }
+
+    /// Returns the byte equivalence classes of `self`: a map from each byte to
+    /// the index of its class, and the number of classes. Two bytes are in
+    /// the same class iff every state sends them to the same state. Classes
+    /// are numbered in order of their smallest byte.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA};
+    /// let digits = RegEx::set(ByteSet::range(b'0', b'9')).plus();
+    /// let (classes, count) = DFA::from(&digits).byte_classes();
+    /// assert_eq!(count, 2);
+    /// assert_eq!(classes[usize::from(b'0')], classes[usize::from(b'9')]);
+    /// assert_ne!(classes[usize::from(b'0')], classes[usize::from(b'a')]);
+    /// ```
+    #[must_use]
+    pub fn byte_classes(&self) -> ([u8; 256], usize) {
+        let mut classes = [0_u8; 256];
+        let mut count = 1;
+        // refine the classes by the transitions of each state in turn
+        for id in 1..self.states.len() {
+            let mut refined: HashMap<(u8, usize), u8> = HashMap::new();
+            for symbol in 0..=255 {
+                let key = (classes[usize::from(symbol)], self.step(id, symbol));
+                let next = u8::try_from(refined.len()).unwrap_or(u8::MAX);
+                classes[usize::from(symbol)] = *refined.entry(key).or_insert(next);
+            }
+            count = refined.len();
+        }
+        (classes, count)
+    }
+
+    /// Returns a summary of the size of `self`, whose `Display` is suited to
+    /// build logs.
+    #[must_use]
+    pub fn stats(&self) -> DfaStats {
+        stats::stats(self)
+    }
+
+    /// Returns the number of bytes `self` holds on the heap, by the capacity
+    /// of each of its buffers: those of its states and, in each, of its
+    /// transitions and classes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA};
+    /// let dfa = DFA::from(&RegEx::set(ByteSet::range(b'a', b'z')).plus()).minimize();
+    /// // far less than a dense table of 256 entries per state
+    /// assert!(dfa.memory_usage() < 256 * dfa.states().len());
+    /// ```
+    #[must_use]
+    pub fn memory_usage(&self) -> usize {
+        let states: usize = self.states.iter()
+            .map(|state| heap_size(&state.next) + heap_size(&state.classes))
+            .sum();
+        heap_size(&self.states) + states + heap_size(&self.dead)
+    }
+
+    /// Returns every class accepted in `state`, in ascending order, of which
+    /// `class` returns the one with the highest priority. Only `DFA::from`,
+    /// `DFA::from_rules` and `minimize_keeping_classes` keep track of more
+    /// than that one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA};
+    /// let lower = RegEx::set(ByteSet::range(b'a', b'z'));
+    /// let dfa = DFA::from(&[lower.plus(), lower.then(&lower)]);
+    /// let state = dfa.run(DFA::START, b"ab");
+    /// assert_eq!(dfa.classes_at(state), [0, 1]);
+    /// assert_eq!(dfa.class(state), Some(0));
+    /// ```
+    #[must_use]
+    pub fn classes_at(&self, state: usize) -> &[usize] {
+        debug_assert!(state < self.states.len(), "state {} out of range for a DFA of {} states", state, self.states.len());
+        &self.states[state].classes
+    }
+
+    /// Returns each set of classes accepted together in some reachable
+    /// state, with a shortest string (taking the smallest bytes first) on
+    /// which they are, in order of those strings. Such strings are ambiguous
+    /// between the rules of the classes: only the one with the highest
+    /// priority ever matches them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA};
+    /// let lit = |s: &[u8]| s.iter().fold(RegEx::empty(), |re, &c| re.then(&RegEx::set(ByteSet::point(c))));
+    /// let lower = RegEx::set(ByteSet::range(b'a', b'z')).plus();
+    /// let dfa = DFA::from(&[lit(b"if"), lit(b"in"), lower]);
+    /// assert_eq!(dfa.ambiguities(), [(vec![0, 2], b"if".to_vec()), (vec![1, 2], b"in".to_vec())]);
+    /// ```
+    #[must_use]
+    pub fn ambiguities(&self) -> Vec<(Vec<usize>, Vec<u8>)> {
+        let mut ambiguities: Vec<(Vec<usize>, Vec<u8>)> = Vec::new();
+
+        // breadth-first, so that each state is first reached by its
+        // shortest string
+        let mut visited = vec![false; self.states.len()];
+        visited[Self::START] = true;
+        let mut queue = VecDeque::from(vec![(Self::START, Vec::new())]);
+        while let Some((id, string)) = queue.pop_front() {
+            for (symbol, dest) in self.states[id].edges() {
+                if !visited[dest] {
+                    visited[dest] = true;
+                    let mut next = string.clone();
+                    next.push(symbol);
+                    queue.push_back((dest, next));
+                }
+            }
+            let classes = &self.states[id].classes;
+            if classes.len() >= 2 && ambiguities.iter().all(|(other, _)| other != classes) {
+                ambiguities.push((classes.clone(), string));
+            }
+        }
+
+        ambiguities
+    }
+
+    /// Returns, in ascending order, the classes that are never the class of
+    /// any reachable state. The rule of such a class never makes a token: it
+    /// is either shadowed, i.e. every string it matches is claimed by a rule
+    /// of higher priority, or matches nothing at all.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA};
+    /// let lit = |s: &[u8]| s.iter().fold(RegEx::empty(), |re, &c| re.then(&RegEx::set(ByteSet::point(c))));
+    /// let ident = RegEx::set(ByteSet::range(b'a', b'z')).plus();
+    /// // the identifier rule claims every keyword
+    /// let dfa = DFA::from(&[ident, lit(b"if"), lit(b"else")]);
+    /// assert_eq!(dfa.unreachable_classes(), [1, 2]);
+    /// ```
+    #[must_use]
+    pub fn unreachable_classes(&self) -> Vec<usize> {
+        let reachable = self.reachable_from(Self::START);
+        let mut accepted = vec![false; self.class_count];
+        for (state, _) in self.states.iter().zip(&reachable).filter(|&(_, &reachable)| reachable) {
+            if let Some(class) = state.class {
+                accepted[class] = true;
+            }
+        }
+        (0..self.class_count).filter(|&class| !accepted[class]).collect()
+    }
+
+    /// Returns `true` iff no string is accepted, i.e. no accepting state is
+    /// reachable from the start state.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        !self.accepting_reachable_from(Self::START)
+    }
+
+    /// Returns `true` iff every string is accepted, i.e. every state reachable
+    /// from the start state is accepting, and none has a transition (explicit
+    /// or missing) to the sink.
+    #[must_use]
+    pub fn is_universal(&self) -> bool {
+        self.reachable_from(Self::START).into_iter().enumerate().filter(|&(_, reachable)| reachable).all(|(id, _)| {
+            id != 0 && self.class(id).is_some() && (0..=255).all(|symbol| self.step(id, symbol) != 0)
+        })
+    }
+
+    /// Returns `true` iff an accepting state is reachable from `state`,
+    /// including `state` itself.
+    #[must_use]
+    pub fn accepting_reachable_from(&self, state: usize) -> bool {
+        !self.is_dead(state)
+    }
+
+    /// Returns `true` iff no accepting state is reachable from `state`, as
+    /// for the sink, so that no further input can be accepted. This is
+    /// computed once on construction, and lets runs over `self` stop as soon
+    /// as a match becomes impossible, rather than only on reaching the sink.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `state` is not the id of a state.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA};
+    /// let digits = RegEx::set(ByteSet::range(b'0', b'9')).plus();
+    /// let z = RegEx::set(ByteSet::point(b'z'));
+    /// // no string of digits ends in z, but the DFA does not know it until
+    /// // it is built
+    /// let any = RegEx::set(ByteSet::universe()).star();
+    /// let dfa = DFA::from(&digits.and(&any.then(&z)));
+    /// let state = dfa.step(DFA::START, b'1');
+    /// assert_ne!(state, 0);
+    /// assert!(dfa.is_dead(state));
+    /// ```
+    #[must_use]
+    pub fn is_dead(&self, state: usize) -> bool {
+        self.dead[state]
+    }
+
+    /// Returns the number of strings of length `len` that are accepted (with
+    /// any class), saturating at `u128::MAX`.
+    #[must_use]
+    pub fn count_matches_of_len(&self, len: usize) -> u128 {
+        // counts[id] = number of strings of the current length leading to id
+        let mut counts = vec![0_u128; self.states.len()];
+        counts[Self::START] = 1;
+
+        for _ in 0..len {
+            let mut next = vec![0_u128; self.states.len()];
+            for (id, &count) in counts.iter().enumerate().filter(|(_, &count)| count > 0) {
+                for symbol in 0..=255 {
+                    let dest = &mut next[self.step(id, symbol)];
+                    *dest = dest.saturating_add(count);
+                }
+            }
+            counts = next;
+        }
+
+        counts.iter().enumerate()
+            .filter(|&(id, _)| self.class(id).is_some())
+            .fold(0, |total, (_, &count)| total.saturating_add(count))
+    }
+}
+
+impl State {
+    /// Returns the id of the state reached from `self` on `symbol`.
+    #[must_use]
+    pub fn step(&self, symbol: u8) -> usize {
+        // most states have a handful of ranges, for which a linear scan
+        // beats a binary search
+        let i = if self.next.len() <= 16 {
+            self.next.iter().position(|&(_, hi, _)| symbol <= hi).unwrap_or(self.next.len())
+        } else {
+            self.next.partition_point(|&(_, hi, _)| hi < symbol)
+        };
+        match self.next.get(i) {
+            Some(&(lo, _, dest)) if lo <= symbol => dest,
+            _ => 0,
+        }
+    }
+
+    /// Returns the transitions out of `self` to states other than the sink,
+    /// each with the set of bytes taking it, in order of destination.
+    pub fn transitions_grouped(&self) -> impl Iterator<Item = (ByteSet, usize)> {
+        let mut sets: BTreeMap<usize, ByteSet> = BTreeMap::new();
+        for &(lo, hi, dest) in &self.next {
+            sets.entry(dest).or_insert_with(ByteSet::empty).insert_range(lo, hi);
+        }
+        sets.into_iter().map(|(dest, set)| (set, dest))
+    }
+}
+
+impl LexTable for DFA {
+    fn start_state(&self) -> usize {
+        DFA::START
+    }
+
+    fn step(&self, state: usize, symbol: u8) -> usize {
+        DFA::step(self, state, symbol)
+    }
+
+    fn class(&self, state: usize) -> Option<usize> {
+        DFA::class(self, state)
+    }
+
+    fn sink(&self) -> usize {
+        0
+    }
+
+    fn class_count(&self) -> usize {
+        DFA::class_count(self)
+    }
+
+    fn is_dead(&self, state: usize) -> bool {
+        DFA::is_dead(self, state)
+    }
+}
+
+impl fmt::Display for DFA {
+    /// Writes one row per state: its id, its class if accepting, and its
+    /// transitions grouped by destination, with those to the sink last.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "0: sink")?;
+        for (id, state) in self.states.iter().enumerate().skip(1) {
+            write!(f, "{id}")?;
+            if let Some(class) = state.class {
+                write!(f, " (class {class})")?;
+            }
+            f.write_str(":")?;
+
+            let sets: Vec<_> = state.transitions_grouped().collect();
+            for (i, (set, dest)) in sets.iter().enumerate() {
+                f.write_str(if i == 0 { " " } else { ", " })?;
+                write_runs(f, set)?;
+                write!(f, " -> {dest}")?;
+            }
+            if sets.iter().map(|(set, _)| set.len()).sum::<u32>() < 256 {
+                f.write_str(if sets.is_empty() { " * -> sink" } else { ", * -> sink" })?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
 }
 
 // =================
 // === INTERNALS ===
 // =================
 
+impl DFA {
+    /// States must be well-formed: state 0 is the sink, state 1 the start,
+    /// and every class accepted is below `class_count`.
+    pub(crate) fn from_states(states: Vec<State>, class_count: usize) -> Self {
+        debug_assert!(states.iter().flat_map(|state| &state.classes).all(|&class| class < class_count));
+        let mut dfa = Self { states, dead: Vec::new(), class_count };
+        dfa.dead = dfa.live(|class| class.is_some()).into_iter().map(|live| !live).collect();
+        dfa
+    }
+
+    /// Returns the number of rules `self` was built from, whether or not
+    /// any state accepts their class.
+    pub(crate) fn class_count(&self) -> usize {
+        self.class_count
+    }
+
+    /// Returns, for each state, whether it is reachable from `state`.
+    pub(crate) fn reachable_from(&self, state: usize) -> Vec<bool> {
+        let mut reachable = vec![false; self.states.len()];
+        reachable[state] = true;
+        let mut stack = vec![state];
+        while let Some(id) = stack.pop() {
+            for symbol in 0..=255 {
+                let next = self.step(id, symbol);
+                if !reachable[next] {
+                    reachable[next] = true;
+                    stack.push(next);
+                }
+            }
+        }
+        reachable
+    }
+
+    /// Returns, for each state, whether a state whose class satisfies
+    /// `accepting` is reachable from it, e.g. any accepting state for
+    /// `|class| class.is_some()`.
+    pub(crate) fn live(&self, accepting: impl Fn(Option<usize>) -> bool) -> Vec<bool> {
+        let mut prev = vec![Vec::new(); self.states.len()];
+        for (id, state) in self.states.iter().enumerate() {
+            for &(_, _, dest) in &state.next {
+                prev[dest].push(id);
+            }
+        }
+
+        let mut live: Vec<bool> = self.states.iter().map(|state| accepting(state.class)).collect();
+        let mut stack: Vec<usize> = (0..self.states.len()).filter(|&id| live[id]).collect();
+        while let Some(id) = stack.pop() {
+            for &source in &prev[id] {
+                if !live[source] {
+                    live[source] = true;
+                    stack.push(source);
+                }
+            }
+        }
+        live
+    }
+}
+
 impl State {
-    fn new(next: HashMap<u8, usize>, class: Option<usize>) -> Self {
-        Self {
-            next,
+    pub(crate) fn new(next: BTreeMap<u8, usize>, class: Option<usize>) -> Self {
+        Self::with_classes(next, class, class.into_iter().collect())
+    }
+
+    /// `classes` must be ascending, and contain `class` iff it is `Some`.
+    pub(crate) fn with_classes(next: BTreeMap<u8, usize>, class: Option<usize>, classes: Vec<usize>) -> Self {
+        let mut state = Self {
+            next: Vec::new(),
             class,
+            classes,
+        };
+        for (symbol, dest) in next {
+            state.insert(symbol, dest);
+        }
+        state
+    }
+
+    pub(crate) fn sink() -> Self {
+        Self::new(BTreeMap::new(), None)
+    }
+
+    /// Adds a transition on `symbol`, which must not have one yet.
+    pub(crate) fn insert(&mut self, symbol: u8, dest: usize) {
+        self.insert_range(symbol, symbol, dest);
+    }
+
+    /// Adds a transition on each byte of `set`, none of which may have one yet.
+    pub(crate) fn insert_set(&mut self, set: &ByteSet, dest: usize) {
+        for range in set.ranges() {
+            self.insert_range(*range.start(), *range.end(), dest);
+        }
+    }
+
+    fn insert_range(&mut self, lo: u8, hi: u8, dest: usize) {
+        if dest == 0 {
+            return;
+        }
+        let i = self.next.partition_point(|&(_, end, _)| end < lo);
+        debug_assert!(self.next.get(i).is_none_or(|&(start, _, _)| hi < start), "transition on {} already exists", lo);
+
+        // merge with the ranges either side, if adjacent and to dest
+        let joins_prev = i > 0 && self.next[i - 1].2 == dest && u16::from(self.next[i - 1].1) + 1 == u16::from(lo);
+        let joins_next = self.next.get(i).is_some_and(|&(start, _, next)| next == dest && u16::from(hi) + 1 == u16::from(start));
+        match (joins_prev, joins_next) {
+            (true, true) => {
+                self.next[i - 1].1 = self.next[i].1;
+                self.next.remove(i);
+            },
+            (true, false) => self.next[i - 1].1 = hi,
+            (false, true) => self.next[i].0 = lo,
+            (false, false) => self.next.insert(i, (lo, hi, dest)),
         }
     }
 
-    fn sink() -> Self {
-        Self::new(HashMap::new(), None)
+    /// Returns the transitions out of `self` to states other than the sink,
+    /// one per symbol, in order of symbol.
+    pub(crate) fn edges(&self) -> impl Iterator<Item = (u8, usize)> + '_ {
+        self.next.iter().flat_map(|&(lo, hi, dest)| (lo..=hi).map(move |symbol| (symbol, dest)))
     }
 }
 
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 struct RegExVec(Vec<RegEx>);
 
 impl RegExVec {
@@ -89,69 +1229,131 @@ impl RegExVec {
         Self(vec![none; size])
     }
 
-    fn deriv(&self, a: u8) -> RegExVec {
-        Self(self.0.iter().map(|node| node.deriv(a)).collect())
+    fn deriv(&self, a: u8, cache: &mut DerivCache) -> RegExVec {
+        Self(self.0.iter().map(|node| node.deriv_cached(a, cache)).collect())
     }
 
     fn class(&self) -> Option<usize> {
         self.0.iter().position(RegEx::is_nullable)
     }
+
+    /// Returns the index of the nullable regex with the highest priority,
+    /// ties going to the earliest.
+    fn class_by_priority(&self, priorities: &[Priority]) -> Option<usize> {
+        self.0.iter().enumerate()
+            .filter(|(_, node)| node.is_nullable())
+            .max_by_key(|&(i, _)| (priorities[i], Reverse(i)))
+            .map(|(i, _)| i)
+    }
 }
 
-struct DFABuilder {
+struct DFABuilder<'o> {
     states: Vec<State>,
-    re2idx: BTreeMap<RegExVec, usize>,
+    re2idx: HashMap<RegExVec, usize>,
+    derivs: Derivs,
+    classes: ClassCache,
+    priorities: Vec<Priority>,
+    limits: BuildLimits,
+    transitions: usize,
+    observer: &'o mut dyn BuildObserver,
 }
 
-impl DFABuilder {
-    fn build(start: &RegExVec) -> DFA {
+impl<'o> DFABuilder<'o> {
+    fn build(start: &RegExVec, priorities: Vec<Priority>, limits: BuildLimits, observer: &'o mut dyn BuildObserver) -> Result<DFA, BuildError> {
+        let class_count = priorities.len();
+        Ok(DFA::from_states(Self::explored(start, priorities, limits, observer)?.states, class_count))
+    }
+
+    fn build_unlimited(start: &RegExVec, priorities: Vec<Priority>) -> DFA {
+        match DFABuilder::build(start, priorities, BuildLimits::default(), &mut Unobserved) {
+            Ok(dfa) => dfa,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Returns the builder after exploring every state reachable from
+    /// `start`, given the priority of each of its regexes.
+    fn explored(start: &RegExVec, priorities: Vec<Priority>, limits: BuildLimits, observer: &'o mut dyn BuildObserver) -> Result<Self, BuildError> {
         // s0 = sink state
         let states = vec![State::sink()];
         let re2idx = once((RegExVec::sink(start.0.len()), 0_usize)).collect();
         
-        let mut builder = Self { states, re2idx };
+        let mut builder = Self {
+            states,
+            re2idx,
+            derivs: Derivs::new(),
+            classes: ClassCache::new(),
+            priorities,
+            limits,
+            transitions: 0,
+            observer,
+        };
         
         // s1 = start state
-        builder.add_state(start);
+        builder.add_state(start)?;
         
-        builder.explore(start, 1);
+        builder.explore(start, DFA::START)?;
 
-        DFA {
-            states: builder.states,
-        }
+        Ok(builder)
     }
 
-    fn add_state(&mut self, q: &RegExVec) -> usize {
+    fn add_state(&mut self, q: &RegExVec) -> Result<usize, BuildError> {
         let idx = self.states.len();
+        if idx >= self.limits.max_states {
+            return Err(BuildError::StateLimitExceeded { built: idx });
+        }
         self.re2idx.insert(q.clone(), idx);
-        self.states.push(State::new(HashMap::new(), q.class()));
-        idx
+        let classes = q.0.iter().positions(RegEx::is_nullable).collect();
+        self.states.push(State::with_classes(BTreeMap::new(), q.class_by_priority(&self.priorities), classes));
+        self.observer.on_state_added(&q.0, idx);
+        Ok(idx)
     }
 
-    fn explore(&mut self, q: &RegExVec, i: usize) {
-        for set in approx_deriv_classes_vec(q) {
-            self.goto(q, i, &set);
+    fn explore(&mut self, q: &RegExVec, i: usize) -> Result<(), BuildError> {
+        // visit classes in order of their smallest byte, so that states are
+        // numbered deterministically
+        let mut classes: Vec<ByteSet> = approx_deriv_classes_vec(q, &mut self.classes).into_iter().collect();
+        classes.sort_by_key(ByteSet::smallest);
+        let derivs = self.derivs.of(q, &classes);
+        for (set, qc) in classes.iter().zip(&derivs) {
+            self.goto(i, set, qc)?;
         }
+        Ok(())
     }
 
-    fn goto(&mut self, q: &RegExVec, i: usize, set: &ByteSet) {
-        let c = set.smallest().unwrap();
-        let qc = &q.deriv(c);
-
-        if let Some(&j) = self.re2idx.get(qc) {
-            for a in set.bytes() {
-                self.states[i].next.insert(a, j);
-            }
-        } else {
-            let j = self.add_state(qc);
-            for a in set.bytes() {
-                self.states[i].next.insert(a, j);
-            }
-            self.explore(qc, j);
+    fn goto(&mut self, i: usize, set: &ByteSet, qc: &RegExVec) -> Result<(), BuildError> {
+        let (j, is_new) = match self.re2idx.get(qc) {
+            Some(&j) => (j, false),
+            None => (self.add_state(qc)?, true),
+        };
+        self.states[i].insert_set(set, j);
+        if j != 0 {
+            self.transitions += set.bytes().count();
         }
+        if self.transitions > self.limits.max_transitions {
+            return Err(BuildError::TransitionLimitExceeded { built: self.transitions });
+        }
+        if is_new {
+            self.explore(qc, j)?;
+        }
+        Ok(())
     }
 }
 
+/// Returns the overlaps between `regexes`: for each state of their joint DFA,
+/// the (ascending) indices of the regexes nullable in that state, where there
+/// are at least 2.
+pub(crate) fn overlaps(regexes: &[RegEx]) -> Vec<Vec<usize>> {
+    let mut unobserved = Unobserved;
+    let Ok(builder) = DFABuilder::explored(&RegExVec::new(regexes.to_vec()), vec![0; regexes.len()], BuildLimits::default(), &mut unobserved) else {
+        unreachable!()
+    };
+    builder.re2idx.keys()
+        .map(|q| q.0.iter().positions(RegEx::is_nullable).collect::<Vec<_>>())
+        .filter(|nullable| nullable.len() >= 2)
+        .collect()
+}
+
 fn cross<'a, B: IntoIterator<Item = &'a ByteSet>>(set1: &HashSet<ByteSet>, set2: B) -> HashSet<ByteSet> {
     set2.into_iter().flat_map(|t| {
         set1.iter().filter_map(move |s| {
@@ -161,54 +1363,93 @@ fn cross<'a, B: IntoIterator<Item = &'a ByteSet>>(set1: &HashSet<ByteSet>, set2:
     }).collect()
 }
 
-// TODO: memoize
-fn approx_deriv_classes(root: &RegEx) -> HashSet<ByteSet> {
-    let mut stack = vec![root];
-    let mut charsets: HashSet<ByteSet> = once(ByteSet::universe()).collect();
-    
-    while let Some(node) = stack.pop() {
-        match node.operator() {
-            Operator::None | Operator::Epsilon => {
-                // Do nothing. C(eps) = {universe}, so C(r) ^ C(eps) = C(r).
-            },
-            Operator::Set(set) => {
-                // TODO: set cannot be empty?
-                if !set.is_empty() && !set.is_universe() {
-                    let cset = &set.complement();
-                    charsets = cross(&charsets, vec![set, cset].into_iter());
-                }
-            },
-            Operator::Cat(children) => {
-                let mut tail = &children[..];
-                while let Some(head) = tail.first() {
-                    stack.push(head);
-                    if !head.is_nullable() {
-                        break;
-                    }
-                    tail = &tail[1..];
-                }
-            },
-            Operator::Star(child) | Operator::Not(child) => {
-                stack.push(child);
-            },
-            Operator::Or(children) | Operator::And(children) => {
-                for child in children {
-                    stack.push(child);
-                }
-            },
-        }
+/// Memo table for `approx_deriv_classes`, keyed on subexpression.
+type ClassCache = HashMap<RegEx, Rc<HashSet<ByteSet>>>;
+
+pub(crate) fn approx_deriv_classes(root: &RegEx) -> HashSet<ByteSet> {
+    approx_deriv_classes_cached(root, &mut ClassCache::new()).as_ref().clone()
+}
+
+/// Same as `approx_deriv_classes`, but the classes of every subexpression
+/// are memoized in `cache`, as subexpressions recur across the states of a
+/// DFA.
+fn approx_deriv_classes_cached(node: &RegEx, cache: &mut ClassCache) -> Rc<HashSet<ByteSet>> {
+    if let Some(charsets) = cache.get(node) {
+        return Rc::clone(charsets);
     }
-    
+
+    let universe = || once(ByteSet::universe()).collect::<HashSet<_>>();
+    let meet = |children: &mut dyn Iterator<Item = &RegEx>, cache: &mut ClassCache| {
+        children.fold(universe(), |acc, child| cross(&acc, approx_deriv_classes_cached(child, cache).iter()))
+    };
+
+    let charsets = match node.operator() {
+        Operator::None | Operator::Epsilon => {
+            // C(eps) = {universe}, so C(r) ^ C(eps) = C(r).
+            Rc::new(universe())
+        },
+        Operator::Set(set) => {
+            if set.is_empty() || set.is_universe() {
+                Rc::new(universe())
+            } else {
+                Rc::new(vec![set.clone(), set.complement()].into_iter().collect())
+            }
+        },
+        Operator::Cat(children) => {
+            // up to and including the first non-nullable child
+            let nullable = children.iter().take_while(|child| child.is_nullable()).count();
+            Rc::new(meet(&mut children.iter().take(nullable + 1), cache))
+        },
+        Operator::Star(child) | Operator::Not(child) => {
+            approx_deriv_classes_cached(child, cache)
+        },
+        Operator::Or(children) | Operator::And(children) | Operator::Interleave(children) => {
+            Rc::new(meet(&mut children.iter(), cache))
+        },
+    };
+
+    cache.insert(node.clone(), Rc::clone(&charsets));
     charsets
 }
 
-fn approx_deriv_classes_vec(root: &RegExVec) -> HashSet<ByteSet> {
+fn approx_deriv_classes_vec(root: &RegExVec, cache: &mut ClassCache) -> HashSet<ByteSet> {
     root.0.iter().fold(once(ByteSet::universe()).collect(), |acc, x| {
-        cross(&acc, &approx_deriv_classes(x))
+        cross(&acc, approx_deriv_classes_cached(x, cache).iter())
     })
 }
 
 mod hopcroft;
+pub use hopcroft::MinimizeReport;
+mod derivs;
+use derivs::Derivs;
+mod product;
+mod dot;
+pub use dot::DotOptions;
+mod binary;
+pub use binary::{DfaRef, FormatError};
+mod stats;
+pub use stats::DfaStats;
+mod fingerprint;
+mod generate;
+pub use generate::GenOptions;
+mod matcher;
+pub use matcher::DfaMatcher;
+mod dense;
+pub use dense::DenseDFA;
+#[cfg(feature = "serde")]
+mod serde;
+pub(crate) mod eliminate;
+pub(crate) mod strings;
+mod search;
+pub use search::{SearchDFA, FindIter};
+mod lazy;
+pub use lazy::{LazyDFA, LazyDFABuilder, Eviction};
+mod limits;
+pub use limits::{BuildLimits, BuildError};
+mod observer;
+pub use observer::BuildObserver;
+use observer::Unobserved;
+pub use eliminate::TooLarge;
 
 #[cfg(test)]
 mod tests;
\ No newline at end of file
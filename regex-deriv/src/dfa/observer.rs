@@ -0,0 +1,21 @@
+use super::{BuildError, DfaStats, RegEx};
+
+/// Callbacks made during construction of a DFA by
+/// `DFA::try_from_rules_observed`, e.g. for reporting progress on large
+/// builds, or for finding which rules blow up. Each does nothing by default.
+pub trait BuildObserver {
+    /// Called when state `id` is added. Its `regexes` are the derivatives of
+    /// the rules, in order, by the first string found leading to it.
+    fn on_state_added(&mut self, _regexes: &[RegEx], _id: usize) {}
+
+    /// Called once construction succeeds, with the stats of the DFA built.
+    fn on_finished(&mut self, _stats: &DfaStats) {}
+
+    /// Called if construction is given up, with the limit that was exceeded.
+    fn on_limit_exceeded(&mut self, _error: &BuildError) {}
+}
+
+/// Observer of the constructions no one is observing.
+pub(crate) struct Unobserved;
+
+impl BuildObserver for Unobserved {}
@@ -0,0 +1,134 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use super::{ByteSet, DFA, State};
+
+type Pair = (usize, usize);
+
+/// Boolean operation performed by the product construction.
+#[derive(Clone, Copy)]
+pub(crate) enum Op {
+    Intersect,
+    Union,
+    Difference,
+}
+
+impl Op {
+    /// Returns the class of a pair of states with classes `a` and `b`.
+    fn class(self, a: Option<usize>, b: Option<usize>) -> Option<usize> {
+        match self {
+            Op::Intersect  => b.and(a),
+            Op::Union      => a.or(b),
+            Op::Difference => if b.is_some() { None } else { a },
+        }
+    }
+
+    /// Returns `true` if no string is accepted from the pair of states `a`
+    /// and `b`, judging only by whether either is the sink.
+    fn is_dead(self, a: usize, b: usize) -> bool {
+        match self {
+            Op::Intersect  => a == 0 || b == 0,
+            Op::Union      => a == 0 && b == 0,
+            Op::Difference => a == 0,
+        }
+    }
+}
+
+/// Constructs the DFA of `op` applied to `dfa1` and `dfa2`, over the pairs
+/// of states reachable from the pair of start states. Transitions out of a
+/// pair are found by intersecting the transitions of each state grouped by
+/// destination, rather than by stepping on every byte.
+pub(crate) fn product(dfa1: &DFA, dfa2: &DFA, op: Op) -> DFA {
+    let classes1: Vec<_> = (0..dfa1.states.len()).map(|id| transition_classes(dfa1, id)).collect();
+    let classes2: Vec<_> = (0..dfa2.states.len()).map(|id| transition_classes(dfa2, id)).collect();
+
+    let mut states = vec![State::sink()];
+    let mut pair2idx: HashMap<Pair, usize> = HashMap::new();
+    let mut stack = Vec::new();
+
+    // the start pair is state 1, even if dead
+    states.push(State::new(BTreeMap::new(), op.class(dfa1.class(DFA::START), dfa2.class(DFA::START))));
+    pair2idx.insert((DFA::START, DFA::START), DFA::START);
+    stack.push((DFA::START, DFA::START));
+
+    while let Some((p, q)) = stack.pop() {
+        let i = pair2idx[&(p, q)];
+        for (&dest1, set1) in &classes1[p] {
+            for (&dest2, set2) in &classes2[q] {
+                if op.is_dead(dest1, dest2) {
+                    continue;
+                }
+                let set = set1.intersection(set2);
+                if set.is_empty() {
+                    continue;
+                }
+                let j = *pair2idx.entry((dest1, dest2)).or_insert_with(|| {
+                    states.push(State::new(BTreeMap::new(), op.class(dfa1.class(dest1), dfa2.class(dest2))));
+                    stack.push((dest1, dest2));
+                    states.len() - 1
+                });
+                states[i].insert_set(&set, j);
+            }
+        }
+    }
+
+    let class_count = match op {
+        Op::Union => dfa1.class_count.max(dfa2.class_count),
+        Op::Intersect | Op::Difference => dfa1.class_count,
+    };
+    DFA::from_states(states, class_count)
+}
+
+/// Returns a shortest string to which `dfa1` and `dfa2` assign different
+/// classes, found by a breadth-first search over pairs of states reachable
+/// from the pair of start states, or `None` if there is no such string.
+pub(crate) fn counterexample(dfa1: &DFA, dfa2: &DFA) -> Option<Vec<u8>> {
+    let classes1: Vec<_> = (0..dfa1.states.len()).map(|id| transition_classes(dfa1, id)).collect();
+    let classes2: Vec<_> = (0..dfa2.states.len()).map(|id| transition_classes(dfa2, id)).collect();
+
+    // parents[pair] = the pair and byte from which pair was first reached
+    let mut parents: HashMap<Pair, Option<(Pair, u8)>> = HashMap::new();
+    let mut queue = VecDeque::new();
+    parents.insert((DFA::START, DFA::START), None);
+    queue.push_back((DFA::START, DFA::START));
+
+    while let Some((p, q)) = queue.pop_front() {
+        if dfa1.class(p) != dfa2.class(q) {
+            let mut string = Vec::new();
+            let mut pair = (p, q);
+            while let Some((parent, symbol)) = parents[&pair] {
+                string.push(symbol);
+                pair = parent;
+            }
+            string.reverse();
+            return Some(string)
+        }
+        let mut edges: Vec<_> = classes1[p].iter()
+            .flat_map(|(&dest1, set1)| classes2[q].iter().filter_map(move |(&dest2, set2)| {
+                set1.intersection(set2).smallest().map(|symbol| (symbol, (dest1, dest2)))
+            }))
+            .collect();
+        edges.sort_unstable();
+        for (symbol, pair) in edges {
+            parents.entry(pair).or_insert_with(|| {
+                queue.push_back(pair);
+                Some(((p, q), symbol))
+            });
+        }
+    }
+
+    None
+}
+
+// =================
+// === INTERNALS ===
+// =================
+
+/// Returns the transitions out of state `id` grouped by destination, where
+/// missing transitions lead to the sink.
+fn transition_classes(dfa: &DFA, id: usize) -> BTreeMap<usize, ByteSet> {
+    let mut classes: BTreeMap<usize, ByteSet> = dfa.states[id].transitions_grouped().map(|(set, dest)| (dest, set)).collect();
+    let missing = classes.values().fold(ByteSet::universe(), |missing, set| missing.difference(set));
+    if !missing.is_empty() {
+        classes.insert(0, missing);
+    }
+    classes
+}
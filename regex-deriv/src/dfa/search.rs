@@ -0,0 +1,94 @@
+use std::ops::Range;
+use super::{ByteSet, DFA, RegEx};
+
+/// Automata for finding the leftmost-longest matches of a regex anywhere in
+/// a haystack, as constructed by `DFA::searcher`.
+///
+/// A match starts at an offset iff some prefix of the rest of the haystack
+/// matches the regex, which is found for every offset at once by running a
+/// DFA of `.*` followed by the reversed regex backwards over the haystack.
+/// The longest match from the leftmost start is then found by running the
+/// DFA of the regex forwards.
+pub struct SearchDFA {
+    forward: DFA,
+    reverse: DFA,
+}
+
+impl SearchDFA {
+    pub(crate) fn new(regex: &RegEx) -> Self {
+        let any = RegEx::set(ByteSet::universe()).star();
+        Self {
+            forward: DFA::from(regex).minimize(),
+            reverse: DFA::from(&any.then(&regex.reverse())).minimize(),
+        }
+    }
+
+    /// Returns the span of the leftmost-longest match in `haystack`, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA};
+    /// let digits = RegEx::set(ByteSet::range(b'0', b'9')).plus();
+    /// let searcher = DFA::searcher(&digits);
+    /// assert_eq!(searcher.find(b"abc 123 45"), Some(4..7));
+    /// assert_eq!(searcher.find(b"abc"), None);
+    /// ```
+    #[must_use]
+    pub fn find(&self, haystack: &[u8]) -> Option<Range<usize>> {
+        self.find_iter(haystack).next()
+    }
+
+    /// Returns an iterator over the successive non-overlapping
+    /// leftmost-longest matches in `haystack`. An empty match is never
+    /// followed directly by another match at the same offset.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA};
+    /// let digits = RegEx::set(ByteSet::range(b'0', b'9')).plus();
+    /// let searcher = DFA::searcher(&digits);
+    /// let spans: Vec<_> = searcher.find_iter(b"abc 123 45").collect();
+    /// assert_eq!(spans, [4..7, 8..10]);
+    /// ```
+    #[must_use]
+    pub fn find_iter<'s, 'h>(&'s self, haystack: &'h [u8]) -> FindIter<'s, 'h> {
+        FindIter { searcher: self, haystack, starts: None, pos: 0 }
+    }
+}
+
+/// Iterator over the matches of a `SearchDFA` in a haystack, as returned by
+/// `SearchDFA::find_iter`.
+pub struct FindIter<'s, 'h> {
+    searcher: &'s SearchDFA,
+    haystack: &'h [u8],
+    // starts[i] iff a match starts at offset i, computed on the first call
+    starts: Option<Vec<bool>>,
+    pos: usize,
+}
+
+impl Iterator for FindIter<'_, '_> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (searcher, haystack) = (self.searcher, self.haystack);
+        let starts = self.starts.get_or_insert_with(|| {
+            let reverse = &searcher.reverse;
+            let mut starts = vec![false; haystack.len() + 1];
+            let mut id = DFA::START;
+            starts[haystack.len()] = reverse.class(id).is_some();
+            for (i, &byte) in haystack.iter().enumerate().rev() {
+                id = reverse.step(id, byte);
+                starts[i] = reverse.class(id).is_some();
+            }
+            starts
+        });
+
+        let start = self.pos + starts.get(self.pos..)?.iter().position(|&start| start)?;
+        let (_, last_accept) = searcher.forward.run_with_last_accept(DFA::START, &haystack[start..]);
+        let (len, _) = last_accept.expect("a match starts here");
+        self.pos = if len == 0 { start + 1 } else { start + len };
+        Some(start..start + len)
+    }
+}
@@ -0,0 +1,93 @@
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use super::{DFA, State};
+
+/// Incremented whenever the serialized representation of a `DFA` changes.
+const FORMAT_VERSION: u32 = 3;
+
+impl Serialize for DFA {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let states = self.states.iter().map(StateRepr::from).collect();
+        Repr { version: FORMAT_VERSION, states, class_count: self.class_count }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for DFA {
+    /// Fails if the DFA is malformed: i.e. if state 0 is not a non-accepting
+    /// sink, if there is no start state, if any transition leads out of
+    /// range, if the classes of any state are not ascending or do not
+    /// include its class, or if any class is not below the class count.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = Repr::deserialize(deserializer)?;
+        if repr.version != FORMAT_VERSION {
+            return Err(de::Error::custom(format_args!("unsupported DFA format version {}", repr.version)))
+        }
+        if repr.states.len() < 2 {
+            return Err(de::Error::custom("DFA has no start state"))
+        }
+
+        let n = repr.states.len();
+        let class_count = repr.class_count;
+        let states: Vec<State> = repr.states.into_iter().map(State::from).collect();
+        if states[0].class.is_some() || !states[0].next.is_empty() {
+            return Err(de::Error::custom("state 0 is not a non-accepting sink"))
+        }
+        for (id, state) in states.iter().enumerate() {
+            if let Some((symbol, dest)) = state.edges().find(|&(_, dest)| dest >= n) {
+                return Err(de::Error::custom(format_args!("transition {id} -({symbol})-> {dest} is out of range")))
+            }
+            let ascending = state.classes.windows(2).all(|pair| pair[0] < pair[1]);
+            let includes = state.class.map_or(state.classes.is_empty(), |class| state.classes.binary_search(&class).is_ok());
+            if !ascending || !includes {
+                return Err(de::Error::custom(format_args!("classes of state {id} are inconsistent")))
+            }
+            if state.classes.last().is_some_and(|&class| class >= class_count) {
+                return Err(de::Error::custom(format_args!("class of state {id} is out of range for {class_count} classes")))
+            }
+        }
+        Ok(DFA::from_states(states, class_count))
+    }
+}
+
+impl Serialize for State {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        StateRepr::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for State {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        StateRepr::deserialize(deserializer).map(State::from)
+    }
+}
+
+// =================
+// === INTERNALS ===
+// =================
+
+#[derive(Serialize, Deserialize)]
+struct Repr {
+    version: u32,
+    states: Vec<StateRepr>,
+    class_count: usize,
+}
+
+/// Transitions are in order of symbol.
+#[derive(Serialize, Deserialize)]
+struct StateRepr {
+    next: Vec<(u8, usize)>,
+    class: Option<usize>,
+    classes: Vec<usize>,
+}
+
+impl From<&State> for StateRepr {
+    fn from(state: &State) -> Self {
+        let next = state.edges().collect();
+        Self { next, class: state.class, classes: state.classes.clone() }
+    }
+}
+
+impl From<StateRepr> for State {
+    fn from(repr: StateRepr) -> Self {
+        State::with_classes(repr.next.into_iter().collect(), repr.class, repr.classes)
+    }
+}
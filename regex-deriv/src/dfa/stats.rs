@@ -0,0 +1,48 @@
+use std::fmt;
+use super::{DFA, binary};
+
+/// Summary of the size of a DFA, as returned by `DFA::stats`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DfaStats {
+    /// Number of states, including the sink.
+    pub states: usize,
+    /// Number of transitions to states other than the sink.
+    pub transitions: usize,
+    /// Number of accepting states of each class.
+    pub accepting: Vec<usize>,
+    /// Number of byte equivalence classes, as given by `DFA::byte_classes`.
+    pub byte_classes: usize,
+    /// Size in bytes of the dense table written by `DFA::write_to`.
+    pub dense_bytes: usize,
+}
+
+impl fmt::Display for DfaStats {
+    /// Writes a one-line summary, e.g. `4 states, 136 transitions, 2
+    /// accepting (classes 1, 1), 3 byte classes, 4128 bytes dense`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} states, {} transitions, {} accepting (classes ", self.states, self.transitions, self.accepting.iter().sum::<usize>())?;
+        for (i, count) in self.accepting.iter().enumerate() {
+            write!(f, "{}{count}", if i == 0 { "" } else { ", " })?;
+        }
+        write!(f, "), {} byte classes, {} bytes dense", self.byte_classes, self.dense_bytes)
+    }
+}
+
+pub fn stats(dfa: &DFA) -> DfaStats {
+    let mut accepting = vec![0; dfa.class_count()];
+    let mut transitions = 0;
+    for state in &dfa.states {
+        if let Some(class) = state.class {
+            accepting[class] += 1;
+        }
+        transitions += state.edges().count();
+    }
+
+    DfaStats {
+        states: dfa.states.len(),
+        transitions,
+        accepting,
+        byte_classes: dfa.byte_classes().1,
+        dense_bytes: binary::table_len(dfa.states.len()),
+    }
+}
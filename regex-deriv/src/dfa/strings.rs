@@ -0,0 +1,78 @@
+use std::borrow::Borrow;
+use super::DFA;
+
+/// Iterates over the strings accepted by a DFA, with their classes, in
+/// shortlex order. For each length in turn, a depth-first search is made over
+/// strings of exactly that length, only ever descending into states that can
+/// still reach an accept state in the number of remaining steps. Transitions
+/// to the sink are dropped up front, so a state only branches on the bytes
+/// that lead somewhere.
+pub(crate) struct Strings<D> {
+    dfa: D,
+    edges: Vec<Vec<(u8, usize)>>,
+    exact: Vec<Vec<bool>>,
+    len: usize,
+    max_len: usize,
+    stack: Vec<(usize, usize)>, // (state, index of next edge to try)
+    string: Vec<u8>,
+}
+
+impl<D: Borrow<DFA>> Strings<D> {
+    pub(crate) fn new(dfa: D, max_len: usize) -> Self {
+        let states = &dfa.borrow().states;
+        let n = states.len();
+
+        // edges[id] = transitions out of id that avoid the sink, by byte
+        let edges: Vec<Vec<(u8, usize)>> = states.iter().enumerate().map(|(id, state)| {
+            if id == 0 { Vec::new() } else { state.edges().collect() }
+        }).collect();
+
+        // exact[k][id] iff an accept state is reachable from id in exactly k steps
+        let mut exact = vec![states.iter().map(|state| state.class.is_some()).collect::<Vec<_>>()];
+        for k in 1..=max_len {
+            let layer = (0..n).map(|id| edges[id].iter().any(|&(_, dest)| exact[k - 1][dest])).collect();
+            exact.push(layer);
+        }
+
+        let stack = if exact[0][DFA::START] { vec![(DFA::START, 0)] } else { Vec::new() };
+        Self { dfa, edges, exact, len: 0, max_len, stack, string: Vec::new() }
+    }
+}
+
+impl<D: Borrow<DFA>> Iterator for Strings<D> {
+    type Item = (Vec<u8>, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(&(id, edge)) = self.stack.last() {
+                let depth = self.string.len();
+                if depth == self.len {
+                    let string = self.string.clone();
+                    let class = self.dfa.borrow().class(id).unwrap();
+                    self.stack.pop();
+                    self.string.pop();
+                    return Some((string, class))
+                }
+
+                let exact = &self.exact[self.len - depth - 1];
+                let edges = &self.edges[id];
+                if let Some(i) = (edge..edges.len()).find(|&i| exact[edges[i].1]) {
+                    let (symbol, dest) = edges[i];
+                    self.stack.last_mut().unwrap().1 = i + 1;
+                    self.string.push(symbol);
+                    self.stack.push((dest, 0));
+                } else {
+                    self.stack.pop();
+                    self.string.pop();
+                }
+            } else if self.len < self.max_len {
+                self.len += 1;
+                if self.exact[self.len][DFA::START] {
+                    self.stack.push((DFA::START, 0));
+                }
+            } else {
+                return None
+            }
+        }
+    }
+}
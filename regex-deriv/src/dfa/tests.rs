@@ -1,21 +1,18 @@
 #![allow(non_snake_case)]
 
-use super::{RegEx, DFA, ByteSet};
+use super::{RegEx, DFA, State, BuildLimits, BuildError, BuildObserver, ByteSet, DotOptions, DenseDFA, DfaMatcher, DfaRef, DfaStats, FormatError, GenOptions, LazyDFA, Eviction, MinimizeReport};
+use crate::{IndexTooNarrow, LexTable, NaiveLexTable, Scan};
 
-// #[test]
-// fn test() {
-//     let a = RegEx::set(ByteSet::point(1));
-//     let b = RegEx::set(ByteSet::point(2));
-//     let c = RegEx::set(ByteSet::point(3));
-//     let regex = a.or(&b.then(&a)).or(&c);
-//     let dfa = DFA::from(&regex);
-//     println!("{}", dfa.dot().unwrap());
-// }
+use std::collections::BTreeMap;
+use std::iter::once;
+
+use rand_core::{RngCore, SeedableRng};
+use rand_pcg::Pcg32;
 
 #[test]
 fn excluding() {
-    let digit = RegEx::set(ByteSet::range(0x30, 0x39));
-    let zero = RegEx::set(ByteSet::point(0x30));
+    let digit = RegEx::set(ByteSet::ASCII_DIGIT);
+    let zero = RegEx::set(ByteSet::point(b'0'));
     let nonzero_digit = digit.and(&zero.not());
 
     let A = DFA::from(&nonzero_digit);
@@ -28,13 +25,10 @@ fn excluding() {
 
 #[test]
 fn indentifiers() {
-    let uppercase  = RegEx::set(ByteSet::range(0x41, 0x5a));
-    let lowercase  = RegEx::set(ByteSet::range(0x61, 0x7a));
-    let digit      = RegEx::set(ByteSet::range(0x30, 0x39));
-    let underscore = RegEx::set(ByteSet::point(0x5f));
+    let character  = RegEx::set(ByteSet::ASCII_ALPHA.union(&ByteSet::point(b'_')));
+    let word       = RegEx::set(ByteSet::WORD);
 
-    let character  = uppercase.or(&lowercase);
-    let indentifier = character.or(&underscore).then(&character.or(&digit).or(&underscore).star());
+    let indentifier = character.then(&word.star());
     // println!("{:?}", &indentifier);
 
     let A = DFA::from(&indentifier);
@@ -44,4 +38,1329 @@ fn indentifiers() {
     assert!( A.matches("ThIsIsAlLoWeD") );
     assert!( A.matches("__allowed_123_") );
     assert!( !A.matches("not allowed") );
-}
\ No newline at end of file
+}
+
+#[test]
+fn count_matches_of_len() {
+    let character  = RegEx::set(ByteSet::ASCII_ALPHA.union(&ByteSet::point(b'_')));
+    let word       = RegEx::set(ByteSet::WORD);
+
+    let indentifier = character.then(&word.star());
+
+    let A = DFA::from(&indentifier);
+
+    assert_eq!(A.count_matches_of_len(0), 0);
+    assert_eq!(A.count_matches_of_len(1), 53);
+    assert_eq!(A.count_matches_of_len(2), 53 * 63);
+    assert_eq!(A.count_matches_of_len(3), 53 * 63 * 63);
+    assert_eq!(A.minimize().count_matches_of_len(3), 53 * 63 * 63);
+
+    let B = DFA::from(&RegEx::set(ByteSet::universe()).star());
+    assert_eq!(B.count_matches_of_len(15), 1 << 120);
+    assert_eq!(B.count_matches_of_len(16), u128::MAX);
+}
+
+#[test]
+fn complement() {
+    let digit = RegEx::set(ByteSet::ASCII_DIGIT);
+    let alpha = RegEx::set(ByteSet::ASCII_ALPHA);
+    let regexes = [
+        digit.plus(),
+        alpha.then(&digit.star()),
+        digit.then(&alpha).star(),
+        RegEx::none(),
+        RegEx::empty(),
+        RegEx::set(ByteSet::universe()).star(),
+    ];
+    let corpus = ["", "0", "42", "a", "a12", "1a2b", "1a2", "ab", "a1a", "\u{e9}", "1a\u{e9}"];
+
+    for re in &regexes {
+        let A = DFA::from(re).complement();
+        let B = DFA::from(&re.not());
+        for text in &corpus {
+            assert_eq!(A.matches(text), B.matches(text), "{re} on {text:?}");
+            assert_eq!(A.minimize().matches(text), B.matches(text), "{re} on {text:?}");
+        }
+        assert_eq!(A.count_matches_of_len(3), B.count_matches_of_len(3));
+        assert!(A.class(0).is_none());
+    }
+
+    // classes are not told apart
+    let A = DFA::from(&[digit.plus(), alpha.plus()]).complement();
+    assert!(!A.matches("42") && !A.matches("ab"));
+    assert!(A.matches("a4") && A.matches(""));
+}
+
+#[test]
+fn product() {
+    let digit = RegEx::set(ByteSet::ASCII_DIGIT);
+    let lower = RegEx::set(ByteSet::ASCII_LOWER);
+    let word  = RegEx::set(ByteSet::WORD);
+
+    // identifiers, words containing a digit, and words of even length
+    let a = lower.then(&word.star());
+    let b = word.star().then(&digit).then(&word.star());
+    let c = word.then(&word).star();
+
+    let mut rng = Pcg32::seed_from_u64(1079);
+    let alphabet = b"a0_ z9";
+    let corpus: Vec<Vec<u8>> = (0..2000).map(|_| {
+        let len = rng.next_u32() % 8;
+        (0..len).map(|_| alphabet[rng.next_u32() as usize % alphabet.len()]).collect()
+    }).collect();
+
+    for (x, y) in &[(&a, &b), (&b, &c), (&c, &a), (&a, &RegEx::none()), (&b, &RegEx::empty())] {
+        let A = DFA::from(*x);
+        let B = DFA::from(*y);
+        let expected = [
+            DFA::from(&x.and(y)),
+            DFA::from(&x.or(y)),
+            DFA::from(&x.and(&y.not())),
+        ];
+        let actual = [A.intersect(&B), A.union(&B), A.difference(&B.minimize())];
+        for (actual, expected) in actual.iter().zip(&expected) {
+            let minimized = actual.minimize();
+            for text in &corpus {
+                assert_eq!(actual.matches_bytes(text), expected.matches_bytes(text), "{x} {y} on {text:?}");
+                assert_eq!(minimized.matches_bytes(text), expected.matches_bytes(text));
+            }
+            assert_eq!(actual.count_matches_of_len(3), expected.count_matches_of_len(3));
+        }
+    }
+
+    // classes of self take precedence
+    let A = DFA::from(&[a.clone(), b.clone()]);
+    let B = DFA::from(&[RegEx::none(), RegEx::none(), c.clone()]);
+    let U = A.union(&B);
+    let classify = |dfa: &DFA, text: &[u8]| dfa.class(text.iter().fold(1, |id, &symbol| dfa.step(id, symbol)));
+    assert_eq!(classify(&U, b"a0"), Some(0));
+    assert_eq!(classify(&U, b"00"), Some(1));
+    assert_eq!(classify(&U, b"__"), Some(2));
+    assert_eq!(classify(&U, b"_"), None);
+    assert_eq!(classify(&A.intersect(&B), b"a0"), Some(0));
+    assert_eq!(classify(&A.difference(&B), b"a"), Some(0));
+}
+
+#[test]
+fn emptiness() {
+    let digit = RegEx::set(ByteSet::ASCII_DIGIT);
+    let alpha = RegEx::set(ByteSet::ASCII_ALPHA);
+    let any   = RegEx::set(ByteSet::universe());
+
+    // empty only because no number also ends in a letter
+    let never = digit.plus().and(&digit.star().then(&alpha));
+    let nested = alpha.then(&never.or(&RegEx::none())).then(&digit.star());
+    assert_ne!(nested, RegEx::none());
+
+    for A in &[DFA::from(&nested), DFA::from(&nested).minimize(), DFA::from(&RegEx::none())] {
+        assert!(A.is_empty());
+        assert!(!A.is_universal());
+        assert!(!A.accepting_reachable_from(0));
+        assert!(!A.accepting_reachable_from(1));
+    }
+
+    let A = DFA::from(&alpha.then(&digit));
+    assert!(!A.is_empty());
+    assert!(!A.is_universal());
+    assert!(!A.accepting_reachable_from(0));
+    assert!(A.accepting_reachable_from(A.step(A.step(1, b'a'), b'0')));
+
+    for re in &[any.star(), any.star().or(&alpha), digit.star().or(&digit.not())] {
+        for A in &[DFA::from(re), DFA::from(re).minimize()] {
+            assert!(!A.is_empty());
+            assert!(A.is_universal(), "{}", re);
+        }
+    }
+    assert!(!DFA::from(&any.plus()).is_universal());
+    assert!(!DFA::from(&RegEx::empty()).is_universal());
+    assert!(DFA::from(&any.plus()).complement().intersect(&DFA::from(&any.plus())).is_empty());
+}
+
+#[test]
+fn equivalence() {
+    let character   = RegEx::set(ByteSet::ASCII_ALPHA.union(&ByteSet::point(b'_')));
+    let word        = RegEx::set(ByteSet::WORD);
+    let digit       = RegEx::set(ByteSet::ASCII_DIGIT);
+    let indentifier = character.then(&word.star());
+
+    for regexes in &[vec![indentifier.clone()], vec![digit.and(&RegEx::set(ByteSet::point(b'0')).not())], vec![indentifier.clone(), digit.plus()]] {
+        let A = DFA::from(regexes);
+        assert!(A.equivalent(&A));
+        assert!(A.equivalent(&A.minimize()));
+        assert!(A.minimize().equivalent(&A.minimize().minimize()));
+        assert_eq!(A.counterexample(&A.minimize()), None);
+    }
+
+    // shortest, taking the smallest bytes
+    let A = DFA::from(&digit.plus());
+    let B = DFA::from(&digit.then(&digit.opt()).then(&digit.opt()));
+    assert_eq!(A.counterexample(&B), Some(b"0000".to_vec()));
+    assert_eq!(B.counterexample(&A), Some(b"0000".to_vec()));
+    assert_eq!(A.counterexample(&DFA::from(&RegEx::none())), Some(b"0".to_vec()));
+
+    // classes must agree, not just acceptance
+    let A = DFA::from(&[indentifier.clone(), digit.plus()]);
+    let B = DFA::from(&[digit.plus(), indentifier.clone()]);
+    assert_eq!(A.counterexample(&B), Some(b"0".to_vec()));
+
+    let A = DFA::from(&indentifier);
+    assert!(A.complement().complement().equivalent(&A));
+    assert!(!A.complement().equivalent(&A));
+    assert!(A.union(&A.complement()).equivalent(&DFA::from(&RegEx::set(ByteSet::universe()).star())));
+}
+
+#[test]
+fn to_regex() {
+    let character   = RegEx::set(ByteSet::ASCII_ALPHA.union(&ByteSet::point(b'_')));
+    let word        = RegEx::set(ByteSet::WORD);
+    let digit       = RegEx::set(ByteSet::ASCII_DIGIT);
+    let indentifier = character.then(&word.star());
+    let nonzero     = digit.and(&RegEx::set(ByteSet::point(b'0')).not());
+
+    for re in &[indentifier.clone(), nonzero.clone(), digit.plus().or(&word.then(&digit).star()), RegEx::none(), RegEx::empty()] {
+        for A in &[DFA::from(re), DFA::from(re).minimize()] {
+            assert!(DFA::from(&A.to_regex()).equivalent(A), "{}", re);
+            assert_eq!(A.to_regex_with_limit(1000).unwrap(), A.to_regex());
+        }
+    }
+
+    let A = DFA::from(&[indentifier, nonzero.plus()]);
+    let regexes = A.to_regexes();
+    assert_eq!(regexes.len(), 2);
+    assert!(DFA::from(&regexes).equivalent(&A));
+    assert_eq!(A.to_regexes_with_limit(1000).unwrap(), regexes);
+
+    // strings whose 6th-last byte is an 'a' need exponentially many states
+    let a = RegEx::set(ByteSet::point(b'a'));
+    let ab = RegEx::set(ByteSet::range(b'a', b'b'));
+    let A = DFA::from(&ab.star().then(&a).then(&ab).then(&ab).then(&ab).then(&ab).then(&ab)).minimize();
+    assert_eq!(A.to_regex_with_limit(100).unwrap_err().limit(), 100);
+    assert_eq!(A.to_regexes_with_limit(100).unwrap_err().to_string(), "regex exceeds the limit of 100 nodes");
+}
+
+#[test]
+fn longest_match() {
+    let lit = |s: &[u8]| s.iter().fold(RegEx::empty(), |re, &c| re.then(&RegEx::set(ByteSet::point(c))));
+    let digits = RegEx::set(ByteSet::ASCII_DIGIT).plus();
+
+    for A in &[DFA::from(&[lit(b"ab"), lit(b"abcde"), digits.clone()]), DFA::from(&[lit(b"ab"), lit(b"abcde"), digits.clone()]).minimize()] {
+        // the longer attempt dies after an earlier accept
+        assert_eq!(A.longest_match(b"abcdx"), Some((2, 0)));
+        assert_eq!(A.longest_match(b"abcde"), Some((5, 1)));
+        assert_eq!(A.longest_match(b"abcdef"), Some((5, 1)));
+        assert_eq!(A.longest_match(b"a"), None);
+        assert_eq!(A.longest_match(b"x"), None);
+        assert_eq!(A.longest_match(b""), None);
+        assert_eq!(A.longest_match_at(b"ab123ab", 2), Some((3, 2)));
+        assert_eq!(A.longest_match_at(b"ab123ab", 7), None);
+    }
+
+    let A = DFA::from(&[digits.star()]);
+    assert_eq!(A.longest_match(b"x"), Some((0, 0)));
+    assert_eq!(A.longest_match_at(b"x12", 1), Some((2, 0)));
+
+    // scanning a DFA directly agrees with its table
+    let A = DFA::from(&[RegEx::set(ByteSet::point(b' ')).plus(), lit(b"ab"), lit(b"abcde"), digits]).minimize();
+    let text = "ab abcde abcdeab 12ab";
+    let naive: Vec<_> = Scan::new(&NaiveLexTable::new(&A), &text).collect::<Result<_, _>>().unwrap();
+    let direct: Vec<_> = Scan::new(&A, &text).collect::<Result<_, _>>().unwrap();
+    assert_eq!(direct, naive);
+    assert!(Scan::new(&A, "abcd").any(|token| token.is_err()));
+}
+
+#[test]
+fn run() {
+    let lit = |s: &[u8]| s.iter().fold(RegEx::empty(), |re, &c| re.then(&RegEx::set(ByteSet::point(c))));
+    let space = RegEx::set(ByteSet::point(b' ')).plus();
+    let lower = RegEx::set(ByteSet::ASCII_LOWER).plus();
+
+    for A in &[DFA::from(&[space.clone(), lower.clone(), lit(b"a-b-c")]), DFA::from(&[space.clone(), lower.clone(), lit(b"a-b-c")]).minimize()] {
+        // the longer attempt dies after an earlier accept
+        let (state, last_accept) = A.run_with_last_accept(1, b"a-b-x");
+        assert_eq!(state, 0);
+        assert_eq!(last_accept, Some((1, 1)));
+        assert_eq!(A.run_with_last_accept(1, b"a-b-c d").1, Some((5, 2)));
+        assert_eq!(A.run_with_last_accept(1, b"abc  ").1, Some((3, 1)));
+        assert_eq!(A.run_with_last_accept(1, b"-").1, None);
+        assert_eq!(A.run_with_last_accept(1, b""), (1, None));
+
+        // from states other than the start
+        let state = A.run(1, b"a-b");
+        assert_ne!(state, 0);
+        assert_eq!(A.class(state), None);
+        assert_eq!(A.run_with_last_accept(state, b"-c").1, Some((2, 2)));
+        assert_eq!(A.run(state, b"-x-c"), 0);
+        assert_eq!(A.run(0, b"abc"), 0);
+
+        let (state, last_accept) = A.run_with_last_accept(A.run(1, b"ab"), b"cd-");
+        assert_eq!((state, last_accept), (0, Some((2, 1))));
+    }
+}
+
+#[test]
+fn shortest_accepted() {
+    let lit = |s: &[u8]| s.iter().fold(RegEx::empty(), |re, &c| re.then(&RegEx::set(ByteSet::point(c))));
+    let lower  = RegEx::set(ByteSet::ASCII_LOWER);
+    let digit  = RegEx::set(ByteSet::ASCII_DIGIT);
+    let space  = RegEx::set(ByteSet::point(b' '));
+
+    // keywords after identifiers are shadowed
+    let rules = [space.plus(), lit(b"if"), lower.plus(), lit(b"else"), digit.plus().then(&lit(b".").then(&digit.plus()).opt())];
+    for A in &[DFA::from(&rules), DFA::from(&rules).minimize()] {
+        assert_eq!(A.shortest_accepted(), [
+            Some(b" ".to_vec()),
+            Some(b"if".to_vec()),
+            Some(b"a".to_vec()),
+            None,
+            Some(b"0".to_vec()),
+        ]);
+        assert!(!DFA::from(&rules[3]).is_empty());
+    }
+
+    // a shadowed last rule still has its entry
+    let A = DFA::from(&[lower.plus(), lit(b"else")]);
+    for B in &[A.minimize(), A.trim().0] {
+        assert_eq!(B.shortest_accepted(), [Some(b"a".to_vec()), None]);
+    }
+    assert_eq!(A.shortest_accepted(), [Some(b"a".to_vec()), None]);
+
+    assert_eq!(DFA::from(&lower.star()).shortest_accepted(), [Some(Vec::new())]);
+    assert_eq!(DFA::from(&RegEx::none()).shortest_accepted(), [None]);
+}
+
+#[test]
+fn strings_up_to() {
+    let character   = RegEx::set(ByteSet::ASCII_ALPHA.union(&ByteSet::point(b'_')));
+    let word        = RegEx::set(ByteSet::WORD);
+    let indentifier = character.then(&word.star());
+    let number      = RegEx::set(ByteSet::ASCII_DIGIT).plus();
+
+    // restricted to a 3-letter alphabet, so as to brute force
+    let alphabet = [b'0', b'_', b'a'];
+    let restricted = RegEx::set(alphabet.iter().copied().collect()).star();
+    for A in &[DFA::from(&[indentifier.and(&restricted), number.and(&restricted)]), DFA::from(&[indentifier.clone(), number.clone()]).intersect(&DFA::from(&restricted))] {
+        let mut expected = Vec::new();
+        let mut strings = vec![Vec::new()];
+        for len in 0..=5 {
+            for string in &strings {
+                if let Some(class) = A.class(string.iter().fold(1, |id, &symbol| A.step(id, symbol))) {
+                    expected.push((string.clone(), class));
+                }
+            }
+            if len < 5 {
+                strings = strings.iter().flat_map(|string| alphabet.iter().map(move |&symbol| {
+                    let mut string = string.clone();
+                    string.push(symbol);
+                    string
+                })).collect();
+            }
+        }
+        assert_eq!(A.strings_up_to(5).collect::<Vec<_>>(), expected);
+        assert_eq!(A.minimize().strings_up_to(5).collect::<Vec<_>>(), expected);
+        assert!(expected.contains(&(b"a_0".to_vec(), 0)) && expected.contains(&(b"00".to_vec(), 1)));
+    }
+
+    // laziness over a language too large to enumerate
+    let A = DFA::from(&indentifier);
+    assert_eq!(A.strings_up_to(100).nth(52), Some((b"z".to_vec(), 0)));
+    assert_eq!(DFA::from(&RegEx::none()).strings_up_to(4).count(), 0);
+}
+
+#[test]
+fn byte_classes() {
+    let character   = RegEx::set(ByteSet::ASCII_ALPHA.union(&ByteSet::point(b'_')));
+    let word        = RegEx::set(ByteSet::WORD);
+    let indentifier = character.then(&word.star());
+
+    for A in &[DFA::from(&indentifier), DFA::from(&indentifier).minimize()] {
+        let (classes, count) = A.byte_classes();
+        assert!(count <= 5);
+        assert!((b'a'..=b'z').chain(b'A'..=b'Z').chain(Some(b'_')).all(|x| classes[usize::from(x)] == classes[usize::from(b'a')]));
+        assert!((b'0'..=b'9').all(|x| classes[usize::from(x)] == classes[usize::from(b'0')]));
+        assert_ne!(classes[usize::from(b'a')], classes[usize::from(b'0')]);
+        assert_ne!(classes[usize::from(b'a')], classes[usize::from(b' ')]);
+        assert_eq!(classes[0], 0);
+        assert!(classes.iter().all(|&class| usize::from(class) < count));
+
+        // bytes of the same class are interchangeable
+        for id in 0..A.states().len() {
+            for symbol in 0..=255 {
+                let representative = (0..=255).find(|&x| classes[usize::from(x)] == classes[usize::from(symbol)]).unwrap();
+                assert_eq!(A.step(id, symbol), A.step(id, representative));
+            }
+        }
+    }
+
+    assert_eq!(DFA::from(&RegEx::set(ByteSet::universe()).star()).byte_classes(), ([0; 256], 1));
+    let (_, count) = DFA::from(&RegEx::set(ByteSet::universe()).then(&RegEx::set(ByteSet::point(0xff)))).byte_classes();
+    assert_eq!(count, 2);
+}
+
+#[test]
+fn shared_subexpressions() {
+    let lit = |s: &[u8]| s.iter().fold(RegEx::empty(), |re, &c| re.then(&RegEx::set(ByteSet::point(c))));
+    let tail = RegEx::set(ByteSet::WORD).star();
+
+    // keywords sharing an identifier tail, whose derivative classes are
+    // memoized rather than recomputed in every state
+    let mut rules: Vec<RegEx> = ["if", "in", "impl", "else", "enum", "for", "fn"].iter().map(|s| lit(s.as_bytes()).then(&tail)).collect();
+    rules.push(RegEx::set(ByteSet::ASCII_ALPHA).then(&tail));
+
+    let A = DFA::from(&rules);
+    let B = crate::NFA::from(&rules).determinize();
+    assert!(A.equivalent(&B));
+    assert!(A.minimize().equivalent(&B.minimize()));
+    assert_eq!(A.minimize().states().len(), B.minimize().states().len());
+    assert_eq!(A.to_string(), DFA::from(&rules).to_string());
+}
+
+#[test]
+fn stats() {
+    let character   = RegEx::set(ByteSet::ASCII_ALPHA.union(&ByteSet::point(b'_')));
+    let word        = RegEx::set(ByteSet::WORD);
+    let indentifier = character.then(&word.star());
+    let number      = RegEx::set(ByteSet::ASCII_DIGIT).plus();
+
+    let A = DFA::from(&indentifier).minimize();
+    let stats = A.stats();
+    assert_eq!(stats, DfaStats { states: 3, transitions: 53 + 63, accepting: vec![1], byte_classes: 3, dense_bytes: A.to_bytes().len() });
+    assert_eq!(stats.to_string(), "3 states, 116 transitions, 1 accepting (classes 1), 3 byte classes, 3100 bytes dense");
+
+    let A = DFA::from(&[indentifier, number]).minimize();
+    assert_eq!(A.stats().to_string(), "4 states, 136 transitions, 2 accepting (classes 1, 1), 3 byte classes, 4128 bytes dense");
+}
+
+#[test]
+fn searcher() {
+    let lit = |s: &[u8]| s.iter().fold(RegEx::empty(), |re, &c| re.then(&RegEx::set(ByteSet::point(c))));
+    let a = RegEx::set(ByteSet::point(b'a'));
+    let b = RegEx::set(ByteSet::point(b'b'));
+
+    let A = DFA::searcher(&lit(b"ab").or(&lit(b"abab")).or(&lit(b"ba")));
+    assert_eq!(A.find(b"xxababa"), Some(2..6));
+    assert_eq!(A.find_iter(b"xxababa").collect::<Vec<_>>(), vec![2..6]);
+    assert_eq!(A.find_iter(b"abaab").collect::<Vec<_>>(), [0..2, 3..5]);
+    assert_eq!(A.find(b"aaa"), None);
+    assert_eq!(A.find(b""), None);
+
+    // empty matches are found once at each offset that starts no longer match
+    let A = DFA::searcher(&a.star());
+    assert_eq!(A.find_iter(b"baab").collect::<Vec<_>>(), [0..0, 1..3, 3..3, 4..4]);
+    assert_eq!(A.find(b""), Some(0..0));
+
+    // leftmost-longest spans agree with those found by brute force
+    let regexes = [
+        a.then(&b.star()).then(&a),
+        a.or(&b).star().then(&b).then(&a.or(&b)),
+        lit(b"ab").plus().or(&lit(b"ba")),
+        b.star().then(&lit(b" a")),
+        a.star().and(&lit(b"aaa").not()),
+    ];
+    let mut rng = Pcg32::seed_from_u64(1093);
+    for re in &regexes {
+        let A = DFA::searcher(re);
+        for _ in 0..200 {
+            let len = rng.next_u32() as usize % 16;
+            let haystack: Vec<u8> = (0..len).map(|_| b"ab "[rng.next_u32() as usize % 3]).collect();
+
+            let mut expected = Vec::new();
+            let mut pos = 0;
+            while pos <= len {
+                let span = (pos..=len).find_map(|start| {
+                    (start..=len).rev().find(|&end| re.is_fullmatch_bytes(&haystack[start..end])).map(|end| start..end)
+                });
+                match span {
+                    Some(span) => {
+                        pos = if span.is_empty() { span.end + 1 } else { span.end };
+                        expected.push(span);
+                    }
+                    None => break,
+                }
+            }
+            assert_eq!(A.find_iter(&haystack).collect::<Vec<_>>(), expected, "{:?} on {:?}", re.to_string(), String::from_utf8_lossy(&haystack));
+            assert_eq!(A.find(&haystack), expected.first().cloned());
+        }
+    }
+}
+
+#[test]
+fn lazy_state_limit() {
+    // (a|b)*a(a|b){20}, whose DFA remembers the last 21 bytes: 2^21 states
+    let a = RegEx::set(ByteSet::point(b'a'));
+    let ab = RegEx::set(ByteSet::point(b'a').union(&ByteSet::point(b'b')));
+    let re = (0..20).fold(ab.star().then(&a), |re, _| re.then(&ab));
+
+    let mut rng = Pcg32::seed_from_u64(1094);
+    let input: Vec<u8> = (0..20_000).map(|_| if rng.next_u32() % 2 == 0 { b'a' } else { b'b' }).collect();
+    // the longest accepted prefix ends 21 bytes after the last a with 20 more
+    let expected = (0..input.len() - 20).rev().find(|&i| input[i] == b'a').map(|i| (i + 21, 0));
+
+    for eviction in [Eviction::Lru, Eviction::Flush] {
+        let A = LazyDFA::builder().state_limit(10_000).eviction(eviction).build(std::iter::once(&re));
+        assert_eq!(A.longest_match_at(&input, 0), expected);
+        assert!(A.state_count() <= 10_000);
+        assert_eq!(A.longest_match_at(&input[..1000], 0), (0..1000 - 20).rev().find(|&i| input[i] == b'a').map(|i| (i + 21, 0)));
+    }
+}
+
+#[test]
+fn trim() {
+    let lower = RegEx::set(ByteSet::ASCII_LOWER).plus();
+    let space = RegEx::set(ByteSet::point(b' ')).plus();
+
+    // the accept-all state added by the first complement is dead after the
+    // second, and scanning into it only fails at the end of the input
+    let A = DFA::from(&[lower.or(&space)]).minimize().complement().complement();
+    let (B, ids) = A.trim();
+    assert!(B.states().len() < A.states().len());
+    assert_eq!(ids.iter().filter(|&&id| id == Some(0)).count(), 2);
+    assert!(B.equivalent(&A));
+    let dead = A.run(1, b"ab1cdefgh");
+    assert_ne!(dead, 0);
+    assert_eq!(ids[dead], Some(0));
+    assert_eq!(B.run(1, b"ab1"), 0);
+    for text in ["ab  cd", "ab 1cd", "", "  x"] {
+        let a: Vec<_> = Scan::new(&A, text).map(|token| token.map_err(|_| ())).collect();
+        let b: Vec<_> = Scan::new(&B, text).map(|token| token.map_err(|_| ())).collect();
+        assert_eq!(a, b);
+    }
+
+    // unreachable states are dropped, and the rest keep their order
+    let step = |symbol: u8, dest: usize| once((symbol, dest)).collect();
+    let A = DFA::from_states(vec![
+        State::sink(),
+        State::new(step(b'a', 3), None),
+        State::new(step(b'a', 3), Some(0)),
+        State::new(step(b'b', 4), Some(0)),
+        State::new(step(b'a', 3), None),
+    ], 1);
+    let (B, ids) = A.trim();
+    assert_eq!(ids, [Some(0), Some(1), None, Some(2), Some(3)]);
+    assert_eq!(B.states().len(), 4);
+    assert!(B.equivalent(&A));
+    assert_eq!(B.trim().1, [Some(0), Some(1), Some(2), Some(3)]);
+
+    // the start is kept even if dead
+    let (B, ids) = DFA::from(&RegEx::none()).trim();
+    assert_eq!((B.states().len(), ids[1]), (2, Some(1)));
+}
+
+#[test]
+fn ambiguities() {
+    let lit = |s: &[u8]| s.iter().fold(RegEx::empty(), |re, &c| re.then(&RegEx::set(ByteSet::point(c))));
+    let ident  = RegEx::set(ByteSet::ASCII_LOWER).plus();
+    let number = RegEx::set(ByteSet::ASCII_DIGIT).plus();
+    let hex    = lit(b"0x").then(&RegEx::set(ByteSet::ASCII_HEXDIGIT).plus());
+
+    // every class accepted is kept, through minimization keeping classes
+    let rules = [lit(b"if"), lit(b"in"), ident.clone(), number.clone()];
+    for A in &[DFA::from(&rules), DFA::from(&rules).minimize_keeping_classes()] {
+        assert_eq!(A.classes_at(A.run(1, b"if")), [0, 2]);
+        assert_eq!(A.classes_at(A.run(1, b"iff")), [2]);
+        assert_eq!(A.classes_at(A.run(1, b"i-")), [] as [usize; 0]);
+        assert_eq!(A.ambiguities(), [(vec![0, 2], b"if".to_vec()), (vec![1, 2], b"in".to_vec())]);
+    }
+    // states told apart only by their ambiguity stay apart there, but not
+    // through minimize, which drops the shadowed classes
+    assert_eq!(DFA::from(&rules).minimize_keeping_classes().states().len(), DFA::from(&[ident.clone(), number.clone()]).minimize().states().len() + 3);
+    let shadowed = DFA::from(&[ident.clone(), lit(b"if")]);
+    assert_eq!(shadowed.minimize().states().len(), DFA::from(&[ident.clone(), RegEx::none()]).minimize().states().len());
+    assert_eq!(shadowed.minimize().states().len(), 3);
+    assert_eq!(shadowed.minimize().ambiguities(), []);
+
+    // the class is still that of the highest priority
+    let A = DFA::from_rules(&[(ident.clone(), 0), (lit(b"if"), 1)]);
+    assert_eq!((A.class(A.run(1, b"if")), A.classes_at(A.run(1, b"if"))), (Some(1), &[0, 1][..]));
+
+    // ambiguity between more than two rules, and none at all
+    let A = DFA::from(&[number.clone(), lit(b"0").then(&number.star()), lit(b"00"), hex]);
+    assert_eq!(A.ambiguities(), [(vec![0, 1], b"0".to_vec()), (vec![0, 1, 2], b"00".to_vec())]);
+    assert_eq!(DFA::from(&[ident, number]).ambiguities(), []);
+}
+
+#[test]
+fn conversions() {
+    let lower = RegEx::set(ByteSet::ASCII_LOWER).plus();
+    let digit = RegEx::set(ByteSet::ASCII_DIGIT).plus();
+    let rules = vec![lower.clone(), digit.clone()];
+
+    // a single regex, by reference
+    let A = DFA::from(&lower).to_string();
+    assert_eq!(DFA::from(std::iter::once(&lower)).to_string(), A);
+    assert_eq!(DFA::from_owned(vec![lower.clone()]).to_string(), A);
+
+    // several regexes, from anything iterating over references, or owned
+    let A = DFA::from(&rules).to_string();
+    assert_eq!(DFA::from(&[lower.clone(), digit.clone()]).to_string(), A);
+    assert_eq!(DFA::from(rules.as_slice()).to_string(), A);
+    assert_eq!(DFA::from(rules.iter()).to_string(), A);
+    assert_eq!(DFA::from_owned(rules).to_string(), A);
+    assert_eq!(DFA::from_rules(&[(lower, 0), (digit, 0)]).to_string(), A);
+}
+
+#[test]
+fn start_state() {
+    // ab*, by hand: the sink, the start, and the accepting state after a
+    let next = |edges: &[(u8, usize)]| edges.iter().copied().collect();
+    let A = DFA::from_states(vec![
+        State::sink(),
+        State::new(next(&[(b'a', 2)]), None),
+        State::new(next(&[(b'b', 2)]), Some(0)),
+    ], 1);
+    assert_eq!(DFA::START, 1);
+    assert_eq!(A.start_state(), DFA::START);
+    assert_eq!(A.step(DFA::START, b'a'), 2);
+    assert_eq!(A.step(DFA::START, b'b'), 0);
+    assert_eq!(A.class(A.run(DFA::START, b"abb")), Some(0));
+    assert!(A.matches("abbb"));
+    assert!(!A.matches("ba"));
+
+    // the table numbers the start 0 and the sink last, but scans the same
+    let table = NaiveLexTable::new(&A);
+    assert_eq!(table.start_state(), 0);
+    assert_eq!(table.sink(), 2);
+    assert_eq!(table.step(0, b'a'), 1);
+    assert_eq!(table.step(0, b'b'), table.sink());
+    for input in [&b"abbx"[..], b"a", b"ba", b""] {
+        assert_eq!(A.longest_match(input), table.longest_match_at(input, 0));
+    }
+}
+
+#[test]
+#[should_panic(expected = "state 3 out of range for a DFA of 3 states")]
+#[cfg(debug_assertions)]
+fn step_out_of_range() {
+    let A = DFA::from(&RegEx::set(ByteSet::point(b'a')).plus()).minimize();
+    let _ = A.step(3, b'a');
+}
+
+#[test]
+fn build_limits() {
+    let character   = RegEx::set(ByteSet::ASCII_ALPHA.union(&ByteSet::point(b'_')));
+    let word        = RegEx::set(ByteSet::WORD);
+    let indentifier = character.then(&word.star());
+    let number      = RegEx::set(ByteSet::ASCII_DIGIT).plus();
+    let rules = [(indentifier, 0), (number, 0)];
+
+    // limits that are just enough
+    let stats = DFA::from_rules(&rules).stats();
+    let limits = BuildLimits { max_states: stats.states, max_transitions: stats.transitions };
+    let A = DFA::try_from_rules(&rules, &limits).unwrap();
+    assert_eq!(A.to_string(), DFA::from_rules(&rules).to_string());
+
+    let limits = BuildLimits { max_states: stats.states - 1, ..BuildLimits::default() };
+    assert_eq!(DFA::try_from_rules(&rules, &limits).err(), Some(BuildError::StateLimitExceeded { built: stats.states - 1 }));
+    let limits = BuildLimits { max_transitions: stats.transitions - 1, ..BuildLimits::default() };
+    assert!(matches!(DFA::try_from_rules(&rules, &limits), Err(BuildError::TransitionLimitExceeded { built }) if built >= stats.transitions));
+
+    // (a|b)*a(a|b){40} would have 2^41 states, but construction stops early
+    let a = RegEx::set(ByteSet::point(b'a'));
+    let ab = RegEx::set(ByteSet::point(b'a').union(&ByteSet::point(b'b')));
+    let explosive = (0..40).fold(ab.star().then(&a), |re, _| re.then(&ab));
+    let limits = BuildLimits { max_states: 500, ..BuildLimits::default() };
+    let err = DFA::try_from_rules(&[(explosive.clone(), 0)], &limits).err().unwrap();
+    assert_eq!(err, BuildError::StateLimitExceeded { built: 500 });
+    assert_eq!(err.to_string(), "DFA exceeds the limit of 500 states");
+    let limits = BuildLimits { max_transitions: 1000, ..BuildLimits::default() };
+    assert!(matches!(DFA::try_from_rules(&[(explosive, 0)], &limits), Err(BuildError::TransitionLimitExceeded { .. })));
+}
+
+#[test]
+fn build_observer() {
+    #[derive(Default)]
+    struct Counter {
+        ids: Vec<usize>,
+        nullable: usize,
+        finished: Option<DfaStats>,
+        error: Option<BuildError>,
+    }
+
+    impl BuildObserver for Counter {
+        fn on_state_added(&mut self, regexes: &[RegEx], id: usize) {
+            self.ids.push(id);
+            self.nullable += regexes.iter().filter(|regex| regex.is_nullable()).count();
+        }
+
+        fn on_finished(&mut self, stats: &DfaStats) {
+            self.finished = Some(stats.clone());
+        }
+
+        fn on_limit_exceeded(&mut self, error: &BuildError) {
+            self.error = Some(error.clone());
+        }
+    }
+
+    let lit = |s: &[u8]| s.iter().fold(RegEx::empty(), |re, &c| re.then(&RegEx::set(ByteSet::point(c))));
+    let rules = [
+        (lit(b"if"), 1),
+        (RegEx::set(ByteSet::ASCII_LOWER).plus(), 0),
+        (RegEx::set(ByteSet::ASCII_DIGIT).plus(), 0),
+    ];
+
+    // the sink, then the start, "i", "if", other identifiers, and numbers
+    let mut counter = Counter::default();
+    let A = DFA::try_from_rules_observed(&rules, &BuildLimits::default(), &mut counter).unwrap();
+    assert_eq!(counter.ids, [1, 2, 3, 4, 5]);
+    assert_eq!(A.states().len(), 6);
+    assert_eq!(counter.nullable, 5);
+    assert_eq!(counter.finished, Some(A.stats()));
+    assert_eq!(counter.error, None);
+
+    let mut counter = Counter::default();
+    let limits = BuildLimits { max_states: 4, ..BuildLimits::default() };
+    let err = DFA::try_from_rules_observed(&rules, &limits, &mut counter).err();
+    assert_eq!(counter.ids, [1, 2, 3]);
+    assert_eq!(counter.finished, None);
+    assert_eq!(counter.error, err);
+}
+
+#[test]
+fn transitions() {
+    let character   = RegEx::set(ByteSet::ASCII_ALPHA.union(&ByteSet::point(b'_')));
+    let word        = RegEx::set(ByteSet::WORD);
+    let indentifier = character.then(&word.star());
+    let number      = RegEx::set(ByteSet::ASCII_DIGIT).plus();
+
+    for A in &[DFA::from(&[indentifier.clone(), number.clone()]), DFA::from(&[indentifier.clone(), number.clone()]).minimize()] {
+        let transitions: Vec<_> = A.transitions().collect();
+        // in order of source and destination, with no sink
+        assert!(transitions.windows(2).all(|pair| (pair[0].0, pair[0].2) < (pair[1].0, pair[1].2)));
+        assert!(transitions.iter().all(|&(source, _, dest)| source != 0 && dest != 0));
+
+        // agrees with stepping byte by byte
+        for (id, state) in A.states().iter().enumerate() {
+            for symbol in 0..=255 {
+                let dest = transitions.iter()
+                    .find(|(source, set, _)| *source == id && set.contains(symbol))
+                    .map_or(0, |&(_, _, dest)| dest);
+                assert_eq!(dest, A.step(id, symbol));
+                assert_eq!(dest, state.step(symbol));
+            }
+            let grouped: Vec<_> = state.transitions_grouped().collect();
+            let expected: Vec<_> = transitions.iter().filter(|(source, ..)| *source == id).map(|(_, set, dest)| (set.clone(), *dest)).collect();
+            assert_eq!(grouped, expected);
+        }
+    }
+
+    let A = DFA::from(&[indentifier, number]).minimize();
+    let sets: Vec<_> = A.states()[DFA::START].transitions_grouped().map(|(set, _)| set).collect();
+    assert_eq!(sets.len(), 2);
+    assert!(sets.contains(&ByteSet::ASCII_ALPHA.union(&ByteSet::point(b'_'))));
+    assert!(sets.contains(&ByteSet::ASCII_DIGIT));
+}
+
+#[test]
+fn state_ranges() {
+    let mut state = State::sink();
+    state.insert_set(&ByteSet::range(b'x', b'z'), 2);
+    state.insert_set(&ByteSet::range(b'a', b'c'), 2);
+    state.insert(b'm', 3);
+    state.insert(b'0', 0);
+    assert_eq!(state.next, [(b'a', b'c', 2), (b'm', b'm', 3), (b'x', b'z', 2)]);
+
+    // adjacent ranges to the same state are merged, from either side
+    state.insert_set(&ByteSet::range(b'd', b'l'), 3);
+    state.insert_set(&ByteSet::range(b'n', b'w'), 3);
+    state.insert(255, 4);
+    state.insert(254, 4);
+    assert_eq!(state.next, [(b'a', b'c', 2), (b'd', b'w', 3), (b'x', b'z', 2), (254, 255, 4)]);
+
+    for symbol in 0..=255 {
+        let dest = match symbol {
+            b'a'..=b'c' | b'x'..=b'z' => 2,
+            b'd'..=b'w' => 3,
+            254..=255 => 4,
+            _ => 0,
+        };
+        assert_eq!(state.step(symbol), dest);
+    }
+    assert_eq!(state.edges().count(), 3 + 20 + 3 + 2);
+}
+
+#[test]
+fn fingerprint() {
+    let lit = |s: &[u8]| s.iter().fold(RegEx::empty(), |re, &c| re.then(&RegEx::set(ByteSet::point(c))));
+    let lower = RegEx::set(ByteSet::range(b'a', b'z'));
+    let digit = RegEx::set(ByteSet::range(b'0', b'9'));
+    let lexer = || DFA::from(&[lit(b"if"), lit(b"in"), lower.plus(), digit.plus()]);
+
+    // the same lexer built twice, and before and after minimization
+    let A = lexer();
+    assert_eq!(A.fingerprint(), lexer().fingerprint());
+    assert_eq!(A.fingerprint(), A.minimize().fingerprint());
+
+    // the same rules defined differently
+    let i = RegEx::set(ByteSet::point(b'i'));
+    let B = DFA::from(&[
+        i.then(&RegEx::set(ByteSet::point(b'f'))),
+        i.then(&RegEx::set(ByteSet::point(b'n'))),
+        lower.then(&lower.star()),
+        digit.plus().or(&digit.then(&digit.star())),
+    ]);
+    assert!(A.equivalent(&B));
+    assert_eq!(A.fingerprint(), B.fingerprint());
+
+    // rules in another order, or differing by a string, differ
+    let C = DFA::from(&[lit(b"in"), lit(b"if"), lower.plus(), digit.plus()]);
+    let D = DFA::from(&[lit(b"if"), lit(b"in"), lower.plus(), digit.star()]);
+    assert_ne!(A.fingerprint(), C.fingerprint());
+    assert_ne!(A.fingerprint(), D.fingerprint());
+    assert_ne!(C.fingerprint(), D.fingerprint());
+
+    // rules shadowed differently don't matter, since no string gets their class
+    let E = DFA::from(&[lower.plus(), lit(b"if")]);
+    let F = DFA::from(&[lower.plus(), RegEx::none()]);
+    assert!(E.equivalent(&F));
+    assert_ne!(E.classes_at(E.run(DFA::START, b"if")), F.classes_at(F.run(DFA::START, b"if")));
+    assert_eq!(E.fingerprint(), F.fingerprint());
+
+    // and so does the DFA accepting nothing
+    assert_ne!(A.fingerprint(), DFA::from(&RegEx::none()).fingerprint());
+}
+
+#[test]
+fn generate() {
+    let lit = |s: &[u8]| s.iter().fold(RegEx::empty(), |re, &c| re.then(&RegEx::set(ByteSet::point(c))));
+    let lower = RegEx::set(ByteSet::range(b'a', b'z'));
+    let digit = RegEx::set(ByteSet::range(b'0', b'9'));
+    let any = RegEx::set(ByteSet::universe()).star();
+    let A = DFA::from(&[lit(b"if"), lower.plus(), digit.plus().then(&any).then(&lit(b"end"))]).minimize();
+
+    for class in once(None).chain((0..3).map(Some)) {
+        let options = GenOptions { max_len: 10, class, ..GenOptions::default() };
+        let mut rng = Pcg32::seed_from_u64(1107);
+        let strings: Vec<_> = (0..100).map(|_| A.generate(&mut rng, &options).unwrap()).collect();
+
+        // accepted, with the class asked for
+        for string in &strings {
+            assert!(string.len() <= 10);
+            let accepted = A.class(A.run(DFA::START, string));
+            assert!(accepted.is_some());
+            assert!(class.is_none_or(|class| accepted == Some(class)));
+        }
+
+        // and the same again from the same seed
+        let mut rng = Pcg32::seed_from_u64(1107);
+        let again: Vec<_> = (0..100).map(|_| A.generate(&mut rng, &options).unwrap()).collect();
+        assert_eq!(strings, again);
+    }
+
+    // no string of the class is short enough, or there is none at all
+    let options = GenOptions { max_len: 3, class: Some(2), ..GenOptions::default() };
+    assert_eq!(A.generate(&mut Pcg32::seed_from_u64(1107), &options), None);
+    let options = GenOptions { class: Some(3), ..GenOptions::default() };
+    assert_eq!(A.generate(&mut Pcg32::seed_from_u64(1107), &options), None);
+
+    // the termination bias keeps strings of a star short, however long allowed
+    let B = DFA::from(&any);
+    let mut rng = Pcg32::seed_from_u64(1107);
+    let options = GenOptions { max_len: usize::MAX, termination: 0.5, ..GenOptions::default() };
+    let total: usize = (0..100).map(|_| B.generate(&mut rng, &options).unwrap().len()).sum();
+    assert!(total < 1000, "{}", total);
+}
+
+#[test]
+fn matcher() {
+    let lit = |s: &[u8]| s.iter().fold(RegEx::empty(), |re, &c| re.then(&RegEx::set(ByteSet::point(c))));
+    let A = DFA::from(&[lit(b"foobar"), lit(b"foo")]).minimize();
+    let mut matcher = A.matcher();
+
+    // chunked the same as in one go, whatever the split
+    for text in &[&b"foobar"[..], b"foo", b"fo", b"", b"foobarx", b"fxobar"] {
+        for split in 0..=text.len() {
+            matcher.reset();
+            matcher.feed(&text[..split]);
+            matcher.feed(&text[split..]);
+            let class = A.class(A.run(DFA::START, text));
+            assert_eq!(matcher.is_accepting(), class);
+            assert_eq!(matcher.is_dead(), A.run(DFA::START, text) == 0);
+        }
+    }
+
+    let mut matcher = DfaMatcher::new(&A);
+    matcher.feed(b"foo");
+    assert_eq!(matcher.is_accepting(), Some(1));
+    matcher.feed(b"bar");
+    assert_eq!(matcher.is_accepting(), Some(0));
+
+    // dead mid-chunk stays dead, even on input that would otherwise match
+    matcher.reset();
+    matcher.feed(b"fox");
+    assert!(matcher.is_dead());
+    matcher.feed(b"foo");
+    assert!(matcher.is_dead());
+    assert_eq!(matcher.is_accepting(), None);
+    assert_eq!(matcher.state(), 0);
+
+    matcher.reset();
+    assert_eq!(matcher.state(), DFA::START);
+    matcher.feed(b"foo");
+    assert_eq!(matcher.is_accepting(), Some(1));
+}
+
+#[test]
+fn dead_states() {
+    let digit = RegEx::set(ByteSet::range(b'0', b'9'));
+    let any = RegEx::set(ByteSet::universe()).star();
+    let never = digit.plus().and(&any.then(&RegEx::set(ByteSet::point(b'z'))));
+    let A = DFA::from(&[never, digit]);
+
+    // dead without being the sink, until minimized
+    let dead: Vec<usize> = (0..A.states().len()).filter(|&id| A.is_dead(id)).collect();
+    assert!(dead.len() >= 2 && dead[0] == 0);
+    assert!(dead.iter().all(|&id| !A.accepting_reachable_from(id)));
+    let M = A.minimize();
+    assert_eq!((0..M.states().len()).filter(|&id| M.is_dead(id)).count(), 1);
+
+    // runs stop at the first dead state, not just the sink
+    let read = std::cell::Cell::new(0);
+    let bytes = b"1234".iter().copied().inspect(|_| read.set(read.get() + 1));
+    assert!(!A.matches_iter(bytes));
+    assert_eq!(read.get(), 2);
+    assert!(A.is_dead(A.run(DFA::START, b"12")));
+    let mut matcher = A.matcher();
+    matcher.feed(b"12");
+    assert!(matcher.is_dead());
+
+    // and scanning is unaffected
+    let text = "0123456789".repeat(10);
+    let tokens: Vec<_> = Scan::new(&A, &text).collect::<Result<_, _>>().unwrap();
+    let expected: Vec<_> = Scan::new(&M, &text).collect::<Result<_, _>>().unwrap();
+    assert_eq!(tokens, expected);
+    assert_eq!(tokens.len(), text.len());
+}
+
+#[test]
+fn minimize_with_ids() {
+    let lit = |s: &[u8]| s.iter().fold(RegEx::empty(), |re, &c| re.then(&RegEx::set(ByteSet::point(c))));
+    let lower = RegEx::set(ByteSet::range(b'a', b'z'));
+    let digit = RegEx::set(ByteSet::range(b'0', b'9'));
+    let rules = [(lit(b"if"), 1), (lit(b"in"), 1), (lower.plus(), 0), (lower.star().then(&digit), 0), (digit.plus(), 0)];
+
+    // overlapping classes, already minimal, and after a product that
+    // leaves equivalent states apart
+    let A = DFA::from_rules(&rules);
+    let B = A.union(&DFA::from(&[RegEx::none(), RegEx::none(), lower.then(&lower)]));
+    for (A, merged) in &[(A, 0), (B, 2)] {
+        let MinimizeReport { dfa: M, ids } = A.minimize_with_ids();
+        assert_eq!(M.states().len(), A.states().len() - merged);
+        assert!(M.equivalent(A));
+        assert_eq!(M.states().len(), M.minimize().states().len());
+
+        assert_eq!(ids[0], Some(0));
+        assert_eq!(ids[DFA::START], Some(DFA::START));
+        for (id, new) in ids.iter().enumerate() {
+            let new = new.unwrap();
+            assert_eq!(A.class(id), M.class(new));
+            for symbol in 0..=255 {
+                assert_eq!(ids[A.step(id, symbol)], Some(M.step(new, symbol)));
+            }
+        }
+    }
+
+    // accepting nothing
+    let MinimizeReport { dfa: M, ids } = DFA::from(&RegEx::none()).minimize_with_ids();
+    assert_eq!(M.states().len(), 2);
+    assert_eq!(ids, [Some(0), Some(DFA::START)]);
+}
+
+#[test]
+fn state_equivalence() {
+    let lower = RegEx::set(ByteSet::range(b'a', b'z'));
+    let digit = RegEx::set(ByteSet::range(b'0', b'9'));
+    let rules = [lower.plus(), lower.star().then(&digit), digit.plus()];
+    let A = DFA::from(&rules);
+    let A = A.union(&DFA::from(&[RegEx::none(), RegEx::none(), lower.then(&lower)]));
+
+    // every suffix of up to 4 bytes, of each kind and none
+    let mut suffixes = vec![Vec::new()];
+    for len in 0..4 {
+        let longer: Vec<Vec<u8>> = suffixes.iter()
+            .filter(|suffix| suffix.len() == len)
+            .flat_map(|suffix| b"a0-".iter().map(move |&c| [&suffix[..], &[c]].concat()))
+            .collect();
+        suffixes.extend(longer);
+    }
+    let behaviour = |id: usize| -> Vec<_> { suffixes.iter().map(|suffix| A.class(A.run(id, suffix))).collect() };
+
+    let blocks = A.state_equivalence();
+    assert_eq!(blocks, A.state_equivalence());
+    assert_eq!(blocks.len(), A.states().len());
+    assert_eq!((blocks[0], blocks[DFA::START]), (0, 1));
+    for x in 0..blocks.len() {
+        for y in 0..blocks.len() {
+            if blocks[x] == blocks[y] {
+                assert_eq!(behaviour(x), behaviour(y));
+            }
+        }
+    }
+
+    // numbered by smallest state, and as in the minimized DFA
+    let firsts: Vec<_> = (0..blocks.len()).filter(|&id| blocks[..id].iter().all(|&block| block != blocks[id])).collect();
+    assert!(firsts.iter().enumerate().all(|(i, &id)| blocks[id] == i));
+    let MinimizeReport { dfa: M, ids } = A.minimize_with_ids();
+    assert_eq!(M.states().len(), firsts.len());
+    assert!(M.states().len() < A.states().len());
+    assert_eq!(ids, blocks.iter().map(|&block| Some(block)).collect::<Vec<_>>());
+}
+
+#[test]
+fn minimize_trivial() {
+    let a = RegEx::set(ByteSet::point(b'a'));
+    for (re, accepted) in &[(RegEx::none(), &[][..]), (RegEx::empty(), &[""][..]), (a.clone(), &["a"][..])] {
+        let A = DFA::from(re);
+        let M = A.minimize();
+        assert_eq!(M.states().len(), A.states().len());
+        assert!(M.equivalent(&A));
+        assert_eq!(M.states()[0].class, None);
+        for text in &["", "a", "aa", "b"] {
+            assert_eq!(M.matches(text), accepted.contains(text));
+        }
+    }
+
+    // an empty lexeme is no token, rather than one made forever
+    let spans = |re: &RegEx, text: &str| -> Vec<_> {
+        let table = NaiveLexTable::new(&DFA::from(re).minimize());
+        Scan::new(&table, text).map(|token| token.map(|token| token.span).map_err(|_| ())).collect()
+    };
+    for re in &[RegEx::none(), RegEx::empty()] {
+        assert_eq!(spans(re, ""), []);
+        assert_eq!(spans(re, "a"), [Err(())]);
+    }
+    assert_eq!(spans(&a, "aa"), [Ok(0..1), Ok(1..2)]);
+    assert_eq!(spans(&a, "ab"), [Ok(0..1), Err(())]);
+    assert_eq!(spans(&a.star(), "aab"), [Ok(0..2), Err(())]);
+}
+
+#[test]
+fn naive_table_dead_start() {
+    let [a, b] = [b'a', b'b'].map(|x| RegEx::set(ByteSet::point(x)));
+    // accepting nothing, with and without more states than the start
+    for A in &[DFA::from(&RegEx::none()), DFA::from(&[RegEx::none(), a.then(&b).and(&b.then(&a))])] {
+        for table in &[NaiveLexTable::new(A), NaiveLexTable::new(&A.minimize())] {
+            assert_eq!(table.sink(), table.start_state());
+            assert_eq!(table.class(table.sink()), None);
+            for text in &["a", "ab", "\0", "zzz"] {
+                let tokens: Vec<_> = Scan::new(table, text).collect();
+                assert_eq!(tokens.len(), 1);
+                assert!(tokens[0].is_err());
+            }
+            assert_eq!(Scan::new(table, "").count(), 0);
+        }
+    }
+}
+
+#[test]
+fn narrow_naive_table() {
+    let digit = RegEx::set(ByteSet::range(b'0', b'9'));
+    let A = DFA::from(&[digit.plus(), RegEx::set(ByteSet::point(b' ')).plus()]).minimize();
+    let wide = NaiveLexTable::<usize>::from_dfa(&A).unwrap();
+    let narrow = NaiveLexTable::<u16>::from_dfa(&A).unwrap();
+    let default = NaiveLexTable::new(&A);
+    for state in 0..=wide.sink() {
+        assert_eq!(narrow.class(state), wide.class(state));
+        assert_eq!(default.class(state), wide.class(state));
+        for symbol in (0..=255).filter(|_| state != wide.sink()) {
+            assert_eq!(narrow.step(state, symbol), wide.step(state, symbol));
+            assert_eq!(default.step(state, symbol), wide.step(state, symbol));
+        }
+    }
+
+    // a chain accepting `len - 1` bytes, of as many states as u16 can
+    // number, and then one more
+    let chain = |len: usize| {
+        let mut states = vec![State::sink()];
+        states.extend((1..len).map(|id| State::new(once((b'a', id + 1)).collect(), None)));
+        states.push(State::new(BTreeMap::new(), Some(0)));
+        DFA::from_states(states, 1)
+    };
+    let A = chain(0xffff);
+    assert_eq!(A.states().len(), 0x10000);
+    let table = NaiveLexTable::<u16>::from_dfa(&A).unwrap();
+    assert_eq!(table.sink(), 0xffff);
+    let tokens: Vec<_> = Scan::new(&table, &vec![b'a'; 0xfffe]).collect::<Result<_, _>>().unwrap();
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0].span.end, 0xfffe);
+
+    let A = chain(0x10000);
+    let error = NaiveLexTable::<u16>::from_dfa(&A).err().unwrap();
+    assert_eq!(error, IndexTooNarrow::new(0x10001, 0xffff));
+    assert_eq!(error.to_string(), "DFA of 65537 states does not fit a table of state ids up to 65535");
+    assert!(NaiveLexTable::<u32>::from_dfa(&A).is_ok());
+}
+
+#[test]
+#[cfg(feature = "rayon")]
+fn parallel_build() {
+    let lit = |s: &[u8]| s.iter().fold(RegEx::empty(), |re, &c| re.then(&RegEx::set(ByteSet::point(c))));
+    let lower = RegEx::set(ByteSet::range(b'a', b'z'));
+    let digit = RegEx::set(ByteSet::range(b'0', b'9'));
+    let mut rules: Vec<RegEx> = [&b"if"[..], b"in", b"int", b"else", b"while", b"for", b"fn"].iter().map(|s| lit(s)).collect();
+    rules.push(lower.then(&lower.or(&digit).star()));
+    rules.push(digit.plus().then(&lit(b".").then(&digit.plus()).opt()));
+    rules.push(lower.star().then(&digit).then(&lower.star()).and(&lit(b"ab").not()));
+
+    // numbered the same, however many threads compute the derivatives
+    let build = |threads| {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+        pool.install(|| DFA::from(&rules))
+    };
+    let A = build(1);
+    for threads in &[2, 4, 8] {
+        let B = build(*threads);
+        assert_eq!(A.to_bytes(), B.to_bytes());
+        assert_eq!(A.fingerprint(), B.fingerprint());
+    }
+}
+
+#[test]
+fn dense() {
+    let lit = |s: &[u8]| s.iter().fold(RegEx::empty(), |re, &c| re.then(&RegEx::set(ByteSet::point(c))));
+    let digit = RegEx::set(ByteSet::range(b'0', b'9'));
+    let space = RegEx::set(ByteSet::point(b' ')).plus();
+    let A = DFA::from(&[lit(b"if"), lit(b"in"), digit.plus(), space]);
+    let M = A.minimize();
+
+    for dfa in &[&A, &M] {
+        let dense: DenseDFA = dfa.to_dense();
+        assert_eq!(dense.len(), dfa.states().len());
+        for state in 0..dfa.states().len() {
+            assert_eq!(LexTable::class(&dense, state), dfa.class(state));
+            assert_eq!(LexTable::is_dead(&dense, state), dfa.is_dead(state));
+            for symbol in 0..=255 {
+                assert_eq!(dense.step(state, symbol), dfa.step(state, symbol));
+            }
+        }
+        for text in &["if", "in", "i", "", "42", "4x2", "  "] {
+            assert_eq!(dense.matches(text), dfa.matches(text));
+        }
+        assert!(!dense.matches_bytes(b"\xff"));
+
+        let text = "if 12 in  7 ifx";
+        let a: Vec<_> = Scan::new(*dfa, text).map(|token| token.map_err(|_| ())).collect();
+        let b: Vec<_> = Scan::new(&dense, text).map(|token| token.map_err(|_| ())).collect();
+        assert_eq!(a, b);
+    }
+}
+
+/// Reader yielding `len` bytes of repeated `pattern`, without allocating them.
+struct Repeat {
+    pattern: &'static [u8],
+    pos: usize,
+    len: usize,
+}
+
+impl std::io::Read for Repeat {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let offset = self.pos % self.pattern.len();
+        let n = buf.len().min(self.len - self.pos).min(self.pattern.len() - offset);
+        buf[..n].copy_from_slice(&self.pattern[offset..offset + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[test]
+fn matches_read() {
+    let lowercase = RegEx::set(ByteSet::ASCII_LOWER);
+    let space     = RegEx::set(ByteSet::point(b' '));
+    let words     = lowercase.plus().then(&space).star();
+
+    let A = DFA::from(&words).minimize();
+
+    let pattern = b"lorem ipsum dolor sit amet consectetur adipiscing elit ";
+    assert!(A.matches_read(Repeat { pattern, pos: 0, len: (100 << 20) / pattern.len() * pattern.len() }).unwrap());
+    assert!(!A.matches_read(Repeat { pattern, pos: 0, len: pattern.len() * 1000 - 1 }).unwrap());
+
+    // stops reading at the sink
+    let mut reader = Repeat { pattern: b"lorem ipsum  ", pos: 0, len: 100 << 20 };
+    assert!(!A.matches_read(&mut reader).unwrap());
+    assert!(reader.pos < 100 << 20);
+
+    assert!(A.matches_iter(b"lorem ipsum ".iter().copied()));
+    assert!(!A.matches_iter(std::iter::repeat(b' ')));
+}
+
+#[test]
+fn matches_bytes() {
+    // a frame is delimited by 0x00 and 0xff, with neither inside it
+    let delim = ByteSet::point(0x00).union(&ByteSet::point(0xff));
+    let frame = RegEx::set(ByteSet::point(0x00))
+        .then(&RegEx::set(delim.complement()).star())
+        .then(&RegEx::set(ByteSet::point(0xff)));
+
+    for A in &[DFA::from(&frame), DFA::from(&frame).minimize()] {
+        assert!(A.matches_bytes(&[0x00, 0xff]));
+        assert!(A.matches_bytes(&[0x00, 0x80, 0xc0, 0xfe, 0xff]));
+        assert!(!A.matches_bytes(&[0x00, 0x80, 0x00, 0xff]));
+        assert!(!A.matches_bytes(&[0x00, 0x80]));
+        assert!(!A.matches_bytes(&[]));
+        assert!(!A.matches("\0abc"));
+    }
+}
+
+/// Returns `true` if the quotes in `dot` are balanced, as are the braces and
+/// brackets outside of quotes.
+fn is_balanced(dot: &str) -> bool {
+    let mut depth = Vec::new();
+    let mut chars = dot.chars();
+    let (mut quoted, mut balanced) = (false, true);
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '\\') => { chars.next(); },
+            (_, '"') => quoted = !quoted,
+            (false, '{') => depth.push('}'),
+            (false, '[') => depth.push(']'),
+            (false, '}' | ']') => balanced &= depth.pop() == Some(c),
+            _ => (),
+        }
+    }
+    balanced && !quoted && depth.is_empty()
+}
+
+#[test]
+fn to_dot() {
+    let character  = RegEx::set(ByteSet::ASCII_ALPHA.union(&ByteSet::point(b'_')));
+    let word       = RegEx::set(ByteSet::WORD);
+    let indentifier = character.then(&word.star());
+    let number      = RegEx::set(ByteSet::ASCII_DIGIT).plus();
+
+    let A = DFA::from(&[indentifier, number]).minimize();
+
+    assert_eq!(A.to_dot(), r#"digraph DFA {
+    rankdir=LR;
+    node [shape=circle];
+    start [shape=none, label=""];
+    start -> 1;
+    1 [label="1"];
+    2 [shape=doublecircle, label="2/1"];
+    3 [shape=doublecircle, label="3/0"];
+    1 -> 2 [label="[0-9]"];
+    1 -> 3 [label="[A-Z_a-z]"];
+    2 -> 2 [label="[0-9]"];
+    3 -> 3 [label="[0-9A-Z_a-z]"];
+}
+"#);
+
+    let options = DotOptions { show_sink: true, rankdir: "TB", state_labels: false };
+    let dot = A.to_dot_with(&options);
+    assert!(dot.contains("rankdir=TB;"));
+    assert!(dot.contains("0 [style=dashed, color=gray, label=\"\"];"));
+    assert!(dot.contains("3 [shape=doublecircle, label=\"0\"];"));
+    assert!(dot.contains("2 -> 0 [label=\"[^0-9]\"];"));
+
+    // labels requiring escapes
+    let quoted = RegEx::set(ByteSet::point(b'"')).then(&RegEx::set(ByteSet::from_ranges(&[(b'\\', b'\\'), (b']', b']'), (0, 31)])));
+    for dfa in &[A, DFA::from(&quoted), DFA::from(&quoted).minimize()] {
+        assert!(is_balanced(&dfa.to_dot()), "{}", dfa.to_dot());
+        assert!(is_balanced(&dfa.to_dot_with(&options)), "{}", dfa.to_dot_with(&options));
+    }
+}
+
+#[test]
+fn display() {
+    let character  = RegEx::set(ByteSet::ASCII_ALPHA.union(&ByteSet::point(b'_')));
+    let word       = RegEx::set(ByteSet::WORD);
+    let indentifier = character.then(&word.star());
+    let number      = RegEx::set(ByteSet::ASCII_DIGIT).plus();
+    let any         = RegEx::set(ByteSet::universe()).star();
+
+    let A = DFA::from(&[indentifier, number]).minimize();
+    assert_eq!(A.to_string(), "\
+0: sink
+1: ['0'-'9'] -> 2, ['A'-'Z', '_', 'a'-'z'] -> 3, * -> sink
+2 (class 1): ['0'-'9'] -> 2, * -> sink
+3 (class 0): ['0'-'9', 'A'-'Z', '_', 'a'-'z'] -> 3, * -> sink
+");
+
+    let B = DFA::from(&any.then(&RegEx::set(ByteSet::point(b'\n')))).minimize();
+    assert_eq!(B.to_string(), "\
+0: sink
+1: [0x00-'\\t', 0x0b-0xff] -> 1, ['\\n'] -> 2
+2 (class 0): [0x00-'\\t', 0x0b-0xff] -> 1, ['\\n'] -> 2
+");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde() {
+    let character  = RegEx::set(ByteSet::ASCII_ALPHA.union(&ByteSet::point(b'_')));
+    let word       = RegEx::set(ByteSet::WORD);
+    let indentifier = character.then(&word.star());
+    let number      = RegEx::set(ByteSet::ASCII_DIGIT).plus();
+
+    for A in &[DFA::from(&[indentifier.clone(), number.clone()]), DFA::from(&[indentifier, number]).minimize()] {
+        let bytes = bincode::serialize(A).unwrap();
+        let B: DFA = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(A.to_string(), B.to_string());
+        assert!(A.equivalent(&B));
+        assert!((0..A.states().len()).all(|id| A.classes_at(id) == B.classes_at(id)));
+        assert_eq!(bincode::serialize(&B).unwrap(), bytes);
+    }
+
+    let state = |next: &[(u8, usize)], class| super::State::new(next.iter().copied().collect(), class);
+    let valid = |states: Vec<super::State>| {
+        // not via from_states, which expects well-formed states
+        let bytes = bincode::serialize(&DFA { dead: vec![false; states.len()], states, class_count: 2 }).unwrap();
+        bincode::deserialize::<DFA>(&bytes).map(|_| ()).map_err(|e| e.to_string())
+    };
+    assert_eq!(valid(vec![state(&[], None), state(&[(b'a', 1)], Some(0))]), Ok(()));
+    assert_eq!(valid(vec![state(&[], None), state(&[(b'a', 2)], Some(0))]).unwrap_err(), "transition 1 -(97)-> 2 is out of range");
+    assert_eq!(valid(vec![state(&[], Some(0)), state(&[], Some(0))]).unwrap_err(), "state 0 is not a non-accepting sink");
+    assert_eq!(valid(vec![state(&[(0, 1)], None), state(&[], Some(0))]).unwrap_err(), "state 0 is not a non-accepting sink");
+    assert_eq!(valid(vec![state(&[], None)]).unwrap_err(), "DFA has no start state");
+    let classes = |class, classes: &[usize]| super::State::with_classes(BTreeMap::new(), class, classes.to_vec());
+    assert_eq!(valid(vec![state(&[], None), classes(Some(1), &[0, 1])]), Ok(()));
+    assert_eq!(valid(vec![state(&[], None), classes(Some(0), &[1])]).unwrap_err(), "classes of state 1 are inconsistent");
+    assert_eq!(valid(vec![state(&[], None), classes(Some(0), &[0, 0])]).unwrap_err(), "classes of state 1 are inconsistent");
+    assert_eq!(valid(vec![state(&[], None), classes(None, &[0])]).unwrap_err(), "classes of state 1 are inconsistent");
+    assert_eq!(valid(vec![state(&[], None), classes(Some(0), &[0, 2])]).unwrap_err(), "class of state 1 is out of range for 2 classes");
+
+    let mut bytes = bincode::serialize(&DFA::from(&RegEx::empty())).unwrap();
+    bytes[0] = 2;
+    assert_eq!(bincode::deserialize::<DFA>(&bytes).map(|_| ()).unwrap_err().to_string(), "unsupported DFA format version 2");
+}
+
+#[test]
+fn binary() {
+    let A = DFA::from(&[
+        RegEx::set(ByteSet::point(b' ')).plus(),
+        RegEx::set(ByteSet::ASCII_DIGIT).plus(),
+        RegEx::set(ByteSet::ASCII_ALPHA).then(&RegEx::set(ByteSet::ASCII_ALNUM).star()),
+    ]);
+    let A = [A.minimize(), A];
+
+    for dfa in &A {
+        let bytes = dfa.to_bytes();
+        let table = DfaRef::from_bytes(&bytes).unwrap();
+        assert_eq!(table.len(), dfa.states.len());
+        for id in 0..dfa.states.len() {
+            assert_eq!(table.class(id), dfa.class(id));
+            for symbol in 0..=255 {
+                assert_eq!(table.step(id, symbol), dfa.step(id, symbol));
+            }
+        }
+
+        // no alignment is assumed
+        let mut shifted = vec![0];
+        shifted.extend_from_slice(&bytes);
+        let table = DfaRef::from_bytes(&shifted[1..]).unwrap();
+        assert_eq!(table.step(table.start_state(), b'x'), dfa.step(1, b'x'));
+    }
+
+    let bytes = A[0].to_bytes();
+    let text = "x1 22 y 3z";
+    let naive: Vec<_> = Scan::new(&NaiveLexTable::new(&A[0]), &text).collect::<Result<_, _>>().unwrap();
+    let view: Vec<_> = Scan::new(&DfaRef::from_bytes(&bytes).unwrap(), &text).collect::<Result<_, _>>().unwrap();
+    assert_eq!(view, naive);
+
+    assert_eq!(DfaRef::from_bytes(b"RDF").unwrap_err(), FormatError::BadMagic);
+    assert_eq!(DfaRef::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err(), FormatError::BadLength);
+    let mut corrupt = bytes.clone();
+    corrupt[16 + 4 * (256 + usize::from(b'a'))] = 0xff;
+    assert_eq!(DfaRef::from_bytes(&corrupt).unwrap_err(), FormatError::BadTransition { state: 1, symbol: b'a' });
+    let mut corrupt = bytes;
+    corrupt[4] = 2;
+    assert_eq!(DfaRef::from_bytes(&corrupt).unwrap_err().to_string(), "unsupported DFA table version 2");
+}
+
@@ -0,0 +1,405 @@
+//! Queries about the language recognized by a `RegEx`. These are answered by
+//! inspecting the regex's DFA rather than its expression tree, as this
+//! correctly accounts for `And` and `Not`.
+
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use itertools::Itertools;
+use rand_core::RngCore;
+use super::{RegEx, DFA};
+use super::dfa::{self, eliminate, strings::Strings};
+
+impl RegEx {
+    /// Returns up to `n` distinct strings of length at most `max_len` that are
+    /// recognized by `self`, in the order that they were sampled. Sampling is
+    /// driven entirely by `rng`, so a seeded `rng` gives reproducible output.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet};
+    /// # use rand_pcg::Pcg32;
+    /// # use rand_core::SeedableRng;
+    /// let re = RegEx::set(ByteSet::range(b'a', b'c')).plus();
+    /// let mut rng = Pcg32::seed_from_u64(0);
+    /// for s in re.sample_strings(10, 4, &mut rng) {
+    ///     assert!((1..=4).contains(&s.len()));
+    ///     assert!(re.is_fullmatch(std::str::from_utf8(&s).unwrap()));
+    /// }
+    /// ```
+    #[must_use]
+    pub fn sample_strings<R: RngCore>(&self, n: usize, max_len: usize, rng: &mut R) -> Vec<Vec<u8>> {
+        let dfa = DFA::from(self);
+        let live = live_within(&dfa, max_len);
+
+        let mut seen = HashSet::new();
+        let mut samples = Vec::new();
+
+        if !live[max_len][DFA::START] {
+            return samples
+        }
+
+        // Bounds the number of walks for languages with less than n strings.
+        let mut attempts = 64 * (n + 1);
+        while samples.len() < n && attempts > 0 {
+            let sample = random_walk(&dfa, &live, max_len, rng);
+            if seen.insert(sample.clone()) {
+                samples.push(sample);
+            }
+            attempts -= 1;
+        }
+
+        samples
+    }
+
+    /// Returns `true` iff some string is recognized by both `self` and
+    /// `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet};
+    /// let lower = RegEx::set(ByteSet::range(b'a', b'z'));
+    /// let digit = RegEx::set(ByteSet::range(b'0', b'9'));
+    /// assert!(lower.plus().overlaps(&lower.then(&digit.star())));
+    /// assert!(!lower.plus().overlaps(&digit.plus()));
+    /// ```
+    #[must_use]
+    pub fn overlaps(&self, other: &Self) -> bool {
+        !dfa::overlaps(&[self.clone(), other.clone()]).is_empty()
+    }
+
+    /// Returns every pair of indices `(i, j)`, with `i < j`, such that
+    /// `rules[i]` overlaps `rules[j]`, in ascending order. All pairs are
+    /// checked at once, via the joint DFA of `rules`.
+    ///
+    /// When building a lexer from `rules`, overlaps are resolved in favour of
+    /// the earlier rule, so this reports where that resolution takes place.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet};
+    /// let [i, f] = [b'i', b'f'].map(|x| RegEx::set(ByteSet::point(x)));
+    /// let lower = RegEx::set(ByteSet::range(b'a', b'z'));
+    /// let digit = RegEx::set(ByteSet::range(b'0', b'9'));
+    /// let rules = [i.then(&f), lower.plus(), digit.plus()];
+    /// assert_eq!(RegEx::overlapping_pairs(&rules), [(0, 1)]);
+    /// ```
+    #[must_use]
+    pub fn overlapping_pairs(rules: &[RegEx]) -> Vec<(usize, usize)> {
+        let pairs: BTreeSet<(usize, usize)> = dfa::overlaps(rules).iter()
+            .flat_map(|nullable| nullable.iter().tuple_combinations().map(|(&i, &j)| (i, j)))
+            .collect();
+        pairs.into_iter().collect()
+    }
+
+    /// Returns `true` iff `self` and `other` recognize the same language.
+    ///
+    /// Note that `==` on `RegEx` compares expression trees, and so only
+    /// approximates language equivalence: equal trees recognize the same
+    /// language, but not vice versa. Compare the results of `canonicalize` if
+    /// equality is needed on a per-tree basis (e.g. for use as a map key).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet};
+    /// let a = RegEx::set(ByteSet::point(b'a'));
+    /// assert!(a.star().equivalent(&a.plus().opt()));
+    /// assert_ne!(a.star(), a.plus().opt());
+    /// ```
+    #[must_use]
+    pub fn equivalent(&self, other: &Self) -> bool {
+        self.distinguish(other).is_none()
+    }
+
+    /// Returns a shortest string recognized by exactly one of `self` and
+    /// `other`, taking the smallest bytes first, or `None` if they are
+    /// equivalent. It is found by a breadth-first search of the product of
+    /// their DFAs, so is the most readable witness of how they differ.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet};
+    /// let digit = RegEx::set(ByteSet::range(b'0', b'9'));
+    /// let up_to_3 = digit.then(&digit.opt()).then(&digit.opt());
+    /// assert_eq!(digit.plus().distinguish(&up_to_3), Some(b"0000".to_vec()));
+    /// assert_eq!(digit.plus().distinguish(&digit.then(&digit.star())), None);
+    /// ```
+    #[must_use]
+    pub fn distinguish(&self, other: &Self) -> Option<Vec<u8>> {
+        DFA::from(self).counterexample(&DFA::from(other))
+    }
+
+    /// Returns a regex recognizing the same language as `self`, constructed
+    /// from `self`'s minimal DFA via state elimination. The result depends
+    /// only on the language of `self`, and so two regexes are equivalent iff
+    /// their canonicalizations are equal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet};
+    /// let [a, b] = [b'a', b'b'].map(|x| RegEx::set(ByteSet::point(x)));
+    /// let re1 = a.then(&b).or(&a).then(&b.star());
+    /// let re2 = a.then(&b.star());
+    /// assert_ne!(re1, re2);
+    /// assert_eq!(re1.canonicalize(), re2.canonicalize());
+    /// ```
+    #[must_use]
+    pub fn canonicalize(&self) -> RegEx {
+        eliminate::to_regex(&DFA::from(self).minimize(), 0)
+    }
+
+    /// Returns `true` iff `self` recognizes the empty string. Same as
+    /// `is_nullable`.
+    #[must_use]
+    pub fn accepts_empty(&self) -> bool {
+        self.is_nullable()
+    }
+
+    /// Returns the length of the shortest string recognized by `self`, or
+    /// `None` if the language is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet};
+    /// let ab = RegEx::set(ByteSet::point(b'a')).then(&RegEx::set(ByteSet::point(b'b')));
+    /// assert_eq!(Some(2), ab.shortest_match_len());
+    /// assert_eq!(Some(0), ab.not().shortest_match_len());
+    /// assert_eq!(None, RegEx::none().shortest_match_len());
+    /// ```
+    #[must_use]
+    pub fn shortest_match_len(&self) -> Option<usize> {
+        self.shortest_string().map(|string| string.len())
+    }
+
+    /// Returns a shortest string recognized by `self`, or `None` if the
+    /// language is empty. Of all shortest strings, the lexicographically
+    /// smallest is returned.
+    #[must_use]
+    pub fn shortest_string(&self) -> Option<Vec<u8>> {
+        let dfa = DFA::from(self);
+        let n = dfa.states().len();
+
+        // breadth-first search from the start state, recording for each
+        // state the (state, symbol) it was first reached from
+        let mut parent: Vec<Option<(usize, u8)>> = vec![None; n];
+        let mut visited = vec![false; n];
+        let mut queue = VecDeque::from(vec![DFA::START]);
+        visited[DFA::START] = true;
+
+        while let Some(id) = queue.pop_front() {
+            if dfa.class(id).is_some() {
+                let mut string = Vec::new();
+                let mut id = id;
+                while let Some((prev, symbol)) = parent[id] {
+                    string.push(symbol);
+                    id = prev;
+                }
+                string.reverse();
+                return Some(string)
+            }
+            for symbol in 0..=255 {
+                let next = dfa.step(id, symbol);
+                if !visited[next] {
+                    visited[next] = true;
+                    parent[next] = Some((id, symbol));
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Returns `true` iff `self` recognizes finitely many strings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet};
+    /// let a = RegEx::set(ByteSet::point(b'a'));
+    /// assert!(a.then(&a).is_finite());
+    /// assert!(!a.star().is_finite());
+    /// assert!(a.star().and(&a.then(&a)).is_finite());
+    /// ```
+    #[must_use]
+    pub fn is_finite(&self) -> bool {
+        longest_path(&DFA::from(self)) != Some(None)
+    }
+
+    /// Returns the length of the longest string recognized by `self`. `None`
+    /// is returned if the language is empty or infinite.
+    #[must_use]
+    pub fn longest_match_len(&self) -> Option<usize> {
+        longest_path(&DFA::from(self)).flatten()
+    }
+
+    /// Returns an iterator over every string of length at most `max_len`
+    /// recognized by `self`, in shortlex order (i.e. ordered by length, then
+    /// lexicographically). Strings are generated lazily.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet};
+    /// let ab = RegEx::set(ByteSet::range(b'a', b'b'));
+    /// let strings: Vec<_> = ab.star().strings_up_to(2).collect();
+    /// assert_eq!(strings, [&b""[..], b"a", b"b", b"aa", b"ab", b"ba", b"bb"]);
+    /// ```
+    pub fn strings_up_to(&self, max_len: usize) -> impl Iterator<Item = Vec<u8>> {
+        Strings::new(DFA::from(self), max_len).map(|(string, _)| string)
+    }
+
+    /// Returns the number of strings of length exactly `len` recognized by
+    /// `self`, saturating at `u128::MAX`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet};
+    /// let digit = RegEx::set(ByteSet::range(b'0', b'9'));
+    /// assert_eq!(100, digit.then(&digit).count_matches_of_len(2));
+    /// ```
+    #[must_use]
+    pub fn count_matches_of_len(&self, len: usize) -> u128 {
+        DFA::from(self).count_matches_of_len(len)
+    }
+
+    /// Returns the number of strings of length at most `max_len` recognized
+    /// by `self`, saturating at `u64::MAX`.
+    #[must_use]
+    pub fn count_up_to(&self, max_len: usize) -> u64 {
+        let dfa = DFA::from(self);
+        let n = dfa.states().len();
+
+        // counts[id] = number of strings of the current length leading to id
+        let mut counts = vec![0_u64; n];
+        counts[DFA::START] = 1;
+        let mut total = 0_u64;
+
+        for len in 0..=max_len {
+            for (id, &count) in counts.iter().enumerate() {
+                if dfa.class(id).is_some() {
+                    total = total.saturating_add(count);
+                }
+            }
+            if len < max_len {
+                let mut next = vec![0_u64; n];
+                for (id, &count) in counts.iter().enumerate().filter(|(_, &count)| count > 0) {
+                    for symbol in 0..=255 {
+                        let dest = &mut next[dfa.step(id, symbol)];
+                        *dest = dest.saturating_add(count);
+                    }
+                }
+                counts = next;
+            }
+        }
+
+        total
+    }
+}
+
+// =================
+// === INTERNALS ===
+// =================
+
+/// `live[l][id]` is `true` iff an accept state is reachable from state `id`
+/// in at most `l` steps.
+fn live_within(dfa: &DFA, max_len: usize) -> Vec<Vec<bool>> {
+    let n = dfa.states().len();
+    let mut live = vec![(0..n).map(|id| dfa.class(id).is_some()).collect::<Vec<_>>()];
+    for l in 1..=max_len {
+        let prev = &live[l - 1];
+        let layer = (0..n).map(|id| {
+            prev[id] || (0..=255).any(|symbol| prev[dfa.step(id, symbol)])
+        }).collect();
+        live.push(layer);
+    }
+    live
+}
+
+/// Walks from the start state to an accept state in at most `max_len` steps,
+/// never entering a state from which no accept state is reachable in time.
+fn random_walk<R: RngCore>(dfa: &DFA, live: &[Vec<bool>], max_len: usize, rng: &mut R) -> Vec<u8> {
+    let mut id = DFA::START;
+    let mut string = Vec::new();
+
+    for remaining in (1..=max_len).rev() {
+        let symbols: Vec<u8> = (0..=255).filter(|&symbol| live[remaining - 1][dfa.step(id, symbol)]).collect();
+
+        if symbols.is_empty() || (dfa.class(id).is_some() && rng.next_u32() & 1 == 0) {
+            return string
+        }
+
+        let symbol = symbols[rng.next_u32() as usize % symbols.len()];
+        string.push(symbol);
+        id = dfa.step(id, symbol);
+    }
+
+    string
+}
+
+fn successors(dfa: &DFA, id: usize) -> Vec<usize> {
+    let mut next: Vec<usize> = (0..=255).map(|symbol| dfa.step(id, symbol)).collect();
+    next.sort_unstable();
+    next.dedup();
+    next
+}
+
+/// Returns the states that are both reachable from the start state and from
+/// which some accept state is reachable. All other states are dead weight.
+fn live_states(dfa: &DFA) -> Vec<bool> {
+    let reachable = dfa.reachable_from(DFA::START);
+    let live = dfa.live(|class| class.is_some());
+    reachable.into_iter().zip(live).map(|(r, l)| r && l).collect()
+}
+
+/// Returns the length of the longest path from the start state to an accept
+/// state: `None` if there is no such path, and `Some(None)` if paths are
+/// unbounded (i.e. there is a cycle amongst the live states).
+#[allow(clippy::option_option)]
+fn longest_path(dfa: &DFA) -> Option<Option<usize>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Mark { Unvisited, OnStack, Done }
+
+    let live = live_states(dfa);
+    if !live[DFA::START] {
+        return None
+    }
+
+    let n = dfa.states().len();
+    let mut marks = vec![Mark::Unvisited; n];
+    let mut longest = vec![0_usize; n];
+
+    // iterative post-order depth-first search over the live states
+    let mut stack = vec![(DFA::START, successors(dfa, DFA::START))];
+    marks[DFA::START] = Mark::OnStack;
+    while let Some((id, next)) = stack.last_mut() {
+        let id = *id;
+        if let Some(next) = next.pop() {
+            if !live[next] {
+                continue;
+            }
+            match marks[next] {
+                Mark::OnStack   => return Some(None),
+                Mark::Done      => longest[id] = longest[id].max(longest[next] + 1),
+                Mark::Unvisited => {
+                    marks[next] = Mark::OnStack;
+                    stack.push((next, successors(dfa, next)));
+                },
+            }
+        } else {
+            marks[id] = Mark::Done;
+            stack.pop();
+            if let Some((parent, _)) = stack.last() {
+                longest[*parent] = longest[*parent].max(longest[id] + 1);
+            }
+        }
+    }
+
+    Some(Some(longest[DFA::START]))
+}
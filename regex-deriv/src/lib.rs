@@ -7,21 +7,67 @@
 #![warn(missing_docs)]
 
 mod byte_set;
-pub use self::byte_set::ByteSet;
+pub use self::byte_set::{
+    ByteSet,
+    ClassParseError,
+    ClassParseErrorKind,
+};
+
+mod codepoint_set;
+pub use self::codepoint_set::CodepointSet;
 
 mod regex;
 pub use self::regex::{
     RegEx,
     Operator,
+    DerivCache,
 };
 
+mod lang;
+
 mod dfa;
-pub use self::dfa::DFA;
+pub use self::dfa::{
+    BuildError,
+    BuildLimits,
+    BuildObserver,
+    DFA,
+    DenseDFA,
+    DfaMatcher,
+    DfaRef,
+    DfaStats,
+    DotOptions,
+    Eviction,
+    FindIter,
+    FormatError,
+    GenOptions,
+    LazyDFA,
+    LazyDFABuilder,
+    MinimizeReport,
+    Priority,
+    SearchDFA,
+    TooLarge,
+};
+
+mod nfa;
+pub use self::nfa::NFA;
 
 mod table;
 pub use self::table::{
+    build_table,
+    ClassLexTable,
+    Command,
+    CompressedLexTable,
+    IndexTooNarrow,
     LexTable,
+    Mismatch,
     NaiveLexTable,
+    ShadowedRules,
+    SparseLexTable,
+    StaticLexTable,
+    TableError,
+    TableIndex,
+    tables_equivalent,
+    TrailingContext,
 };
 
 mod scan;
@@ -31,6 +77,37 @@ pub use self::scan::{
     ScanError
 };
 
+#[cfg(feature = "syntax")]
+pub mod syntax;
+
+/// Constructs a `RegEx` that recognizes the UTF-8 encoding of any one `char`.
+/// Invalid UTF-8 (such as overlong encodings or encoded surrogates) is never
+/// recognized.
+///
+/// # Examples
+///
+/// ```
+/// # use regex_deriv::utf8_any;
+/// let any = utf8_any();
+/// assert!(any.is_fullmatch("a") && any.is_fullmatch("é") && any.is_fullmatch("𐍈"));
+/// assert!(!any.is_fullmatch("") && !any.is_fullmatch("ab"));
+/// ```
+#[must_use]
+pub fn utf8_any() -> RegEx {
+    let cont = RegEx::set(ByteSet::UTF8_CONT);
+    (2..=4).fold(RegEx::set(ByteSet::utf8_lead(1)), |acc, len| {
+        // group the leads by the bytes that may follow them
+        let mut leads: std::collections::BTreeMap<ByteSet, ByteSet> = std::collections::BTreeMap::new();
+        for lead in ByteSet::utf8_lead(len).bytes() {
+            leads.entry(ByteSet::utf8_second(lead)).or_insert_with(ByteSet::empty).insert(lead);
+        }
+        let tail = (2..len).fold(RegEx::empty(), |tail, _| tail.then(&cont));
+        leads.into_iter().fold(acc, |acc, (second, lead)| {
+            acc.or(&RegEx::set(lead).then(&RegEx::set(second)).then(&tail))
+        })
+    })
+}
+
 // =================
 // === INTERNALS ===
 // =================
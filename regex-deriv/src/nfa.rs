@@ -0,0 +1,163 @@
+use std::collections::{BTreeMap, BTreeSet};
+use super::{ByteSet, RegEx, DFA};
+use super::dfa::{State as DFAState, approx_deriv_classes};
+
+/// Nondeterministic finite automaton without epsilon transitions, built from
+/// Antimirov partial derivatives. Each state corresponds to a partial
+/// derivative of one of the input regexes; accepting states are labelled
+/// with the index of that regex.
+pub struct NFA {
+    states: Vec<State>,
+    starts: Vec<usize>,
+}
+
+/// A state of an `NFA`.
+pub struct State {
+    next: Vec<(ByteSet, usize)>,
+    class: Option<usize>,
+}
+
+impl From<&RegEx> for NFA {
+    fn from(regex: &RegEx) -> Self {
+        NFABuilder::build(std::slice::from_ref(regex))
+    }
+}
+
+impl<'a, T> From<T> for NFA
+where
+    T: IntoIterator<Item = &'a RegEx>,
+{
+    fn from(regexes: T) -> Self {
+        NFABuilder::build(&regexes.into_iter().cloned().collect::<Vec<_>>())
+    }
+}
+
+impl NFA {
+    /// Returns the states of `self`.
+    #[must_use]
+    pub fn states(&self) -> &[State] {
+        &self.states
+    }
+
+    /// Returns the start states of `self`, one per input regex.
+    #[must_use]
+    pub fn starts(&self) -> &[usize] {
+        &self.starts
+    }
+
+    /// Constructs the equivalent DFA via subset construction. The class of
+    /// each DFA state is the smallest class amongst its NFA states.
+    #[must_use]
+    pub fn determinize(&self) -> DFA {
+        // s0 = sink state (the empty subset), s1 = start state
+        let mut states = vec![DFAState::sink()];
+        let mut subset2idx: BTreeMap<BTreeSet<usize>, usize> = BTreeMap::new();
+        subset2idx.insert(BTreeSet::new(), 0);
+
+        let start: BTreeSet<usize> = self.starts.iter().copied().collect();
+        let mut stack = vec![start.clone()];
+        subset2idx.insert(start.clone(), DFA::START);
+        states.push(DFAState::new(BTreeMap::new(), self.class(&start)));
+
+        while let Some(subset) = stack.pop() {
+            let i = subset2idx[&subset];
+            for symbol in 0..=255 {
+                let dest: BTreeSet<usize> = subset.iter()
+                    .flat_map(|&id| &self.states[id].next)
+                    .filter(|(set, _)| set.contains(symbol))
+                    .map(|&(_, dest)| dest)
+                    .collect();
+
+                let j = if let Some(&j) = subset2idx.get(&dest) {
+                    j
+                } else {
+                    let j = states.len();
+                    states.push(DFAState::new(BTreeMap::new(), self.class(&dest)));
+                    subset2idx.insert(dest.clone(), j);
+                    stack.push(dest);
+                    j
+                };
+
+                states[i].insert(symbol, j);
+            }
+        }
+
+        DFA::from_states(states, self.starts.len())
+    }
+}
+
+impl State {
+    /// Returns the outgoing transitions of `self`, each labelled with the set
+    /// of bytes it is taken on.
+    #[must_use]
+    pub fn transitions(&self) -> &[(ByteSet, usize)] {
+        &self.next
+    }
+
+    /// Returns the class of `self` if it is accepting.
+    #[must_use]
+    pub fn class(&self) -> Option<usize> {
+        self.class
+    }
+}
+
+// =================
+// === INTERNALS ===
+// =================
+
+impl NFA {
+    fn class(&self, subset: &BTreeSet<usize>) -> Option<usize> {
+        subset.iter().filter_map(|&id| self.states[id].class).min()
+    }
+}
+
+struct NFABuilder {
+    states: Vec<State>,
+    terms: Vec<(usize, RegEx)>,
+    term2idx: BTreeMap<(usize, RegEx), usize>,
+}
+
+impl NFABuilder {
+    fn build(regexes: &[RegEx]) -> NFA {
+        let mut builder = Self { states: Vec::new(), terms: Vec::new(), term2idx: BTreeMap::new() };
+
+        let starts = regexes.iter().cloned().enumerate().map(|term| builder.add_state(term)).collect();
+
+        let mut i = 0;
+        while i < builder.terms.len() {
+            builder.explore(i);
+            i += 1;
+        }
+
+        NFA { states: builder.states, starts }
+    }
+
+    fn add_state(&mut self, term: (usize, RegEx)) -> usize {
+        if let Some(&idx) = self.term2idx.get(&term) {
+            return idx;
+        }
+        let idx = self.states.len();
+        let class = if term.1.is_nullable() { Some(term.0) } else { None };
+        self.states.push(State { next: Vec::new(), class });
+        self.terms.push(term.clone());
+        self.term2idx.insert(term, idx);
+        idx
+    }
+
+    fn explore(&mut self, i: usize) {
+        let (class, term) = self.terms[i].clone();
+
+        // all bytes of a derivative class have the same partial derivatives
+        let mut next: BTreeMap<usize, ByteSet> = BTreeMap::new();
+        for set in approx_deriv_classes(&term) {
+            let c = set.smallest().unwrap();
+            for pd in term.partial_deriv(c) {
+                let j = self.add_state((class, pd));
+                let label = next.entry(j).or_insert_with(ByteSet::empty);
+                *label = label.union(&set);
+            }
+        }
+
+        self.states[i].next = next.into_iter().map(|(j, set)| (set, j)).collect();
+    }
+}
@@ -1,13 +1,24 @@
 #![allow(clippy::match_same_arms)]
 
+// nodes are shared between threads when building DFAs in parallel
+#[cfg(not(feature = "rayon"))]
 use std::rc::Rc;
+#[cfg(feature = "rayon")]
+use std::sync::Arc as Rc;
 use std::iter::once;
+use std::collections::{BTreeSet, HashMap};
+use std::io::{self, Read};
 use std::fmt::Formatter;
 use std::fmt::Error;
 use std::fmt::Debug;
+use std::fmt::Display;
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use std::collections::hash_map::DefaultHasher;
 
 use itertools::Itertools;
 use super::ByteSet;
+use super::byte_set::write_escaped;
 
 macro_rules! range_impl {
     ($uint:ty) => {
@@ -50,12 +61,39 @@ macro_rules! range_impl {
 
 /// Regular expression object. Internally, represented by an
 /// expression tree.
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+///
+/// Besides the named constructors, expressions can be combined with
+/// operators: `+` (concatenation), `|` (alternation), `&` (intersection)
+/// and `!` (complement).
+///
+/// # Examples
+///
+/// ```
+/// # use regex_deriv::{RegEx, ByteSet};
+/// let alpha = RegEx::set(ByteSet::range(b'a', b'z')) | RegEx::set(ByteSet::range(b'A', b'Z'));
+/// let digit = RegEx::set(ByteSet::range(b'0', b'9'));
+/// let underscore = RegEx::set(ByteSet::point(b'_'));
+///
+/// let identifier = (&alpha | &underscore) + (&alpha | &digit | &underscore).star();
+/// let not_keyword = !(RegEx::set(ByteSet::point(b'i')) + RegEx::set(ByteSet::point(b'f')));
+///
+/// let rule = identifier & not_keyword;
+/// assert!(rule.is_fullmatch("if_"));
+/// assert!(!rule.is_fullmatch("if"));
+/// ```
+#[derive(Clone)]
 pub struct RegEx {
-    root: Rc<Operator>,
+    root: Rc<Node>,
 }
 
-#[derive(PartialEq, Eq, PartialOrd, Ord)]
+/// A regex node, along with its hash, computed once on construction from
+/// the hashes of its children.
+struct Node {
+    operator: Operator,
+    hash: u64,
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Operator {
     None,
     Epsilon,
@@ -82,6 +120,7 @@ pub enum Operator {
     /// * No child is None
     /// * No child is Or
     /// * At most 1 child is a Set
+    /// * At most 1 child is a complemented Set, and not alongside a Set
     Or(Vec<RegEx>),
 
     /// # Invariants
@@ -90,13 +129,21 @@ pub enum Operator {
     /// * No child is Epsilon
     /// * No child is And
     /// * At most 1 child is a Set
+    /// * At most 1 child is a complemented Set, and not alongside a Set
     And(Vec<RegEx>),
 
     /// # Invariants
     /// * Child is not None
-    /// * Child is not a Set
     /// * Child is not Not
     Not(RegEx),
+
+    /// # Invariants
+    /// * At least 2 children
+    /// * No child is None
+    /// * No child is Epsilon
+    /// * No child is Interleave
+    /// * Children are sorted
+    Interleave(Vec<RegEx>),
 }
 
 impl RegEx {
@@ -179,7 +226,7 @@ impl RegEx {
     // aka. Kleene closure
     #[must_use]
     pub fn star(&self) -> Self {
-        match *self.root {
+        match self.operator() {
             Operator::None | Operator::Epsilon => RegEx::new(Operator::Epsilon),
             Operator::Star(_)                  => self.clone(),
             _                                  => RegEx::new(Operator::Star(self.clone())),
@@ -193,7 +240,8 @@ impl RegEx {
             A: IntoIterator<Item=&'a RegEx>,
             B: IntoIterator<Item=&'a RegEx>,
         {
-            let refs = merged_sets(res1.into_iter().merge(res2), |acc, other| *acc = acc.union(other));
+            let refs = merged_sets(res1.into_iter().merge(res2).dedup(), ByteSet::union_assign);
+            let refs = merged_complemented_sets(refs, false);
     
             if refs.is_empty() {
                 RegEx::new(Operator::None)
@@ -222,7 +270,8 @@ impl RegEx {
             A: IntoIterator<Item=&'a RegEx>,
             B: IntoIterator<Item=&'a RegEx>,
         {
-            let refs = merged_sets(res1.into_iter().merge(res2), |acc, other| *acc = acc.intersection(other));
+            let refs = merged_sets(res1.into_iter().merge(res2).dedup(), ByteSet::intersection_assign);
+            let refs = merged_complemented_sets(refs, true);
     
             if refs.is_empty() {
                 RegEx::new(Operator::None)
@@ -236,8 +285,9 @@ impl RegEx {
         match (self.operator(), other.operator()) {
             (_                , Operator::None   ) => RegEx::new(Operator::None),
             (Operator::None   , _                ) => RegEx::new(Operator::None),
-            (_                , Operator::Epsilon) => if self.is_nullable() { RegEx::new(Operator::Epsilon) } else { RegEx::new(Operator::None) }, // TODO: check
-            (Operator::Epsilon, _                ) => if other.is_nullable() { RegEx::new(Operator::Epsilon) } else { RegEx::new(Operator::None) }, // TODO: check
+            // r & e = e if e in L(r), otherwise r & e = {}
+            (_                , Operator::Epsilon) => if self.is_nullable() { RegEx::new(Operator::Epsilon) } else { RegEx::new(Operator::None) },
+            (Operator::Epsilon, _                ) => if other.is_nullable() { RegEx::new(Operator::Epsilon) } else { RegEx::new(Operator::None) },
             (Operator::Set(x) , Operator::Set(y) ) => RegEx::set(x.intersection(&y)),
             (Operator::And(a) , Operator::And(b) ) => and_aux(a, b),
             (Operator::And(a) , _                ) => and_aux(a, once(other)),
@@ -255,6 +305,43 @@ impl RegEx {
         }
     }
 
+    /// Constructs the interleaving (aka. shuffle) of `self` and `other`, that
+    /// is, the language of strings formed by interspersing a string of `self`
+    /// with a string of `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet};
+    /// let [a, b, c] = [b'a', b'b', b'c'].map(|x| RegEx::set(ByteSet::point(x)));
+    /// let re = a.then(&b).interleave(&c);
+    /// assert!(re.is_fullmatch("cab"));
+    /// assert!(re.is_fullmatch("acb"));
+    /// assert!(re.is_fullmatch("abc"));
+    /// assert!(!re.is_fullmatch("bca"));
+    /// ```
+    #[must_use]
+    pub fn interleave(&self, other: &Self) -> Self {
+        fn interleave_aux<'a, A, B>(res1: A, res2: B) -> RegEx
+        where
+            A: IntoIterator<Item=&'a RegEx>,
+            B: IntoIterator<Item=&'a RegEx>,
+        {
+            RegEx::new(Operator::Interleave(res1.into_iter().merge(res2).cloned().collect()))
+        }
+
+        match (self.operator(), other.operator()) {
+            (_                        , Operator::Epsilon      ) => self.clone(),
+            (Operator::Epsilon        , _                      ) => other.clone(),
+            (_                        , Operator::None         ) => RegEx::new(Operator::None),
+            (Operator::None           , _                      ) => RegEx::new(Operator::None),
+            (Operator::Interleave(a)  , Operator::Interleave(b)) => interleave_aux(a, b),
+            (Operator::Interleave(a)  , _                      ) => interleave_aux(a, once(other)),
+            (_                        , Operator::Interleave(b)) => interleave_aux(once(self), b),
+            (_                        , _                      ) => interleave_aux(once(self), once(other)),
+        }
+    }
+
     // === non-canonical constructors ===
 
     #[must_use]
@@ -272,18 +359,598 @@ impl RegEx {
         self.and(&other.not())
     }
 
+    /// Constructs a regular expression recognizing the strings of `pattern`
+    /// that lie within a single line, i.e. that contain no `\n` or `\r`
+    /// bytes. Matching this in full against a line is what `^pattern$` means
+    /// in multiline mode.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet};
+    /// let any = RegEx::set(ByteSet::universe()).star();
+    /// let line = RegEx::line_of(&any);
+    /// assert!(line.is_fullmatch("no newlines here"));
+    /// assert!(!line.is_fullmatch("one\ntwo"));
+    /// ```
+    #[must_use]
+    pub fn line_of(pattern: &RegEx) -> Self {
+        let newline = ByteSet::point(b'\n').union(&ByteSet::point(b'\r'));
+        pattern.and(&RegEx::set(newline.complement()).star())
+    }
+
     // === other functions ===
 
     #[must_use]
     pub fn deriv(&self, a: u8) -> Self {
-        fn deriv_cat(children: &[RegEx], a: u8) -> RegEx {
-            fn aux(r: &RegEx, s: &RegEx, a: u8) -> RegEx {
+        self.deriv_with(a, &mut |re, a| re.deriv(a))
+    }
+
+    /// Same as `deriv`, except that the derivatives of all subexpressions
+    /// are looked up in, and recorded to, `cache`.
+    #[must_use]
+    pub fn deriv_cached(&self, a: u8, cache: &mut DerivCache) -> Self {
+        cache.deriv(self, a)
+    }
+
+    /// Returns the Antimirov partial derivatives of `self` with respect to
+    /// `a`: a set of expressions whose union recognizes the same language as
+    /// `self.deriv(a)`. Intersection and complement are handled by taking
+    /// pairwise intersections, and the complement of the union, respectively.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet};
+    /// let [a, b, c] = [b'a', b'b', b'c'].map(|x| RegEx::set(ByteSet::point(x)));
+    /// let re = a.then(&b).or(&a.then(&c));
+    /// assert_eq!(re.partial_deriv(b'a').into_iter().collect::<Vec<_>>(), [b, c]);
+    /// ```
+    #[must_use]
+    pub fn partial_deriv(&self, a: u8) -> BTreeSet<RegEx> {
+        fn then_each(res: BTreeSet<RegEx>, s: &RegEx) -> BTreeSet<RegEx> {
+            res.into_iter().map(|r| r.then(s)).collect()
+        }
+
+        let mut res: BTreeSet<RegEx> = match self.operator() {
+            Operator::None
+            | Operator::Epsilon => BTreeSet::new(),
+            Operator::Set(s)    => if s.contains(a) { once(RegEx::empty()).collect() } else { BTreeSet::new() },
+            Operator::Cat(res)  => {
+                let (r, s) = match &res[..] {
+                    [] | [_] => unreachable!("Should be impossible for Cat node to have <2 children."),
+                    [r, s] => (r, s.clone()),
+                    // Tail of children still form a valid Cat node.
+                    [r, ..] => (r, RegEx::new(Operator::Cat(res[1..].to_vec()))),
+                };
+                let mut pds = then_each(r.partial_deriv(a), &s);
+                if r.is_nullable() {
+                    pds.extend(s.partial_deriv(a));
+                }
+                pds
+            },
+            Operator::Star(re)  => then_each(re.partial_deriv(a), self),
+            Operator::Or(res)   => res.iter().flat_map(|re| re.partial_deriv(a)).collect(),
+            Operator::And(res)  => {
+                res[1..].iter().fold(res[0].partial_deriv(a), |acc, re| {
+                    let pds = re.partial_deriv(a);
+                    acc.iter().cartesian_product(&pds).map(|(p, q)| p.and(q)).collect()
+                })
+            },
+            Operator::Not(re)   => {
+                let union = re.partial_deriv(a).iter().fold(RegEx::none(), |acc, pd| acc.or(pd));
+                once(union.not()).collect()
+            },
+            Operator::Interleave(res) => {
+                res.iter().enumerate().flat_map(|(i, re)| {
+                    re.partial_deriv(a).into_iter().map(move |pd| {
+                        res.iter().enumerate().fold(RegEx::empty(), |acc, (j, other)| {
+                            acc.interleave(if i == j { &pd } else { other })
+                        })
+                    })
+                }).collect()
+            },
+        };
+
+        res.remove(&RegEx::none());
+        res
+    }
+
+    /// Rebuilds `self` with every set `s` replaced by `f(s)`. Subexpressions
+    /// left unchanged by the transformation are shared with `self`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet};
+    /// let lower = RegEx::set(ByteSet::range(b'a', b'z')).plus();
+    /// let alpha = lower.map_sets(|s| s.union(&ByteSet::range(b'A', b'Z')));
+    /// assert!(alpha.is_fullmatch("aBc"));
+    /// ```
+    #[must_use]
+    pub fn map_sets<F: Fn(&ByteSet) -> ByteSet>(&self, f: F) -> Self {
+        self.map_sets_with(&f)
+    }
+
+    /// Rebuilds `self` with every set replaced by its image under `f`, where
+    /// bytes mapped to `None` are dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet};
+    /// let lower = RegEx::set(ByteSet::range(b'a', b'z')).plus();
+    /// let upper = lower.map_bytes(|b| Some(b.to_ascii_uppercase()));
+    /// assert!(upper.is_fullmatch("ABC"));
+    /// assert!(!upper.is_fullmatch("abc"));
+    /// ```
+    #[must_use]
+    pub fn map_bytes<F: Fn(u8) -> Option<u8>>(&self, f: F) -> Self {
+        self.map_sets(|set| {
+            set.bytes().filter_map(&f).collect()
+        })
+    }
+
+    /// Returns the longest byte string that every string recognized by
+    /// `self` begins with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet};
+    /// let [a, b, c, d] = [b'a', b'b', b'c', b'd'].map(|x| RegEx::set(ByteSet::point(x)));
+    /// assert_eq!(b"ab", &a.then(&b).then(&c.or(&d)).literal_prefix()[..]);
+    /// assert_eq!(b"", &a.opt().then(&b).then(&c).literal_prefix()[..]);
+    /// ```
+    #[must_use]
+    pub fn literal_prefix(&self) -> Vec<u8> {
+        fn common_prefix(mut acc: Vec<u8>, other: &[u8]) -> Vec<u8> {
+            let len = acc.iter().zip(other).take_while(|(a, b)| a == b).count();
+            acc.truncate(len);
+            acc
+        }
+
+        match self.operator() {
+            Operator::None
+            | Operator::Epsilon
+            | Operator::Star(_)
+            | Operator::Not(_)
+            | Operator::Interleave(_) => Vec::new(),
+            Operator::Set(s)    => s.is_single().map(|b| vec![b]).unwrap_or_default(),
+            Operator::Cat(res)  => {
+                let mut prefix = Vec::new();
+                for re in res {
+                    if let Operator::Set(s) = re.operator() {
+                        if let Some(b) = s.is_single() {
+                            prefix.push(b);
+                            continue;
+                        }
+                    }
+                    prefix.extend(re.literal_prefix());
+                    break;
+                }
+                prefix
+            },
+            Operator::Or(res)   => {
+                let mut prefixes = res.iter().map(RegEx::literal_prefix);
+                let first = prefixes.next().unwrap_or_default();
+                prefixes.fold(first, |acc, prefix| common_prefix(acc, &prefix))
+            },
+            Operator::And(res)  => {
+                // every child's prefix is mandatory, so if they are all
+                // prefixes of the longest one, that one is mandatory too
+                let prefixes: Vec<_> = res.iter().map(RegEx::literal_prefix).collect();
+                let longest = prefixes.iter().max_by_key(|prefix| prefix.len()).cloned().unwrap_or_default();
+                if prefixes.iter().all(|prefix| longest.starts_with(prefix)) {
+                    longest
+                } else {
+                    Vec::new()
+                }
+            },
+        }
+    }
+
+    /// Returns the one string recognized by `self`, if `self` is (trivially)
+    /// a literal. Sets must be singletons, and stars, complements, and
+    /// alternations of distinct branches are never literals. An intersection
+    /// is a literal if one of its children is a literal recognized by all of
+    /// the others.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet};
+    /// let [a, b, c] = [b'a', b'b', b'c'].map(|x| RegEx::set(ByteSet::point(x)));
+    /// assert_eq!(Some(b"abc".to_vec()), a.then(&b).then(&c).as_literal());
+    /// assert_eq!(Some(b"".to_vec()), RegEx::empty().as_literal());
+    /// assert_eq!(None, a.or(&b).as_literal());
+    /// assert_eq!(None, a.star().as_literal());
+    /// ```
+    #[must_use]
+    pub fn as_literal(&self) -> Option<Vec<u8>> {
+        match self.operator() {
+            Operator::None
+            | Operator::Star(_)
+            | Operator::Or(_)
+            | Operator::Not(_)
+            | Operator::Interleave(_) => None,
+            Operator::Epsilon   => Some(Vec::new()),
+            Operator::Set(s)    => s.is_single().map(|b| vec![b]),
+            Operator::Cat(res)  => {
+                res.iter().try_fold(Vec::new(), |mut acc, re| {
+                    acc.extend(re.as_literal()?);
+                    Some(acc)
+                })
+            },
+            Operator::And(res)  => {
+                res.iter().filter_map(RegEx::as_literal).find(|literal| {
+                    res.iter().all(|re| re.is_fullmatch_iter(literal.iter().copied()))
+                })
+            },
+        }
+    }
+
+    /// Returns `true` iff `self` is (trivially) a literal, i.e. iff
+    /// `as_literal` returns a string.
+    #[must_use]
+    pub fn is_literal(&self) -> bool {
+        self.as_literal().is_some()
+    }
+
+    /// Returns the set of bytes that strings recognized by `self` may begin
+    /// with. This is exact, except in the presence of `And` and `Not`, where
+    /// it may over-approximate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet};
+    /// let [a, b, c] = [b'a', b'b', b'c'].map(|x| RegEx::set(ByteSet::point(x)));
+    /// assert_eq!(ByteSet::range(b'a', b'b'), a.opt().then(&b).then(&c).prefix_bytes());
+    /// ```
+    #[must_use]
+    pub fn prefix_bytes(&self) -> ByteSet {
+        match self.operator() {
+            Operator::None
+            | Operator::Epsilon => ByteSet::empty(),
+            Operator::Set(s)    => s.clone(),
+            Operator::Cat(res)  => {
+                let mut set = ByteSet::empty();
+                for re in res {
+                    set = set.union(&re.prefix_bytes());
+                    if !re.is_nullable() {
+                        break;
+                    }
+                }
+                set
+            },
+            Operator::Star(re)  => re.prefix_bytes(),
+            Operator::Or(res)   => res.iter().fold(ByteSet::empty(), |acc, re| acc.union(&re.prefix_bytes())),
+            Operator::And(res)  => res.iter().fold(ByteSet::universe(), |acc, re| acc.intersection(&re.prefix_bytes())),
+            Operator::Not(_)    => ByteSet::universe(),
+            Operator::Interleave(res) => res.iter().fold(ByteSet::empty(), |acc, re| acc.union(&re.prefix_bytes())),
+        }
+    }
+
+    /// Returns the regex recognizing the reversal of every string recognized
+    /// by `self`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet};
+    /// let [a, b] = [b'a', b'b'].map(|x| RegEx::set(ByteSet::point(x)));
+    /// let re = a.then(&b.star()).reverse();
+    /// assert!(re.is_fullmatch("bba"));
+    /// assert!(!re.is_fullmatch("abb"));
+    /// ```
+    #[must_use]
+    pub fn reverse(&self) -> Self {
+        match self.operator() {
+            Operator::None
+            | Operator::Epsilon
+            | Operator::Set(_)  => self.clone(),
+            Operator::Cat(res)  => res.iter().rev().fold(RegEx::empty(), |acc, re| acc.then(&re.reverse())),
+            Operator::Star(re)  => re.reverse().star(),
+            Operator::Or(res)   => res.iter().fold(RegEx::none(), |acc, re| acc.or(&re.reverse())),
+            Operator::And(res)  => res[1..].iter().fold(res[0].reverse(), |acc, re| acc.and(&re.reverse())),
+            Operator::Not(re)   => re.reverse().not(),
+            Operator::Interleave(res) => res.iter().fold(RegEx::empty(), |acc, re| acc.interleave(&re.reverse())),
+        }
+    }
+
+    /// Returns an equivalent regex with the following rewrites applied,
+    /// bottom-up and in a single pass, on top of those done by the smart
+    /// constructors:
+    /// * `(ε|r)*` → `r*` and `(r*|s)*` → `(r|s)*`
+    /// * `ε|r` → `r` where `r` is nullable (so `(r*)?` → `r*`)
+    /// * `r|(r&s)` → `r` and `r&(r|s)` → `r` (absorption)
+    /// * `r|Σ*` → `Σ*` and `r&Σ*` → `r`
+    /// * `hr|hs` → `h(r|s)` (factoring of shared heads)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet};
+    /// let [a, b, c, d] = [b'a', b'b', b'c', b'd'].map(|x| RegEx::set(ByteSet::point(x)));
+    /// let ab = a.or(&b);
+    /// let re = ab.then(&c).or(&ab.then(&d));
+    /// assert_eq!(re.simplify(), ab.then(&c.or(&d)));
+    /// assert_eq!(a.star().opt().simplify(), a.star());
+    /// ```
+    #[must_use]
+    pub fn simplify(&self) -> Self {
+        match self.operator() {
+            Operator::None
+            | Operator::Epsilon
+            | Operator::Set(_)  => self.clone(),
+            Operator::Cat(res)  => res.iter().fold(RegEx::empty(), |acc, re| acc.then(&re.simplify())),
+            Operator::Star(re)  => simplify_star(&re.simplify()),
+            Operator::Or(res)   => simplify_or(&res.iter().map(RegEx::simplify).collect::<Vec<_>>()),
+            Operator::And(res)  => simplify_and(&res.iter().map(RegEx::simplify).collect::<Vec<_>>()),
+            Operator::Not(re)   => re.simplify().not(),
+            Operator::Interleave(res) => res.iter().fold(RegEx::empty(), |acc, re| acc.interleave(&re.simplify())),
+        }
+    }
+
+    #[must_use]
+    pub fn operator(&self) -> &Operator {
+        &self.root.operator
+    }
+
+    /// Returns true iff recognizes epsilon.
+    #[must_use]
+    pub fn is_nullable(&self) -> bool {
+        match self.operator() {
+            Operator::None     => false,
+            Operator::Epsilon  => true,
+            Operator::Set(_)   => false,
+            Operator::Cat(res) => res.iter().all(RegEx::is_nullable),
+            Operator::Star(_)  => true,
+            Operator::Or(res)  => res.iter().any(RegEx::is_nullable),
+            Operator::And(res) => res.iter().all(RegEx::is_nullable),
+            Operator::Not(re)  => !re.is_nullable(),
+            Operator::Interleave(res) => res.iter().all(RegEx::is_nullable),
+        }
+    }
+
+    /// Returns `true` iff `self` recognizes `text` in full. Same as
+    /// `is_fullmatch_bytes`.
+    #[must_use]
+    pub fn is_fullmatch(&self, text: &str) -> bool {
+        self.is_fullmatch_bytes(text.as_bytes())
+    }
+
+    /// Returns `true` iff `self` recognizes `input` in full. `input` need not
+    /// be UTF-8.
+    #[must_use]
+    pub fn is_fullmatch_bytes(&self, input: &[u8]) -> bool {
+        self.is_fullmatch_iter(input.iter().copied())
+    }
+
+    /// Returns, for each line of `text`, its (zero-based) line number and
+    /// whether `self` recognizes the line in full. Lines are split as by
+    /// `str::lines`: they end with `\n` or `\r\n`, which are not part of the
+    /// line, and the final line need not be terminated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet};
+    /// let digits = RegEx::set(ByteSet::range(b'0', b'9')).plus();
+    /// let results: Vec<_> = digits.fullmatch_lines("12\r\nab\n3").collect();
+    /// assert_eq!(results, [(0, true), (1, false), (2, true)]);
+    /// ```
+    pub fn fullmatch_lines<'a>(&'a self, text: &'a str) -> impl Iterator<Item = (usize, bool)> + 'a {
+        text.lines().enumerate().map(move |(i, line)| (i, self.is_fullmatch(line)))
+    }
+
+    /// Returns `true` iff `self` recognizes the string of bytes yielded by
+    /// `bytes`. Stops consuming `bytes` as soon as a match becomes impossible.
+    #[must_use]
+    pub fn is_fullmatch_iter<I: IntoIterator<Item = u8>>(&self, bytes: I) -> bool {
+        let mut regex = self.clone();
+        for byte in bytes {
+            regex = regex.deriv(byte);
+            if let Operator::None = regex.operator() {
+                return false;
+            }
+        }
+        regex.is_nullable()
+    }
+
+    /// Returns `true` iff `self` recognizes the contents of `reader`, which
+    /// is read in chunks rather than all at once. Stops reading as soon as a
+    /// match becomes impossible. Derivatives are memoized, so this is suited
+    /// to long inputs.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error encountered whilst reading, other than interrupts.
+    pub fn is_fullmatch_read<R: Read>(&self, mut reader: R) -> io::Result<bool> {
+        let mut cache = DerivCache::new();
+        let mut regex = self.clone();
+        let mut buf = [0; 8 * 1024];
+        loop {
+            let len = match reader.read(&mut buf) {
+                Ok(0) => return Ok(regex.is_nullable()),
+                Ok(len) => len,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            for &byte in &buf[..len] {
+                regex = cache.deriv(&regex, byte);
+                if let Operator::None = regex.operator() {
+                    return Ok(false);
+                }
+            }
+        }
+    }
+
+    /// Same as `is_fullmatch`, but derivatives are memoized in `cache`. Worth
+    /// using when matching long inputs, or many inputs against the same
+    /// expression, as each distinct derivative is then only computed once.
+    /// `text` may be any bytes, not just a `str`.
+    #[must_use]
+    pub fn is_fullmatch_cached<T: AsRef<[u8]>>(&self, text: T, cache: &mut DerivCache) -> bool {
+        let mut regex = self.clone();
+        for &byte in text.as_ref() {
+            regex = cache.deriv(&regex, byte);
+            if let Operator::None = regex.operator() {
+                return false;
+            }
+        }
+        regex.is_nullable()
+    }
+}
+
+/// Memo table for derivatives, keyed on (node, byte).
+///
+/// Derivatives recorded in the cache are hash-consed, so that structurally
+/// equal derivatives share a node and repeated lookups are cheap.
+#[derive(Default)]
+pub struct DerivCache {
+    // keys hold onto their node, so that its address cannot be reused
+    derivs: HashMap<(usize, u8), (RegEx, RegEx)>,
+    nodes: BTreeSet<RegEx>,
+}
+
+impl DerivCache {
+    /// Constructs an empty cache.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of memoized derivatives.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.derivs.len()
+    }
+
+    /// Returns `true` if no derivatives have been memoized.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.derivs.is_empty()
+    }
+
+    /// Forgets all memoized derivatives.
+    pub fn clear(&mut self) {
+        self.derivs.clear();
+        self.nodes.clear();
+    }
+
+    fn deriv(&mut self, regex: &RegEx, a: u8) -> RegEx {
+        let key = (Rc::as_ptr(&regex.root) as usize, a);
+        if let Some((_, da)) = self.derivs.get(&key) {
+            return da.clone();
+        }
+        let da = regex.deriv_with(a, &mut |re, a| self.deriv(re, a));
+        let da = self.intern(da);
+        self.derivs.insert(key, (regex.clone(), da.clone()));
+        da
+    }
+
+    fn intern(&mut self, regex: RegEx) -> RegEx {
+        if let Some(node) = self.nodes.get(&regex) {
+            node.clone()
+        } else {
+            self.nodes.insert(regex.clone());
+            regex
+        }
+    }
+}
+
+macro_rules! binop_impl {
+    ($trait:ident, $method:ident, $constructor:ident) => {
+        impl std::ops::$trait<&RegEx> for &RegEx {
+            type Output = RegEx;
+            fn $method(self, other: &RegEx) -> RegEx { self.$constructor(other) }
+        }
+
+        impl std::ops::$trait<RegEx> for &RegEx {
+            type Output = RegEx;
+            fn $method(self, other: RegEx) -> RegEx { self.$constructor(&other) }
+        }
+
+        impl std::ops::$trait<&RegEx> for RegEx {
+            type Output = RegEx;
+            fn $method(self, other: &RegEx) -> RegEx { self.$constructor(other) }
+        }
+
+        impl std::ops::$trait<RegEx> for RegEx {
+            type Output = RegEx;
+            fn $method(self, other: RegEx) -> RegEx { self.$constructor(&other) }
+        }
+    };
+}
+
+// `a + b` is `a.then(&b)`, `a | b` is `a.or(&b)`, and `a & b` is `a.and(&b)`.
+binop_impl!(Add, add, then);
+binop_impl!(BitOr, bitor, or);
+binop_impl!(BitAnd, bitand, and);
+
+impl std::ops::Not for &RegEx {
+    type Output = RegEx;
+    fn not(self) -> RegEx { RegEx::not(self) }
+}
+
+impl std::ops::Not for RegEx {
+    type Output = RegEx;
+    fn not(self) -> RegEx { RegEx::not(&self) }
+}
+
+// =================
+// === INTERNALS ===
+// =================
+
+impl RegEx {
+    fn new(operator: Operator) -> RegEx {
+        let mut hasher = DefaultHasher::new();
+        operator.hash(&mut hasher);
+        RegEx { root: Rc::new(Node { operator, hash: hasher.finish() }) }
+    }
+
+    fn map_sets_with(&self, f: &dyn Fn(&ByteSet) -> ByteSet) -> RegEx {
+        fn map_children(
+            children: &[RegEx],
+            f: &dyn Fn(&ByteSet) -> ByteSet,
+            combine: fn(&RegEx, &RegEx) -> RegEx,
+        ) -> Option<RegEx> {
+            let mapped: Vec<_> = children.iter().map(|child| child.map_sets_with(f)).collect();
+            if mapped.iter().zip(children).all(|(a, b)| Rc::ptr_eq(&a.root, &b.root)) {
+                None
+            } else {
+                Some(mapped[1..].iter().fold(mapped[0].clone(), |acc, child| combine(&acc, child)))
+            }
+        }
+
+        let mapped = match self.operator() {
+            Operator::None
+            | Operator::Epsilon => None,
+            Operator::Set(s)    => Some(f(s)).filter(|t| t != s).map(RegEx::set),
+            Operator::Cat(res)  => map_children(res, f, RegEx::then),
+            Operator::Star(re)  => Some(re.map_sets_with(f)).filter(|m| !Rc::ptr_eq(&m.root, &re.root)).map(|m| m.star()),
+            Operator::Or(res)   => map_children(res, f, RegEx::or),
+            Operator::And(res)  => map_children(res, f, RegEx::and),
+            Operator::Not(re)   => Some(re.map_sets_with(f)).filter(|m| !Rc::ptr_eq(&m.root, &re.root)).map(|m| m.not()),
+            Operator::Interleave(res) => map_children(res, f, RegEx::interleave),
+        };
+
+        mapped.unwrap_or_else(|| self.clone())
+    }
+
+    /// Derivative of `self` with respect to `a`, where the derivatives of
+    /// subexpressions are computed by `d`.
+    fn deriv_with(&self, a: u8, d: &mut dyn FnMut(&RegEx, u8) -> RegEx) -> RegEx {
+        fn deriv_cat(children: &[RegEx], a: u8, d: &mut dyn FnMut(&RegEx, u8) -> RegEx) -> RegEx {
+            fn aux(r: &RegEx, s: &RegEx, a: u8, d: &mut dyn FnMut(&RegEx, u8) -> RegEx) -> RegEx {
                 let nu_r_da_s = if r.is_nullable() {
-                    s.deriv(a)
+                    d(s, a)
                 } else {
                     RegEx::new(Operator::None)
                 };
-                (r.deriv(a).then(s)).or(&nu_r_da_s)
+                (d(r, a).then(s)).or(&nu_r_da_s)
             }
     
             match children {
@@ -291,98 +958,184 @@ impl RegEx {
                     unreachable!("Should be impossible for Cat node to have <2 children.")
                 },
                 [r, s] => {
-                    aux(r, s, a)
+                    aux(r, s, a, d)
                 },
                 [r, ..] => {
                     // Tail of children still form a valid Cat node.
                     let s = &RegEx::new(Operator::Cat(children[1..].to_vec()));
-                    aux(r, s, a)
+                    aux(r, s, a, d)
                 },
             }
         }
         
-        fn deriv_or(children: &[RegEx], a: u8) -> RegEx {
+        fn deriv_or(children: &[RegEx], a: u8, d: &mut dyn FnMut(&RegEx, u8) -> RegEx) -> RegEx {
             match children {
                 [] | [_] => {
                     unreachable!("Should be impossible for Or node to have <2 children.")
                 },
                 [r, s] => {
-                    r.deriv(a).or(&s.deriv(a))
+                    d(r, a).or(&d(s, a))
                 },
                 [r, ..] => {
                     let s = &RegEx::new(Operator::Or(children[1..].to_vec()));
-                    r.deriv(a).or(&s.deriv(a))
+                    d(r, a).or(&d(s, a))
                 },
             }
         }
         
-        fn deriv_and(children: &[RegEx], a: u8) -> RegEx {
+        fn deriv_and(children: &[RegEx], a: u8, d: &mut dyn FnMut(&RegEx, u8) -> RegEx) -> RegEx {
             match children {
                 [] | [_] => {
                     unreachable!("Should be impossible for And node to have <2 children.")
                 },
                 [r, s] => {
-                    r.deriv(a).and(&s.deriv(a))
+                    d(r, a).and(&d(s, a))
                 },
                 [r, ..] => {
                     let s = &RegEx::new(Operator::And(children[1..].to_vec()));
-                    r.deriv(a).and(&s.deriv(a))
+                    d(r, a).and(&d(s, a))
                 },
             }
         }
     
+        fn deriv_interleave(children: &[RegEx], a: u8, d: &mut dyn FnMut(&RegEx, u8) -> RegEx) -> RegEx {
+            // d(r1 ||| ... ||| rn) = (d(r1) ||| ... ||| rn) | ... | (r1 ||| ... ||| d(rn))
+            (0..children.len()).fold(RegEx::new(Operator::None), |acc, i| {
+                let di = d(&children[i], a);
+                let term = children.iter().enumerate().fold(RegEx::new(Operator::Epsilon), |term, (j, child)| {
+                    term.interleave(if i == j { &di } else { child })
+                });
+                acc.or(&term)
+            })
+        }
+
         match self.operator() {
             Operator::None
             | Operator::Epsilon => RegEx::new(Operator::None),
             Operator::Set(s)    => if s.contains(a) { RegEx::new(Operator::Epsilon) } else { RegEx::new(Operator::None) },
-            Operator::Cat(res)  => deriv_cat(res, a),
-            Operator::Star(re)  => re.deriv(a).then(self),
-            Operator::Or(res)   => deriv_or(res, a),
-            Operator::And(res)  => deriv_and(res, a),
-            Operator::Not(re)   => re.deriv(a).not(),
+            Operator::Cat(res)  => deriv_cat(res, a, d),
+            Operator::Star(re)  => d(re, a).then(self),
+            Operator::Or(res)   => deriv_or(res, a, d),
+            Operator::And(res)  => deriv_and(res, a, d),
+            Operator::Not(re)   => d(re, a).not(),
+            Operator::Interleave(res) => deriv_interleave(res, a, d),
         }
     }
+}
 
-    #[must_use]
-    pub fn operator(&self) -> &Operator {
-        &*self.root
+fn children(re: &RegEx, split: fn(&Operator) -> Option<&Vec<RegEx>>) -> Vec<RegEx> {
+    split(re.operator()).cloned().unwrap_or_else(|| vec![re.clone()])
+}
+
+fn alternatives(re: &RegEx) -> Vec<RegEx> {
+    children(re, |op| if let Operator::Or(res) = op { Some(res) } else { None })
+}
+
+fn conjuncts(re: &RegEx) -> Vec<RegEx> {
+    children(re, |op| if let Operator::And(res) = op { Some(res) } else { None })
+}
+
+fn simplify_star(re: &RegEx) -> RegEx {
+    alternatives(re).iter().fold(RegEx::none(), |acc, alt| {
+        match alt.operator() {
+            Operator::Epsilon  => acc,
+            Operator::Star(re) => acc.or(re),
+            _                  => acc.or(alt),
+        }
+    }).star()
+}
+
+/// Simplifies the alternation of `res`, whose elements are already
+/// simplified.
+fn simplify_or(res: &[RegEx]) -> RegEx {
+    let universe = RegEx::none().not();
+    let mut res = alternatives(&res.iter().fold(RegEx::none(), |acc, re| acc.or(re)));
+
+    if res.contains(&universe) {
+        return universe
+    }
+    if res.iter().any(|re| re.is_nullable() && *re.operator() != Operator::Epsilon) {
+        res.retain(|re| *re.operator() != Operator::Epsilon);
     }
+    let others = res.clone();
+    res.retain(|re| !conjuncts(re).iter().any(|conjunct| conjunct != re && others.contains(conjunct)));
 
-    /// Returns true iff recognizes epsilon.
-    #[must_use]
-    pub fn is_nullable(&self) -> bool {
-        match self.operator() {
-            Operator::None     => false,
-            Operator::Epsilon  => true,
-            Operator::Set(_)   => false,
-            Operator::Cat(res) => res.iter().all(RegEx::is_nullable),
-            Operator::Star(_)  => true,
-            Operator::Or(res)  => res.iter().any(RegEx::is_nullable),
-            Operator::And(res) => res.iter().all(RegEx::is_nullable),
-            Operator::Not(re)  => !re.is_nullable(),
+    // group alternatives by their head, preserving the order of first occurrence
+    let mut groups: Vec<(RegEx, Vec<RegEx>)> = Vec::new();
+    for re in res {
+        let (head, tail) = match re.operator() {
+            Operator::Cat(res) => (res[0].clone(), res[1..].iter().fold(RegEx::empty(), |acc, re| acc.then(re))),
+            _                  => (re.clone(), RegEx::empty()),
+        };
+        match groups.iter_mut().find(|(h, _)| *h == head) {
+            Some((_, tails)) => tails.push(tail),
+            None             => groups.push((head, vec![tail])),
         }
     }
 
-    #[must_use]
-    pub fn is_fullmatch(&self, text: &str) -> bool {
-        let mut regex = self.clone();
-        for byte in text.bytes() {
-            regex = regex.deriv(byte);
-            if let Operator::None = regex.operator() {
-                return false;
-            }
+    groups.into_iter().fold(RegEx::none(), |acc, (head, tails)| {
+        if tails.len() == 1 {
+            acc.or(&head.then(&tails[0]))
+        } else {
+            acc.or(&head.then(&simplify_or(&tails)))
         }
-        regex.is_nullable()
+    })
+}
+
+/// Simplifies the intersection of `res`, whose elements are already
+/// simplified.
+fn simplify_and(res: &[RegEx]) -> RegEx {
+    let universe = RegEx::none().not();
+    let mut res = conjuncts(&res[1..].iter().fold(res[0].clone(), |acc, re| acc.and(re)));
+
+    res.retain(|re| *re != universe);
+    let others = res.clone();
+    res.retain(|re| !alternatives(re).iter().any(|alt| alt != re && others.contains(alt)));
+
+    match res.split_first() {
+        Some((first, rest)) => rest.iter().fold(first.clone(), |acc, re| acc.and(re)),
+        None                => universe,
     }
 }
 
-// =================
-// === INTERNALS ===
-// =================
+/// Merges the complemented sets amongst the (sorted) children `res` of a
+/// union, or of an intersection if `intersect`, into any set amongst them.
+/// Note that the complement of a set `y` contains every string other than the
+/// bytes in `y`, so
+/// * `~y1 | ~y2 = ~(y1 & y2)` and `x | ~y = ~(y - x)`
+/// * `~y1 & ~y2 = ~(y1 | y2)` and `x & ~y = x - y`
+fn merged_complemented_sets(res: Vec<RegEx>, intersect: bool) -> Vec<RegEx> {
+    let complemented = |re: &RegEx| match re.operator() {
+        Operator::Not(re) => match re.operator() {
+            Operator::Set(y) => Some(y.clone()),
+            _                => None,
+        },
+        _ => None,
+    };
 
-impl RegEx {
-    fn new(node: Operator) -> RegEx {
-        RegEx { root: Rc::new(node) }
+    let (nots, mut new_res): (Vec<_>, Vec<_>) = res.into_iter().partition(|re| complemented(re).is_some());
+    let Some(excluded) = nots.iter().filter_map(complemented).reduce(|acc, y| {
+        if intersect { acc.union(&y) } else { acc.intersection(&y) }
+    }) else {
+        return new_res
+    };
+
+    let set = new_res.iter().position(|re| matches!(re.operator(), Operator::Set(_)));
+    let merged = match set.map(|i| new_res.remove(i)).as_ref().map(RegEx::operator) {
+        Some(Operator::Set(x)) if intersect => RegEx::set(x.difference(&excluded)),
+        Some(Operator::Set(x))              => RegEx::set(excluded.difference(x)).not(),
+        _                                   => RegEx::set(excluded).not(),
+    };
+
+    // merged may have collapsed to the empty set, or to everything
+    match merged.operator() {
+        Operator::None => if intersect { vec![merged] } else { new_res },
+        Operator::Star(_) => if intersect { new_res } else { vec![merged] },
+        _ => {
+            new_res.push(merged);
+            new_res.sort();
+            new_res
+        }
     }
 }
 
@@ -411,39 +1164,158 @@ where
     }
 }
 
+// Comparisons short-circuit on shared nodes, and equality additionally on
+// differing hashes, before falling back to structural comparison.
+
+impl PartialEq for RegEx {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.root, &other.root)
+            || (self.root.hash == other.root.hash && self.root.operator == other.root.operator)
+    }
+}
+
+impl Eq for RegEx {}
+
+impl PartialOrd for RegEx {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RegEx {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if Rc::ptr_eq(&self.root, &other.root) {
+            Ordering::Equal
+        } else {
+            self.root.operator.cmp(&other.root.operator)
+        }
+    }
+}
+
+impl Hash for RegEx {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.root.hash);
+    }
+}
+
+/// Bytes that are escaped when written outside of a set.
+const SPECIAL: &[u8] = b".+*?()|&~[]{}^-\\";
+
+/// Returns the loosest binding context in which `node` can be written
+/// without parentheses: 0 = `|`, 1 = `&`, 2 = interleaving, 3 = juxtaposition,
+/// 4 = prefix/postfix operators, 5 = atoms.
+fn precedence(node: &Operator) -> u8 {
+    match node {
+        Operator::Or(children) if children[0] == RegEx::empty() => 4, // written as an optional
+        Operator::Or(_)         => 0,
+        Operator::And(_)        => 1,
+        Operator::Interleave(_) => 2,
+        Operator::Cat(_)        => 3,
+        Operator::Star(_)
+        | Operator::Not(_)      => 4,
+        Operator::None
+        | Operator::Epsilon
+        | Operator::Set(_)      => 5,
+    }
+}
+
+/// Writes `node` in infix notation, parenthesized if it binds looser than
+/// `context` (see `precedence`).
+fn write_infix(node: &Operator, f: &mut Formatter, context: u8) -> Result<(), Error> {
+    fn write_all(children: &[RegEx], f: &mut Formatter, sep: &str, context: u8) -> Result<(), Error> {
+        for (i, child) in children.iter().enumerate() {
+            if i > 0 {
+                f.write_str(sep)?;
+            }
+            write_infix(child.operator(), f, context)?;
+        }
+        Ok(())
+    }
+
+    let parenthesize = precedence(node) < context;
+    if parenthesize {
+        f.write_str("(")?;
+    }
+
+    match node {
+        Operator::None => f.write_str("\u{2205}")?,
+        Operator::Epsilon => f.write_str("\u{03B5}")?,
+        Operator::Set(set) => match set.is_single() {
+            Some(byte) => write_escaped(f, byte, SPECIAL)?,
+            None       => write!(f, "{set}")?,
+        },
+        Operator::Cat(children) => write_all(children, f, "", 4)?,
+        Operator::Star(child) => {
+            write_infix(child.operator(), f, 5)?;
+            f.write_str("*")?;
+        },
+        Operator::Or(children) if children[0] == RegEx::empty() => {
+            // epsilon is the least regex, so is always the first child
+            if let [child] = &children[1..] {
+                write_infix(child.operator(), f, 5)?;
+            } else {
+                f.write_str("(")?;
+                write_all(&children[1..], f, "|", 1)?;
+                f.write_str(")")?;
+            }
+            f.write_str("?")?;
+        },
+        Operator::Or(children) => write_all(children, f, "|", 1)?,
+        Operator::And(children) => write_all(children, f, "&", 2)?,
+        Operator::Not(child) => {
+            f.write_str("~")?;
+            write_infix(child.operator(), f, 5)?;
+        },
+        Operator::Interleave(children) => write_all(children, f, "\u{29E2}", 3)?,
+    }
+
+    if parenthesize {
+        f.write_str(")")?;
+    }
+    Ok(())
+}
+
+/// Writes `node` as an S-expression, with each child on its own line,
+/// indented by `depth`.
+fn write_sexpr(node: &Operator, f: &mut Formatter, depth: usize) -> Result<(), Error> {
+    let (name, children) = match node {
+        Operator::None
+        | Operator::Epsilon
+        | Operator::Set(_)              => return write_infix(node, f, 0),
+        Operator::Cat(children)         => ("cat", &children[..]),
+        Operator::Star(child)           => ("star", std::slice::from_ref(child)),
+        Operator::Or(children)          => ("or", &children[..]),
+        Operator::And(children)         => ("and", &children[..]),
+        Operator::Not(child)            => ("not", std::slice::from_ref(child)),
+        Operator::Interleave(children)  => ("interleave", &children[..]),
+    };
+
+    write!(f, "({name}")?;
+    for child in children {
+        write!(f, "\n{:indent$}", "", indent = 2 * (depth + 1))?;
+        write_sexpr(child.operator(), f, depth + 1)?;
+    }
+    f.write_str(")")
+}
+
 impl Debug for Operator {
     fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
-        match self {
-            Operator::None => {
-                f.write_str("\u{2205}")
-            },
-            Operator::Epsilon => {
-                f.write_str("\u{03B5}")
-            },
-            Operator::Set(set) => {
-                f.write_str(&format!("{:?}", set))
-            },
-            Operator::Cat(children) => {
-                f.write_str(&format!("({})", children.iter().map(|child| format!("{:?}", child)).collect::<String>()))
-            },
-            Operator::Star(child) => {
-                f.write_str(&format!("({:?})*", child))
-            },
-            Operator::Or(children) => {
-                f.write_str(&format!("({})", children.iter().map(|child| format!("{:?}", child)).collect::<Vec<_>>().join("|")))
-            },
-            Operator::And(children) => {
-                f.write_str(&format!("({})", children.iter().map(|child| format!("{:?}", child)).collect::<Vec<_>>().join("&")))
-            },
-            Operator::Not(child) => {
-                f.write_str(&format!("!({:?})", child))
-            },
+        if f.alternate() {
+            write_sexpr(self, f, 0)
+        } else {
+            write_infix(self, f, 0)
         }
     }
 }
 
 impl Debug for RegEx {
     fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
-        f.write_str(&format!("{:?}", self.operator()))
+        Debug::fmt(self.operator(), f)
+    }
+}
+
+impl Display for RegEx {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write_infix(self.operator(), f, 0)
     }
 }
\ No newline at end of file
@@ -1,5 +1,5 @@
 use std::ops::Range;
-use super::LexTable;
+use super::{Command, LexTable};
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Token {
@@ -7,10 +7,32 @@ pub struct Token {
     pub span: Range<usize>,
 }
 
-pub struct Scan<'a, S> {
+/// Iterator over the tokens of an input, by maximal munch, driven by any
+/// `LexTable`. `ClassLexTable` is the recommended table, as the smallest
+/// of the dense ones; `NaiveLexTable` may step faster for lexers small
+/// enough that its rows of 256 entries all stay in cache.
+///
+/// If there is an EOF class, as given by `LexTable::eof_class` or
+/// `with_eof_class`, the last token is an empty one of that class at the end
+/// of the input, as parsers expect. By default, there is none after an error.
+///
+/// # Examples
+///
+/// ```
+/// # use regex_deriv::{RegEx, ByteSet, NaiveLexTable, Command, Scan, Token};
+/// let word = RegEx::set(ByteSet::ASCII_ALPHA).plus();
+/// let space = RegEx::set(ByteSet::point(b' ')).plus();
+/// let table = NaiveLexTable::from_rules_with_commands(&[(word, Command::Emit), (space, Command::Skip)]);
+/// let tokens: Vec<_> = Scan::new(&table, "a b").with_eof_class(2).map(Result::unwrap).collect();
+/// assert_eq!(tokens.last(), Some(&Token { class: 2, span: 3..3 }));
+/// ```
+pub struct Scan<'a, S: ?Sized> {
     table: &'a S,
     input: &'a [u8],
     index: usize,
+    // taken once yielded
+    eof: Option<usize>,
+    eof_after_error: bool,
 }
 
 #[derive(Debug)]
@@ -18,62 +40,69 @@ pub struct ScanError {
     pos: usize,
 }
 
-impl<'a, S: LexTable> Scan<'a, S> {
+impl<'a, S: LexTable + ?Sized> Scan<'a, S> {
     #[must_use]
     pub fn new<I: AsRef<[u8]> + ?Sized>(table: &'a S, input: &'a I) -> Self {
         Self {
             table,
             input: input.as_ref(),
             index: 0,
+            eof: table.eof_class(),
+            eof_after_error: false,
         }
     }
+
+    /// Returns `self` ending with a token of class `class` at the end of the
+    /// input, in place of any given by `LexTable::eof_class`.
+    #[must_use]
+    pub fn with_eof_class(self, class: usize) -> Self {
+        Self { eof: Some(class), ..self }
+    }
+
+    /// Returns `self` ending with its EOF token, if any, even after an
+    /// error, which otherwise ends the scan.
+    #[must_use]
+    pub fn with_eof_after_error(self) -> Self {
+        Self { eof_after_error: true, ..self }
+    }
 }
 
-impl<'a, S: LexTable> Iterator for Scan<'a, S> {
-    type Item = Result<Token, ScanError>;
+impl<S: LexTable + ?Sized> Scan<'_, S> {
+    /// Returns the end of the token of class `class` starting at the current
+    /// index, where the lexeme matched ends at `end`.
+    fn trim(&self, class: usize, end: usize) -> usize {
+        self.table.trailing_context(class)
+            .and_then(|context| context.split(&self.input[self.index..end]))
+            .map_or(end, |len| self.index + len)
+    }
+}
 
-    #[allow(clippy::option_if_let_else)]
-    fn next(&mut self) -> Option<Self::Item> {       
-        if self.index < self.input.as_ref().len() {
-            let mut state = S::START_STATE;
-            let mut index = self.index;
-            
-            let mut last_accept_state = self.table.sink();
-            let mut last_accept_index = 0_usize;
+impl<S: LexTable + ?Sized> Iterator for Scan<'_, S> {
+    type Item = Result<Token, ScanError>;
 
-            // simulate dfa until hit the sink state or end of input
-            for byte in self.input[self.index..].iter().copied() {            
-                if state == self.table.sink() {
-                    break;
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.index < self.input.len() {
+            let i = self.index;
+            // an empty lexeme would make no progress, so is as good as none
+            let token = self.table.longest_match_at(self.input, i)
+                .map(|(len, class)| (class, self.trim(class, i + len)))
+                .filter(|&(_, end)| end > i);
+            if let Some((class, end)) = token {
+                self.index = end;
+                match self.table.command(class) {
+                    Command::Emit => return Some(Ok(Token { span: i..end, class })),
+                    Command::Skip => {},
                 }
-                
-                if self.table.class(state).is_some() {
-                    last_accept_state = state;
-                    last_accept_index = index;
-                }
-                
-                state = self.table.step(state, byte);
-                index += 1;
-            }
-
-            // currently on an accept state
-            if let Some(class) = self.table.class(state) {
-                let i = self.index;
-                self.index = index;
-                Some(Ok(Token { span: i..self.index, class }))
-            // landed on an accept state in the past
-            } else if let Some(class) = self.table.class(last_accept_state) {
-                let i = self.index;
-                self.index = last_accept_index;
-                Some(Ok(Token { span: i..self.index, class }))
             // failed to match anything
             } else {
-                let i = self.index;
                 self.index = usize::MAX; // forces next iteration to return None
-                Some(Err(ScanError { pos: i }))
+                if !self.eof_after_error {
+                    self.eof = None;
+                }
+                return Some(Err(ScanError { pos: i }))
             }
-        } else {
-            None
         }
+        let len = self.input.len();
+        self.eof.take().map(|class| Ok(Token { class, span: len..len }))
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,128 @@
+//! Parsing of regular expression patterns into `RegEx`s. Only available with
+//! the `syntax` feature.
+
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use lalrpop_util::lalrpop_mod;
+use super::{RegEx, ByteSet};
+
+lalrpop_mod!(#[allow(clippy::all, clippy::pedantic, unused)] parser, "/syntax/parser.rs");
+mod utils;
+
+/// Parses `pattern` into a `RegEx`.
+///
+/// # Errors
+///
+/// Returns a `ParseError` locating the first offending part of `pattern`.
+pub fn parse(pattern: &str) -> Result<RegEx, ParseError> {
+    parser::ExprParser::new().parse(pattern).map_err(ParseError::from)
+}
+
+/// Constructs a `RegEx` that recognizes some input string only.
+#[must_use]
+pub fn literal(s: &str) -> RegEx {
+    literal_bytes(s.as_bytes())
+}
+
+/// Constructs a `RegEx` that recognizes some input byte string only.
+#[must_use]
+pub fn literal_bytes(s: &[u8]) -> RegEx {
+    s.iter().fold(RegEx::empty(), |r, &byte| {
+        r.then(&RegEx::set(ByteSet::point(byte)))
+    })
+}
+
+/// Constructs a `RegEx` that recognizes any one of the chars in the input string.
+/// ASCII chars are collected into a single set.
+#[must_use]
+pub fn any(s: &str) -> RegEx {
+    let ascii: Vec<u8> = s.bytes().filter(u8::is_ascii).collect();
+    s.chars().filter(|c| !c.is_ascii()).fold(any_bytes(&ascii), |r, c| {
+        r.or(&literal(c.encode_utf8(&mut [0; 4])))
+    })
+}
+
+/// Constructs a `RegEx` that recognizes any one of the bytes in the input byte string.
+#[must_use]
+pub fn any_bytes(s: &[u8]) -> RegEx {
+    RegEx::set(s.iter().copied().collect())
+}
+
+/// Error produced when a pattern fails to parse.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseError {
+    pos: usize,
+    kind: ParseErrorKind,
+}
+
+/// The ways in which a pattern can fail to parse.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ParseErrorKind {
+    /// A character that cannot begin any token.
+    InvalidToken,
+    /// The pattern ended prematurely.
+    UnexpectedEnd,
+    /// A token that is not valid at its position.
+    UnexpectedToken(String),
+    /// A character class with no items, i.e. `[]` or `[^]`.
+    EmptyClass,
+}
+
+impl ParseError {
+    /// Returns the byte offset into the pattern at which the error occurred.
+    #[must_use]
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns what went wrong.
+    #[must_use]
+    pub fn kind(&self) -> &ParseErrorKind {
+        &self.kind
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ParseErrorKind::InvalidToken           => write!(f, "invalid token at {}", self.pos),
+            ParseErrorKind::UnexpectedEnd          => write!(f, "unexpected end of pattern at {}", self.pos),
+            ParseErrorKind::UnexpectedToken(token) => write!(f, "unexpected token {:?} at {}", token, self.pos),
+            ParseErrorKind::EmptyClass             => write!(f, "empty character class at {}", self.pos),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl FromStr for RegEx {
+    type Err = ParseError;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        parse(pattern)
+    }
+}
+
+// =================
+// === INTERNALS ===
+// =================
+
+type LalrpopError<'a> = lalrpop_util::ParseError<usize, lalrpop_util::lexer::Token<'a>, ParseError>;
+
+impl From<LalrpopError<'_>> for ParseError {
+    fn from(error: LalrpopError) -> Self {
+        match error {
+            LalrpopError::InvalidToken { location } => {
+                Self { pos: location, kind: ParseErrorKind::InvalidToken }
+            },
+            LalrpopError::UnrecognizedEOF { location, .. } => {
+                Self { pos: location, kind: ParseErrorKind::UnexpectedEnd }
+            },
+            LalrpopError::UnrecognizedToken { token: (pos, token, _), .. }
+            | LalrpopError::ExtraToken { token: (pos, token, _) } => {
+                Self { pos, kind: ParseErrorKind::UnexpectedToken(token.1.to_string()) }
+            },
+            LalrpopError::User { error } => error,
+        }
+    }
+}
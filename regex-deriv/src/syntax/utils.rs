@@ -0,0 +1,46 @@
+use crate::{RegEx, ByteSet};
+use super::literal;
+
+// Constructs a `RegEx` that recognizes all chars within a provided range (inclusive).
+// Also accounts for char ranges that span different number of bytes. Inputs must be
+// valid single unicode chars (as string slices).
+pub fn range(a: &str, b: &str) -> RegEx {
+    let mut a_chars = a.chars();
+    let mut b_chars = b.chars();
+
+    let from = a_chars.next().unwrap() as u32;
+    let to = b_chars.next().unwrap() as u32;
+    
+    assert!(a_chars.next().is_none());
+    assert!(b_chars.next().is_none());
+    
+    RegEx::range32(from, to)
+}
+// An item of a character class: a single (unicode) char, a range of chars, or
+// a nested class.
+pub enum ClassItem<'a> {
+    Char(&'a str),
+    Range(&'a str, &'a str),
+    Nested(RegEx),
+}
+
+// Constructs a `RegEx` that recognizes any one of the items in a character class.
+// Single-byte chars and ranges are collected into a single set.
+pub fn class<'a, I: IntoIterator<Item = ClassItem<'a>>>(items: I) -> RegEx {
+    let mut set = ByteSet::empty();
+    let mut rest = RegEx::none();
+    for item in items {
+        match item {
+            ClassItem::Char(a) if a.len() == 1 => {
+                set.insert(a.as_bytes()[0]);
+            },
+            ClassItem::Range(a, b) if a.len() == 1 && b.len() == 1 => {
+                set.insert_range(a.as_bytes()[0], b.as_bytes()[0]);
+            },
+            ClassItem::Char(a)     => rest = rest.or(&literal(a)),
+            ClassItem::Range(a, b) => rest = rest.or(&range(a, b)),
+            ClassItem::Nested(r)   => rest = rest.or(&r),
+        }
+    }
+    RegEx::set(set).or(&rest)
+}
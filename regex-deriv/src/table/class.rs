@@ -0,0 +1,169 @@
+use std::convert::TryFrom;
+use std::io::{self, Write};
+use super::{class_data_size, heap_size, Command, static_table, LexTable, NaiveLexTable, TrailingContext};
+use crate::DFA;
+
+/// Transition table of a DFA over its byte equivalence classes (see
+/// `DFA::byte_classes`), with states numbered as in `NaiveLexTable`. A step
+/// maps the byte to its class, then looks up a row of one entry per class.
+/// Lexers typically have a few dozen classes, so the table is a fraction of
+/// the size of a `NaiveLexTable`, and more of it stays in cache: it is the
+/// recommended table for `Scan`.
+///
+/// # Examples
+///
+/// ```
+/// # use regex_deriv::{RegEx, ByteSet, DFA, ClassLexTable, Scan};
+/// let word = RegEx::set(ByteSet::range(b'a', b'z')).plus();
+/// let space = RegEx::set(ByteSet::point(b' ')).plus();
+/// let table = ClassLexTable::new(&DFA::from(&[word, space]).minimize());
+/// assert_eq!(table.byte_class_count(), 3);
+/// let tokens: Vec<_> = Scan::new(&table, "hi there").map(|token| token.unwrap().span).collect();
+/// assert_eq!(tokens, [0..2, 2..3, 3..8]);
+/// ```
+pub struct ClassLexTable {
+    remap:    [u8; 256],
+    stride:   usize,
+    // by `stride * state + remap[symbol]`
+    next:     Vec<u32>,
+    classes:  Vec<Option<usize>>,
+    class_count: usize,
+    names:    Vec<String>,
+    contexts: Vec<Option<TrailingContext>>,
+    commands: Vec<Command>,
+}
+
+impl ClassLexTable {
+    /// Constructs the table of `dfa`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `dfa` has `u32::MAX` or more states.
+    #[must_use]
+    pub fn new(dfa: &DFA) -> Self {
+        Self::from_naive(dfa, NaiveLexTable::new(dfa))
+    }
+
+    /// Same as `new`, except that the class `i` has trailing context
+    /// `contexts[i]`, as in `NaiveLexTable::with_trailing_contexts`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `dfa` has `u32::MAX` or more states.
+    #[must_use]
+    pub fn with_trailing_contexts(dfa: &DFA, contexts: Vec<Option<TrailingContext>>) -> Self {
+        Self::from_naive(dfa, NaiveLexTable::with_trailing_contexts(dfa, contexts))
+    }
+
+    /// Same as `new`, except that tokens of class `i` are handled as given
+    /// by `commands[i]`, as in `NaiveLexTable::with_commands`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `dfa` has `u32::MAX` or more states.
+    #[must_use]
+    pub fn with_commands(dfa: &DFA, commands: &[Command]) -> Self {
+        Self::from_naive(dfa, NaiveLexTable::with_commands(dfa, commands))
+    }
+
+    /// Reads the columns of `table`, the naive table of `dfa`, off by byte
+    /// class.
+    fn from_naive(dfa: &DFA, table: NaiveLexTable) -> Self {
+        let (remap, stride) = dfa.byte_classes();
+        let sink = table.sink();
+
+        // a representative byte of each class, to read its column off
+        let mut bytes = vec![0; stride];
+        for symbol in (0..=255_u8).rev() {
+            bytes[usize::from(remap[usize::from(symbol)])] = symbol;
+        }
+
+        // unlike in the naive table, the sink has a row, leading back to it
+        let mut next = Vec::with_capacity(stride * (sink + 1));
+        for state in 0..=sink {
+            for &symbol in &bytes {
+                let dest = if state == sink { sink } else { table.step(state, symbol) };
+                next.push(u32::try_from(dest).expect("DFA too large"));
+            }
+        }
+
+        Self {
+            remap,
+            stride,
+            next,
+            classes: table.classes,
+            class_count: table.class_count,
+            names: table.names,
+            contexts: table.contexts,
+            commands: table.commands,
+        }
+    }
+
+    /// Returns `self` with the class `i` named `names[i]`, as in
+    /// `NaiveLexTable::with_class_names`.
+    #[must_use]
+    pub fn with_class_names(self, names: &[&str]) -> Self {
+        Self { names: names.iter().map(|&name| name.to_string()).collect(), ..self }
+    }
+
+    /// Writes Rust source defining `pub static <ident>: StaticLexTable`, the
+    /// same table, to `out`, as `NaiveLexTable::write_rust` does.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error encountered whilst writing.
+    pub fn write_rust(&self, out: &mut impl Write, ident: &str) -> io::Result<()> {
+        static_table::write_rust(out, ident, &static_table::Source {
+            remap: &self.remap,
+            stride: self.stride,
+            next: &self.next,
+            classes: &self.classes,
+            class_count: self.class_count,
+            class_names: &self.names,
+        })
+    }
+
+    /// Returns the number of bytes `self` holds on the heap, as in
+    /// `NaiveLexTable::memory_usage`.
+    #[must_use]
+    pub fn memory_usage(&self) -> usize {
+        heap_size(&self.next) + heap_size(&self.classes) + class_data_size(&self.names, &self.contexts, &self.commands)
+    }
+
+    /// Returns the number of byte classes, which is the length of each row.
+    #[must_use]
+    pub fn byte_class_count(&self) -> usize {
+        self.stride
+    }
+}
+
+impl LexTable for ClassLexTable {
+    fn step(&self, state: usize, symbol: u8) -> usize {
+        let class = usize::from(self.remap[usize::from(symbol)]);
+        usize::try_from(self.next[self.stride * state + class]).unwrap()
+    }
+
+    fn class(&self, state: usize) -> Option<usize> {
+        self.classes[state]
+    }
+
+    fn sink(&self) -> usize {
+        self.classes.len() - 1
+    }
+
+    fn class_count(&self) -> usize {
+        self.class_count
+    }
+
+    fn class_name(&self, class: usize) -> Option<&str> {
+        self.names.get(class).map(String::as_str)
+    }
+
+    fn trailing_context(&self, class: usize) -> Option<&TrailingContext> {
+        self.contexts.get(class).and_then(Option::as_ref)
+    }
+
+    fn command(&self, class: usize) -> Command {
+        self.commands.get(class).copied().unwrap_or_default()
+    }
+}
@@ -0,0 +1,180 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use super::{class_data_size, heap_size, Command, LexTable, NaiveLexTable, TableIndex, TrailingContext};
+use crate::DFA;
+
+/// Transition table of a DFA compressed by row displacement, with states
+/// numbered as in `NaiveLexTable`. Each state has a default destination, its
+/// most common one, and only its other transitions are stored: those of all
+/// states are overlaid in one array, each state's at an offset chosen so as
+/// not to collide, and tagged with their state so that a lookup can tell its
+/// own entries from those of others.
+///
+/// Destinations and tags are of type `I`, as the entries of the
+/// `NaiveLexTable<I>` the table is built from.
+///
+/// # Examples
+///
+/// ```
+/// # use regex_deriv::{RegEx, ByteSet, DFA, CompressedLexTable, Scan};
+/// let word = RegEx::set(ByteSet::range(b'a', b'z')).plus();
+/// let space = RegEx::set(ByteSet::point(b' ')).plus();
+/// let dfa = DFA::from(&[word, space]).minimize();
+/// let table = CompressedLexTable::new(&dfa);
+/// let tokens: Vec<_> = Scan::new(&table, "hi there").map(|token| token.unwrap().span).collect();
+/// assert_eq!(tokens, [0..2, 2..3, 3..8]);
+/// ```
+pub struct CompressedLexTable<I: TableIndex = u32> {
+    // by state
+    default: Vec<I>,
+    base:    Vec<usize>,
+    // by `base[state] + symbol`; entries owned by no state are tagged with
+    // the sink, which has none of its own, and lead back to it
+    next:    Vec<I>,
+    check:   Vec<I>,
+    classes:  Vec<Option<usize>>,
+    class_count: usize,
+    names:    Vec<String>,
+    contexts: Vec<Option<TrailingContext>>,
+    commands: Vec<Command>,
+}
+
+impl CompressedLexTable {
+    /// Constructs the compressed table of `dfa`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `dfa` has more than `u32::MAX` states.
+    #[must_use]
+    pub fn new(dfa: &DFA) -> Self {
+        Self::from(NaiveLexTable::new(dfa))
+    }
+}
+
+impl<I: TableIndex> CompressedLexTable<I> {
+    /// Returns the number of entries of the overlaid array, against the
+    /// `256` per state of a `NaiveLexTable`.
+    #[must_use]
+    pub fn entries(&self) -> usize {
+        self.next.len()
+    }
+
+    /// Returns the number of bytes `self` holds on the heap, as in
+    /// `NaiveLexTable::memory_usage`.
+    #[must_use]
+    pub fn memory_usage(&self) -> usize {
+        let rows = heap_size(&self.default) + heap_size(&self.base) + heap_size(&self.next) + heap_size(&self.check);
+        rows + heap_size(&self.classes) + class_data_size(&self.names, &self.contexts, &self.commands)
+    }
+}
+
+impl<I: TableIndex> From<NaiveLexTable<I>> for CompressedLexTable<I> {
+    fn from(table: NaiveLexTable<I>) -> Self {
+        let sink = table.sink();
+        let rows = table.classes.len();
+        let row = |state: usize| -> Vec<usize> { table.next[256 * state..256 * (state + 1)].iter().map(|&dest| dest.to_usize()).collect() };
+
+        // the sink's row is implicit in the naive table, and only leads back
+        let default: Vec<usize> = (0..rows)
+            .map(|state| if state == sink { sink } else { most_common(&row(state)) })
+            .collect();
+        let entries: Vec<Vec<(usize, usize)>> = (0..rows)
+            .map(|state| if state == sink { Vec::new() } else {
+                row(state).into_iter().enumerate().filter(|&(_, dest)| dest != default[state]).collect()
+            })
+            .collect();
+
+        // rows are placed greedily at the first offset where they fit,
+        // fullest first, as sparser rows are easier to fit in the gaps
+        let mut order: Vec<usize> = (0..rows).collect();
+        order.sort_by_key(|&state| Reverse(entries[state].len()));
+
+        let mut base = vec![0; rows];
+        let mut next = Vec::new();
+        let mut check: Vec<I> = Vec::new();
+        let free = I::from_usize(sink);
+        for state in order {
+            if entries[state].is_empty() {
+                continue;
+            }
+            // past the last entry, any offset fits
+            let offset = (0..=check.len())
+                .find(|&offset| entries[state].iter().all(|&(symbol, _)| check.get(offset + symbol).is_none_or(|&owner| owner == free)))
+                .unwrap();
+            let end = offset + entries[state].last().unwrap().0 + 1;
+            if check.len() < end {
+                check.resize(end, free);
+                next.resize(end, free);
+            }
+            for &(symbol, dest) in &entries[state] {
+                check[offset + symbol] = I::from_usize(state);
+                next[offset + symbol] = I::from_usize(dest);
+            }
+            base[state] = offset;
+        }
+
+        // so that lookups past the last entry need no bounds check
+        check.resize(base.iter().max().map_or(0, |&max| max + 256).max(check.len()), free);
+        next.resize(check.len(), free);
+
+        Self {
+            default: default.into_iter().map(I::from_usize).collect(),
+            base,
+            next,
+            check,
+            classes: table.classes,
+            class_count: table.class_count,
+            names: table.names,
+            contexts: table.contexts,
+            commands: table.commands,
+        }
+    }
+}
+
+impl<I: TableIndex> LexTable for CompressedLexTable<I> {
+    fn step(&self, state: usize, symbol: u8) -> usize {
+        let i = self.base[state] + usize::from(symbol);
+        if self.check[i].to_usize() == state {
+            self.next[i].to_usize()
+        } else {
+            self.default[state].to_usize()
+        }
+    }
+
+    fn class(&self, state: usize) -> Option<usize> {
+        self.classes[state]
+    }
+
+    fn sink(&self) -> usize {
+        self.classes.len() - 1
+    }
+
+    fn class_count(&self) -> usize {
+        self.class_count
+    }
+
+    fn class_name(&self, class: usize) -> Option<&str> {
+        self.names.get(class).map(String::as_str)
+    }
+
+    fn trailing_context(&self, class: usize) -> Option<&TrailingContext> {
+        self.contexts.get(class).and_then(Option::as_ref)
+    }
+
+    fn command(&self, class: usize) -> Command {
+        self.commands.get(class).copied().unwrap_or_default()
+    }
+}
+
+// =================
+// === INTERNALS ===
+// =================
+
+/// Returns the most common destination of `row`, the smallest of any ties.
+fn most_common(row: &[usize]) -> usize {
+    let mut counts: HashMap<usize, usize> = HashMap::new();
+    for &dest in row {
+        *counts.entry(dest).or_default() += 1;
+    }
+    counts.into_iter().max_by_key(|&(dest, count)| (count, Reverse(dest))).unwrap().0
+}
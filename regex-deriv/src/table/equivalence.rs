@@ -0,0 +1,115 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use super::{Command, LexTable};
+
+/// Returns `Ok` iff `a` and `b` implement the same automaton: i.e. every
+/// input leads both to a state of the same class, and tokens of each class
+/// accepted are handled by the same command. States are numbered
+/// independently, so tables of different kinds, or built from the DFA
+/// before and after minimization, can be compared. Runs stop at the sink,
+/// whose row, if any, is never read.
+///
+/// Pairs of states are explored breadth first from the start states, over
+/// all 256 bytes, so the witness of any mismatch is one of the shortest.
+/// Trailing contexts are not compared.
+///
+/// # Errors
+///
+/// Returns a shortest input on which the tables differ, if any.
+///
+/// # Examples
+///
+/// ```
+/// # use regex_deriv::{RegEx, ByteSet, DFA, NaiveLexTable, ClassLexTable, tables_equivalent};
+/// let word = RegEx::set(ByteSet::range(b'a', b'z')).plus();
+/// let space = RegEx::set(ByteSet::point(b' ')).plus();
+/// let dfa = DFA::from(&[word.clone(), space]);
+/// assert!(tables_equivalent(&dfa, &ClassLexTable::new(&dfa.minimize())).is_ok());
+/// let err = tables_equivalent(&dfa, &NaiveLexTable::new(&DFA::from(&word))).unwrap_err();
+/// assert_eq!(err.input(), b" ");
+/// assert_eq!(err.classes(), (Some(1), None));
+/// ```
+pub fn tables_equivalent(a: &(impl LexTable + ?Sized), b: &(impl LexTable + ?Sized)) -> Result<(), Mismatch> {
+    let start = (a.start_state(), b.start_state());
+    let mut parents = Parents::new();
+    parents.insert(start, None);
+    let mut queue = VecDeque::from(vec![start]);
+    while let Some(pair @ (p, q)) = queue.pop_front() {
+        let classes = (a.class(p), b.class(q));
+        let commands = classes.0.map(|class| (a.command(class), b.command(class)));
+        if classes.0 != classes.1 || commands.is_some_and(|(x, y)| x != y) {
+            return Err(Mismatch { input: witness(&parents, pair), classes, commands })
+        }
+        if p == a.sink() && q == b.sink() {
+            continue;
+        }
+        for symbol in 0..=255 {
+            let next = (step(a, p, symbol), step(b, q, symbol));
+            parents.entry(next).or_insert_with(|| {
+                queue.push_back(next);
+                Some((pair, symbol))
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Error produced by `tables_equivalent` when the tables differ.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Mismatch {
+    input: Vec<u8>,
+    classes: (Option<usize>, Option<usize>),
+    commands: Option<(Command, Command)>,
+}
+
+impl Mismatch {
+    /// Returns an input, of the shortest, on which the tables differ.
+    #[must_use]
+    pub fn input(&self) -> &[u8] {
+        &self.input
+    }
+
+    /// Returns the classes of the states `input` leads to, in each table.
+    /// These are the same if only the commands of the class differ.
+    #[must_use]
+    pub fn classes(&self) -> (Option<usize>, Option<usize>) {
+        self.classes
+    }
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "tables differ on input {:?}: ", String::from_utf8_lossy(&self.input))?;
+        match (self.classes, self.commands) {
+            ((Some(class), Some(_)), Some((x, y))) if x != y => write!(f, "tokens of class {class} are handled by {x:?} against {y:?}"),
+            ((x, y), _) => write!(f, "class {x:?} against {y:?}"),
+        }
+    }
+}
+
+impl std::error::Error for Mismatch {}
+
+// =================
+// === INTERNALS ===
+// =================
+
+/// Pair of states -> the pair and byte it was first reached from, if not the
+/// start pair.
+type Parents = HashMap<(usize, usize), Option<((usize, usize), u8)>>;
+
+/// Steps from `state`, unless it is the sink, which is never left.
+fn step<T: LexTable + ?Sized>(table: &T, state: usize, symbol: u8) -> usize {
+    if state == table.sink() { state } else { table.step(state, symbol) }
+}
+
+/// Returns the bytes leading from the start pair to `pair`.
+fn witness(parents: &Parents, pair: (usize, usize)) -> Vec<u8> {
+    let mut input = Vec::new();
+    let mut pair = pair;
+    while let Some((parent, symbol)) = parents[&pair] {
+        input.push(symbol);
+        pair = parent;
+    }
+    input.reverse();
+    input
+}
@@ -0,0 +1,86 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+/// Unsigned integer type of the state ids stored in a `NaiveLexTable`. The
+/// narrower the type, the more of the table stays in cache, but the fewer
+/// states it can number.
+pub trait TableIndex: Copy + PartialEq + fmt::Debug {
+    /// The largest state id the type can hold.
+    const MAX: usize;
+
+    /// Converts `id`, which is at most `MAX`.
+    fn from_usize(id: usize) -> Self;
+
+    /// Widens `self` back into a state id.
+    fn to_usize(self) -> usize;
+}
+
+impl TableIndex for u16 {
+    const MAX: usize = 0xffff;
+
+    fn from_usize(id: usize) -> Self {
+        Self::try_from(id).unwrap()
+    }
+
+    fn to_usize(self) -> usize {
+        usize::from(self)
+    }
+}
+
+impl TableIndex for u32 {
+    const MAX: usize = 0xffff_ffff;
+
+    fn from_usize(id: usize) -> Self {
+        Self::try_from(id).unwrap()
+    }
+
+    fn to_usize(self) -> usize {
+        usize::try_from(self).unwrap()
+    }
+}
+
+impl TableIndex for usize {
+    const MAX: usize = usize::MAX;
+
+    fn from_usize(id: usize) -> Self {
+        id
+    }
+
+    fn to_usize(self) -> usize {
+        self
+    }
+}
+
+/// Error produced by `NaiveLexTable::from_dfa` when the DFA has more states
+/// than the index type can number.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct IndexTooNarrow {
+    states: usize,
+    max: usize,
+}
+
+impl IndexTooNarrow {
+    pub(crate) fn new(states: usize, max: usize) -> Self {
+        Self { states, max }
+    }
+
+    /// Returns the number of states of the DFA.
+    #[must_use]
+    pub fn states(&self) -> usize {
+        self.states
+    }
+
+    /// Returns the largest state id the index type can hold.
+    #[must_use]
+    pub fn max(&self) -> usize {
+        self.max
+    }
+}
+
+impl fmt::Display for IndexTooNarrow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DFA of {} states does not fit a table of state ids up to {}", self.states, self.max)
+    }
+}
+
+impl std::error::Error for IndexTooNarrow {}
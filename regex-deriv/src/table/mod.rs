@@ -0,0 +1,642 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{self, Write};
+use std::mem::size_of;
+use std::rc::Rc;
+use std::sync::Arc;
+use super::{BuildError, BuildLimits, RegEx, DFA};
+
+pub trait LexTable {
+    /// Returns the id of the state to start each token from. This is 0 for
+    /// `NaiveLexTable`, but `DFA::START` for a `DFA` driven directly.
+    fn start_state(&self) -> usize {
+        0
+    }
+
+    fn step(&self, state: usize, symbol: u8) -> usize;
+    fn class(&self, state: usize) -> Option<usize>;
+    fn sink(&self) -> usize;
+
+    /// Returns the number of classes, such that the class of every state is
+    /// in `0..class_count()`, e.g. for sizing arrays by class. For tables
+    /// built from rules, this is the number of rules, including any that are
+    /// shadowed and so never accepted.
+    fn class_count(&self) -> usize;
+
+    /// Returns the name of `class`, if it has one, for displaying tokens and
+    /// in error messages. By default, no class has a name.
+    fn class_name(&self, _class: usize) -> Option<&str> {
+        None
+    }
+
+    /// Returns the class of the token `Scan` yields at the end of the input,
+    /// if any. By default, there is none.
+    fn eof_class(&self) -> Option<usize> {
+        None
+    }
+
+    /// Returns the trailing context of `class`, if any. Tokens of such a
+    /// class only span the head of the matched lexeme.
+    fn trailing_context(&self, _class: usize) -> Option<&TrailingContext> {
+        None
+    }
+
+    /// Returns what the scanner does with tokens of `class`. By default,
+    /// every token is emitted.
+    fn command(&self, _class: usize) -> Command {
+        Command::Emit
+    }
+
+    /// Returns `true` iff no accepting state is reachable from `state`, so
+    /// that runs can stop early. By default, only the sink is known to be
+    /// dead.
+    fn is_dead(&self, state: usize) -> bool {
+        state == self.sink()
+    }
+
+    /// Returns the state reached by stepping from `state` over `bytes`,
+    /// stopping early if a dead state is reached.
+    fn run(&self, state: usize, bytes: &[u8]) -> usize {
+        let mut state = state;
+        for &byte in bytes {
+            if self.is_dead(state) {
+                break;
+            }
+            state = self.step(state, byte);
+        }
+        state
+    }
+
+    /// Same as `run`, but also returns the offset into `bytes` and class of
+    /// the last accepting state visited (including `state` itself, at offset
+    /// 0), if any.
+    fn run_with_last_accept(&self, state: usize, bytes: &[u8]) -> (usize, Option<(usize, usize)>) {
+        let mut state = state;
+        let mut last_accept = self.class(state).map(|class| (0, class));
+        for (i, &byte) in bytes.iter().enumerate() {
+            if self.is_dead(state) {
+                break;
+            }
+            state = self.step(state, byte);
+            if let Some(class) = self.class(state) {
+                last_accept = Some((i + 1, class));
+            }
+        }
+        (state, last_accept)
+    }
+
+    /// Returns the length and class of the longest prefix of `input[start..]`
+    /// that is accepted (i.e. the maximal munch at `start`), or `None` if not
+    /// even the empty prefix is accepted. Stops reading `input` as soon as
+    /// a dead state is reached.
+    fn longest_match_at(&self, input: &[u8], start: usize) -> Option<(usize, usize)> {
+        self.run_with_last_accept(self.start_state(), &input[start..]).1
+    }
+}
+
+/// Forwards every method, so that overrides of the defaults are kept.
+macro_rules! forward_impl {
+    ($($pointer:ty),*) => {$(
+        impl<T: LexTable + ?Sized> LexTable for $pointer {
+            fn start_state(&self) -> usize { (**self).start_state() }
+            fn step(&self, state: usize, symbol: u8) -> usize { (**self).step(state, symbol) }
+            fn class(&self, state: usize) -> Option<usize> { (**self).class(state) }
+            fn sink(&self) -> usize { (**self).sink() }
+            fn class_count(&self) -> usize { (**self).class_count() }
+            fn class_name(&self, class: usize) -> Option<&str> { (**self).class_name(class) }
+            fn eof_class(&self) -> Option<usize> { (**self).eof_class() }
+            fn trailing_context(&self, class: usize) -> Option<&TrailingContext> { (**self).trailing_context(class) }
+            fn command(&self, class: usize) -> Command { (**self).command(class) }
+            fn is_dead(&self, state: usize) -> bool { (**self).is_dead(state) }
+            fn run(&self, state: usize, bytes: &[u8]) -> usize { (**self).run(state, bytes) }
+            fn run_with_last_accept(&self, state: usize, bytes: &[u8]) -> (usize, Option<(usize, usize)>) {
+                (**self).run_with_last_accept(state, bytes)
+            }
+            fn longest_match_at(&self, input: &[u8], start: usize) -> Option<(usize, usize)> {
+                (**self).longest_match_at(input, start)
+            }
+        }
+    )*};
+}
+
+forward_impl!(&T, Box<T>, Rc<T>, Arc<T>);
+
+/// What the scanner does with the tokens of a class, as given by
+/// `LexTable::command`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum Command {
+    /// Yield the token.
+    #[default]
+    Emit,
+    /// Drop the token and carry on scanning, as for whitespace or comments.
+    Skip,
+}
+
+/// A regex `head` that only matches when followed by `lookahead`, as in lex's
+/// `head/lookahead` rules. The class of such a rule should be built from
+/// `regex()`, which also consumes the lookahead; the scanner then trims each
+/// lexeme of the class back to the end of its head. Note that the lookahead
+/// takes part in maximal munch, so should typically be bounded in length.
+pub struct TrailingContext {
+    head: RegEx,
+    lookahead: RegEx,
+    head_dfa: DFA,
+    reversed_lookahead_dfa: DFA,
+}
+
+impl RegEx {
+    /// Returns the trailing-context rule matching `self` only when followed
+    /// by `lookahead`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet};
+    /// let [a, b] = [b'a', b'b'].map(|x| RegEx::set(ByteSet::point(x)));
+    /// let rule = a.plus().followed_by(&b);
+    /// assert!(rule.regex().is_fullmatch("aab"));
+    /// assert_eq!(rule.split(b"aab"), Some(2));
+    /// ```
+    #[must_use]
+    pub fn followed_by(&self, lookahead: &RegEx) -> TrailingContext {
+        TrailingContext::new(self, lookahead)
+    }
+}
+
+impl TrailingContext {
+    /// Constructs the rule matching `head` only when followed by `lookahead`.
+    #[must_use]
+    pub fn new(head: &RegEx, lookahead: &RegEx) -> Self {
+        Self {
+            head: head.clone(),
+            lookahead: lookahead.clone(),
+            head_dfa: DFA::from(head).minimize(),
+            reversed_lookahead_dfa: DFA::from(&lookahead.reverse()).minimize(),
+        }
+    }
+
+    /// Returns the regex matching the head followed by the lookahead.
+    #[must_use]
+    pub fn regex(&self) -> RegEx {
+        self.head.then(&self.lookahead)
+    }
+
+    /// Returns the length of the longest head of `lexeme` such that the rest
+    /// of `lexeme` matches the lookahead, or `None` if `lexeme` does not match
+    /// `regex()`.
+    #[must_use]
+    pub fn split(&self, lexeme: &[u8]) -> Option<usize> {
+        // head_ends[i] iff lexeme[..i] matches the head
+        let mut head_ends = vec![false; lexeme.len() + 1];
+        let mut id = DFA::START;
+        head_ends[0] = self.head_dfa.class(id).is_some();
+        for (i, &byte) in lexeme.iter().enumerate() {
+            id = self.head_dfa.step(id, byte);
+            if id == 0 {
+                break;
+            }
+            head_ends[i + 1] = self.head_dfa.class(id).is_some();
+        }
+
+        // run the reversed lookahead backwards from the end of the lexeme
+        let dfa = &self.reversed_lookahead_dfa;
+        let mut id = DFA::START;
+        for i in (0..=lexeme.len()).rev() {
+            if dfa.class(id).is_some() && head_ends[i] {
+                return Some(i);
+            }
+            if i == 0 {
+                break;
+            }
+            id = dfa.step(id, lexeme[i - 1]);
+            if id == 0 {
+                break;
+            }
+        }
+        None
+    }
+}
+
+/// Dense transition table of a DFA, one row of 256 entries per state. Rows
+/// are numbered as the states of the DFA, less one, but with the sink last:
+/// so the start is state 0. The sink has no row, as runs stop there. If the
+/// DFA accepts nothing, the table is just the sink, which is then also the
+/// start.
+///
+/// Entries are of type `I`, `u32` unless constructed by `from_dfa`: `u16`
+/// halves the size of the table, for DFAs of up to 65536 states.
+pub struct NaiveLexTable<I: TableIndex = u32> {
+    pub(crate) next:        Vec<I>,
+    pub(crate) classes:     Vec<Option<usize>>,
+    pub(crate) class_count: usize,
+    pub(crate) names:       Vec<String>,
+    pub(crate) contexts:    Vec<Option<TrailingContext>>,
+    pub(crate) commands:    Vec<Command>,
+}
+
+impl NaiveLexTable {
+    /// Constructs the table of `dfa`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `dfa` has more than `u32::MAX` states.
+    #[must_use]
+    pub fn new(dfa: &DFA) -> Self {
+        Self::from_dfa(dfa).unwrap()
+    }
+
+    /// Constructs the table of the minimized DFA of `rules`, as
+    /// `NaiveLexTable::new(&DFA::from(rules).minimize())` would.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the DFA has more than `u32::MAX` states.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, NaiveLexTable, Scan};
+    /// let word = RegEx::set(ByteSet::ASCII_ALPHA).plus();
+    /// let table = NaiveLexTable::from_rules(&[word, RegEx::set(ByteSet::point(b' '))]);
+    /// assert_eq!(Scan::new(&table, "hi there").count(), 3);
+    /// ```
+    #[must_use]
+    pub fn from_rules(rules: &[RegEx]) -> Self {
+        Self::new(&DFA::from(rules).minimize())
+    }
+
+    /// Same as `from_rules`, but gives up as soon as the DFA would exceed
+    /// `limits`, as in `DFA::try_from_rules`.
+    ///
+    /// # Errors
+    ///
+    /// Returns which limit was exceeded, if any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the DFA has more than `u32::MAX` states.
+    pub fn try_from_rules(rules: &[RegEx], limits: &BuildLimits) -> Result<Self, BuildError> {
+        let rules: Vec<_> = rules.iter().map(|rule| (rule.clone(), 0)).collect();
+        DFA::try_from_rules(&rules, limits).map(|dfa| Self::new(&dfa.minimize()))
+    }
+
+    /// Same as `from_rules`, except that tokens of each rule are handled as
+    /// given by its command, as in `with_commands`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the DFA has more than `u32::MAX` states.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, NaiveLexTable, Command, Scan};
+    /// let word = RegEx::set(ByteSet::ASCII_ALPHA).plus();
+    /// let number = RegEx::set(ByteSet::ASCII_DIGIT).plus();
+    /// let space = RegEx::set(ByteSet::ASCII_WHITESPACE).plus();
+    /// let table = NaiveLexTable::from_rules_with_commands(&[(word, Command::Emit), (number, Command::Emit), (space, Command::Skip)]);
+    /// let classes: Vec<_> = Scan::new(&table, "route 66").map(|token| token.unwrap().class).collect();
+    /// assert_eq!(classes, [0, 1]);
+    /// ```
+    #[must_use]
+    pub fn from_rules_with_commands(rules: &[(RegEx, Command)]) -> Self {
+        let (regexes, commands): (Vec<_>, Vec<_>) = rules.iter().cloned().unzip();
+        Self::with_commands(&DFA::from_owned(regexes).minimize(), &commands)
+    }
+
+    /// Same as `new`, but fails if any rule never makes a token, as given by
+    /// `DFA::unreachable_classes`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the classes of the rules that never make a token, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA, NaiveLexTable};
+    /// let lit = |s: &[u8]| s.iter().fold(RegEx::empty(), |re, &c| re.then(&RegEx::set(ByteSet::point(c))));
+    /// let ident = RegEx::set(ByteSet::range(b'a', b'z')).plus();
+    /// let err = NaiveLexTable::try_new(&DFA::from(&[ident.clone(), lit(b"if")])).err().unwrap();
+    /// assert_eq!(err.classes(), [1]);
+    /// assert!(NaiveLexTable::try_new(&DFA::from(&[lit(b"if"), ident])).is_ok());
+    /// ```
+    pub fn try_new(dfa: &DFA) -> Result<Self, ShadowedRules> {
+        let classes = dfa.unreachable_classes();
+        if classes.is_empty() {
+            Ok(Self::new(dfa))
+        } else {
+            Err(ShadowedRules { classes })
+        }
+    }
+
+    /// Same as `new`, except that the class `i` has trailing context
+    /// `contexts[i]`. `dfa` is expected to have been built with the `regex()`
+    /// of each trailing context in place of its rule.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA, NaiveLexTable, Scan};
+    /// let [a, b] = [b'a', b'b'].map(|x| RegEx::set(ByteSet::point(x)));
+    /// let rule = a.followed_by(&b);
+    /// let table = NaiveLexTable::with_trailing_contexts(
+    ///     &DFA::from(&[rule.regex(), b.clone()]).minimize(),
+    ///     vec![Some(rule), None],
+    /// );
+    /// let classes: Vec<_> = Scan::new(&table, "ab").map(|token| token.unwrap().class).collect();
+    /// assert_eq!(classes, [0, 1]);
+    /// ```
+    #[must_use]
+    pub fn with_trailing_contexts(dfa: &DFA, contexts: Vec<Option<TrailingContext>>) -> Self {
+        Self { contexts, ..Self::new(dfa) }
+    }
+
+    /// Same as `new`, except that tokens of class `i` are handled as given
+    /// by `commands[i]`, or emitted if there is no such command.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA, NaiveLexTable, Command, Scan};
+    /// let space = RegEx::set(ByteSet::point(b' ')).plus();
+    /// let word = RegEx::set(ByteSet::range(b'a', b'z')).plus();
+    /// let table = NaiveLexTable::with_commands(&DFA::from(&[space, word]).minimize(), &[Command::Skip]);
+    /// let spans: Vec<_> = Scan::new(&table, "hi  there ").map(|token| token.unwrap().span).collect();
+    /// assert_eq!(spans, [0..2, 4..9]);
+    /// ```
+    #[must_use]
+    pub fn with_commands(dfa: &DFA, commands: &[Command]) -> Self {
+        Self { commands: commands.to_vec(), ..Self::new(dfa) }
+    }
+}
+
+impl<I: TableIndex> NaiveLexTable<I> {
+    /// Constructs the table of `dfa`, with entries of type `I`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `dfa` has more states than `I` can number.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA, NaiveLexTable, Scan};
+    /// let word = RegEx::set(ByteSet::range(b'a', b'z')).plus();
+    /// let table = NaiveLexTable::<u16>::from_dfa(&DFA::from(&word)).unwrap();
+    /// assert_eq!(Scan::new(&table, "hello").count(), 1);
+    /// ```
+    pub fn from_dfa(dfa: &DFA) -> Result<Self, IndexTooNarrow> {
+        // with a dead start, no other row would ever be reached, and the
+        // start must be the sink for scans to stop at once
+        if dfa.is_dead(DFA::START) {
+            return Ok(Self { next: Vec::new(), classes: vec![None], class_count: dfa.class_count(), names: Vec::new(), contexts: Vec::new(), commands: Vec::new() });
+        }
+
+        let nrows = dfa.states().len() - 1; // excluding sink
+        if nrows > I::MAX {
+            return Err(IndexTooNarrow::new(dfa.states().len(), I::MAX));
+        }
+        let mut next = vec![I::from_usize(nrows); 256 * nrows];
+        for (i, state) in dfa.states().iter().skip(1).enumerate() {
+            // transitions into the sink are left leading to its last row
+            for (set, dest) in state.transitions_grouped() {
+                for symbol in set.bytes() {
+                    next[256 * i + symbol as usize] = I::from_usize(dest - 1);
+                }
+            }
+        }
+        
+        let classes = dfa.states().iter().skip(1)
+            .map(|state| state.class)
+            .chain(vec![None]) // <-- sink states class
+            .collect();
+        
+        Ok(Self {
+            next,
+            classes,
+            class_count: dfa.class_count(),
+            names: Vec::new(),
+            contexts: Vec::new(),
+            commands: Vec::new(),
+        })
+    }
+
+    /// Returns `self` with the class `i` named `names[i]`, as given by
+    /// `LexTable::class_name`. Classes past the end of `names` have none.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, NaiveLexTable, LexTable};
+    /// let word = RegEx::set(ByteSet::ASCII_ALPHA).plus();
+    /// let number = RegEx::set(ByteSet::ASCII_DIGIT).plus();
+    /// let table = NaiveLexTable::from_rules(&[word, number]).with_class_names(&["IDENT"]);
+    /// assert_eq!(table.class_count(), 2);
+    /// assert_eq!(table.class_name(0), Some("IDENT"));
+    /// assert_eq!(table.class_name(1), None);
+    /// ```
+    #[must_use]
+    pub fn with_class_names(self, names: &[&str]) -> Self {
+        Self { names: names.iter().map(|&name| name.to_string()).collect(), ..self }
+    }
+
+    /// Returns the number of bytes `self` holds on the heap, by the capacity
+    /// of each of its buffers, including any class names, commands and the
+    /// DFAs of trailing contexts, but not the regexes of those.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA, NaiveLexTable};
+    /// let dfa = DFA::from(&RegEx::set(ByteSet::range(b'a', b'z')).plus()).minimize();
+    /// let narrow = NaiveLexTable::<u16>::from_dfa(&dfa).unwrap();
+    /// assert!(narrow.memory_usage() < NaiveLexTable::new(&dfa).memory_usage());
+    /// ```
+    #[must_use]
+    pub fn memory_usage(&self) -> usize {
+        heap_size(&self.next) + heap_size(&self.classes) + class_data_size(&self.names, &self.contexts, &self.commands)
+    }
+
+    /// Writes Rust source defining `pub static <ident>: StaticLexTable`, the
+    /// same table, to `out`, for building it ahead of time (e.g. in a build
+    /// script) and `include!`ing it. The source refers to `StaticLexTable`
+    /// unqualified, so it must be in scope where it is included, and `ident`
+    /// should be in upper case for it to compile without warnings. Class
+    /// names are written, but trailing contexts and commands are not.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error encountered whilst writing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self` has `u32::MAX` or more states.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA, NaiveLexTable};
+    /// let table = NaiveLexTable::new(&DFA::from(&RegEx::set(ByteSet::point(b'a'))));
+    /// let mut source = Vec::new();
+    /// table.write_rust(&mut source, "TABLE").unwrap();
+    /// assert!(String::from_utf8(source).unwrap().contains("pub static TABLE: StaticLexTable"));
+    /// ```
+    pub fn write_rust(&self, out: &mut impl Write, ident: &str) -> io::Result<()> {
+        let mut remap = [0; 256];
+        for (class, symbol) in remap.iter_mut().zip(0..=255) {
+            *class = symbol;
+        }
+        // unlike here, the sink has a row, leading back to it
+        let sink = self.sink();
+        let next: Vec<u32> = self.next.iter().map(|&dest| dest.to_usize())
+            .chain(std::iter::repeat_n(sink, 256))
+            .map(|dest| u32::try_from(dest).expect("table too large"))
+            .collect();
+        static_table::write_rust(out, ident, &static_table::Source {
+            remap: &remap,
+            stride: 256,
+            next: &next,
+            classes: &self.classes,
+            class_count: self.class_count,
+            class_names: &self.names,
+        })
+    }
+}
+
+/// Error produced by `NaiveLexTable::try_new` when some rules never make a
+/// token.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ShadowedRules {
+    classes: Vec<usize>,
+}
+
+impl ShadowedRules {
+    /// Returns the classes of the rules that never make a token, in
+    /// ascending order.
+    #[must_use]
+    pub fn classes(&self) -> &[usize] {
+        &self.classes
+    }
+}
+
+impl fmt::Display for ShadowedRules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rules of classes ")?;
+        for (i, class) in self.classes.iter().enumerate() {
+            write!(f, "{}{class}", if i == 0 { "" } else { ", " })?;
+        }
+        write!(f, " never match: they are shadowed by rules of higher priority, or match nothing")
+    }
+}
+
+impl std::error::Error for ShadowedRules {}
+
+impl<I: TableIndex> LexTable for NaiveLexTable<I> {
+    fn step(&self, state: usize, symbol: u8) -> usize {
+        self.next[256 * state + symbol as usize].to_usize()
+    }
+
+    fn class(&self, state: usize) -> Option<usize> {
+        self.classes[state]
+    }
+
+    fn sink(&self) -> usize { 
+        self.classes.len() - 1
+    }
+
+    fn class_count(&self) -> usize {
+        self.class_count
+    }
+
+    fn class_name(&self, class: usize) -> Option<&str> {
+        self.names.get(class).map(String::as_str)
+    }
+
+    fn trailing_context(&self, class: usize) -> Option<&TrailingContext> {
+        self.contexts.get(class).and_then(Option::as_ref)
+    }
+
+    fn command(&self, class: usize) -> Command {
+        self.commands.get(class).copied().unwrap_or_default()
+    }
+}
+
+/// Size in bytes under which a `NaiveLexTable` is taken to stay in cache, as
+/// for a typical L2 cache.
+const CACHE_SIZE: usize = 256 * 1024;
+
+/// Constructs the table of `dfa` best suited to its size and density: a
+/// `NaiveLexTable`, whose step is quickest, if it would stay in cache;
+/// otherwise a `ClassLexTable`, unless a `SparseLexTable` would be less than
+/// half its size, as its step is a binary search.
+///
+/// # Panics
+///
+/// Panics if `dfa` has `u32::MAX` or more states.
+///
+/// # Examples
+///
+/// ```
+/// # use regex_deriv::{RegEx, ByteSet, DFA, build_table, Scan};
+/// let word = RegEx::set(ByteSet::range(b'a', b'z')).plus();
+/// let space = RegEx::set(ByteSet::point(b' ')).plus();
+/// let table = build_table(&DFA::from(&[word, space]).minimize());
+/// assert_eq!(Scan::new(&table, "hi there").count(), 3);
+/// ```
+#[must_use]
+pub fn build_table(dfa: &DFA) -> Box<dyn LexTable> {
+    let states = dfa.states().len();
+    let classes_size = states * size_of::<Option<usize>>();
+    if states * 256 * size_of::<u32>() + classes_size <= CACHE_SIZE {
+        return Box::new(NaiveLexTable::new(dfa))
+    }
+    // estimated rather than built, as building goes through a naive table
+    let (_, stride) = dfa.byte_classes();
+    let class_size = states * stride * size_of::<u32>() + classes_size;
+    let sparse = SparseLexTable::new(dfa);
+    if 2 * sparse.memory_usage() < class_size {
+        Box::new(sparse)
+    } else {
+        Box::new(ClassLexTable::new(dfa))
+    }
+}
+
+// =================
+// === INTERNALS ===
+// =================
+
+/// Returns the number of bytes `vec` holds on the heap.
+#[allow(clippy::ptr_arg)] // by capacity, not length
+pub(crate) fn heap_size<T>(vec: &Vec<T>) -> usize {
+    vec.capacity() * size_of::<T>()
+}
+
+/// Returns the number of bytes held on the heap by the class data shared by
+/// the tables built from a `NaiveLexTable`.
+pub(crate) fn class_data_size(names: &Vec<String>, contexts: &Vec<Option<TrailingContext>>, commands: &Vec<Command>) -> usize {
+    let names_size: usize = names.iter().map(String::capacity).sum();
+    let contexts_size: usize = contexts.iter().flatten()
+        .map(|context| context.head_dfa.memory_usage() + context.reversed_lookahead_dfa.memory_usage())
+        .sum();
+    heap_size(names) + names_size + heap_size(contexts) + contexts_size + heap_size(commands)
+}
+
+mod index;
+pub use index::{IndexTooNarrow, TableIndex};
+mod equivalence;
+pub use equivalence::{tables_equivalent, Mismatch};
+mod compressed;
+pub use compressed::CompressedLexTable;
+mod class;
+pub use class::ClassLexTable;
+mod sparse;
+pub use sparse::SparseLexTable;
+mod static_table;
+pub use static_table::StaticLexTable;
+mod validate;
+pub use validate::TableError;
+
+#[cfg(feature = "serde")]
+mod serde;
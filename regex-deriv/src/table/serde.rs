@@ -0,0 +1,73 @@
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+use super::{Command, NaiveLexTable, TableIndex};
+
+/// Incremented whenever the serialized representation of a `NaiveLexTable`
+/// changes.
+const FORMAT_VERSION: u32 = 3;
+
+impl<I: TableIndex> Serialize for NaiveLexTable<I> {
+    /// Fails if any class has a trailing context, as those are not
+    /// serialized.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.contexts.iter().any(Option::is_some) {
+            return Err(ser::Error::custom("trailing contexts cannot be serialized"))
+        }
+        let next = self.next.iter().map(|&dest| dest.to_usize()).collect();
+        Repr {
+            version: FORMAT_VERSION,
+            next,
+            classes: self.classes.clone(),
+            class_count: self.class_count,
+            names: self.names.clone(),
+            commands: self.commands.clone(),
+        }.serialize(serializer)
+    }
+}
+
+impl<'de, I: TableIndex> Deserialize<'de> for NaiveLexTable<I> {
+    /// Fails if the table is malformed, as given by `NaiveLexTable::validate`,
+    /// so `step` never panics on the result. Also fails if `I` cannot number
+    /// its states.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = Repr::deserialize(deserializer)?;
+        if repr.version != FORMAT_VERSION {
+            return Err(de::Error::custom(format_args!("unsupported table format version {}", repr.version)))
+        }
+        // checked as usize, as entries out of range may not fit I
+        let table = NaiveLexTable::<usize> {
+            next: repr.next,
+            classes: repr.classes,
+            class_count: repr.class_count,
+            names: repr.names,
+            contexts: Vec::new(),
+            commands: repr.commands,
+        };
+        table.validate().map_err(de::Error::custom)?;
+        let n = table.classes.len();
+        if n - 1 > I::MAX {
+            return Err(de::Error::custom(format_args!("table of {n} states does not fit state ids up to {}", I::MAX)))
+        }
+        Ok(Self {
+            next: table.next.into_iter().map(I::from_usize).collect(),
+            classes: table.classes,
+            class_count: table.class_count,
+            names: table.names,
+            contexts: table.contexts,
+            commands: table.commands,
+        })
+    }
+}
+
+// =================
+// === INTERNALS ===
+// =================
+
+#[derive(Serialize, Deserialize)]
+struct Repr {
+    version: u32,
+    next: Vec<usize>,
+    classes: Vec<Option<usize>>,
+    class_count: usize,
+    names: Vec<String>,
+    commands: Vec<Command>,
+}
@@ -0,0 +1,156 @@
+use std::convert::TryFrom;
+use std::mem::size_of;
+use super::{class_data_size, heap_size, Command, LexTable, TrailingContext};
+use crate::DFA;
+
+/// Transition table of a DFA as runs of bytes, with states numbered as in
+/// `NaiveLexTable`. Each state has a row of `(last, dest)` pairs, in
+/// ascending order of `last`, covering all 256 bytes: bytes up to `last`,
+/// from past the previous run, lead to `dest`. A step is then a binary
+/// search of the row. Most states of large lexers lead to a handful of
+/// others, by a few runs each, so the table is far smaller than a dense one
+/// for such DFAs, at the cost of a slower step.
+///
+/// # Examples
+///
+/// ```
+/// # use regex_deriv::{RegEx, ByteSet, DFA, NaiveLexTable, SparseLexTable, Scan};
+/// let word = RegEx::set(ByteSet::range(b'a', b'z')).plus();
+/// let space = RegEx::set(ByteSet::point(b' ')).plus();
+/// let dfa = DFA::from(&[word, space]).minimize();
+/// let table = SparseLexTable::new(&dfa);
+/// assert!(table.memory_usage() < NaiveLexTable::new(&dfa).memory_usage() / 10);
+/// let tokens: Vec<_> = Scan::new(&table, "hi there").map(|token| token.unwrap().span).collect();
+/// assert_eq!(tokens, [0..2, 2..3, 3..8]);
+/// ```
+pub struct SparseLexTable {
+    rows:     Vec<Box<[(u8, u32)]>>,
+    classes:  Vec<Option<usize>>,
+    class_count: usize,
+    names:    Vec<String>,
+    contexts: Vec<Option<TrailingContext>>,
+    commands: Vec<Command>,
+}
+
+impl SparseLexTable {
+    /// Constructs the table of `dfa`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `dfa` has `u32::MAX` or more states.
+    #[must_use]
+    pub fn new(dfa: &DFA) -> Self {
+        // as in the naive table, a dead start is the sink
+        if dfa.is_dead(DFA::START) {
+            return Self::from_rows(dfa, vec![Box::new([(255, 0)])], vec![None]);
+        }
+
+        let sink = dfa.states().len() - 1;
+        let id = |dest: usize| u32::try_from(if dest == 0 { sink } else { dest - 1 }).expect("DFA too large");
+        let mut rows = Vec::with_capacity(sink + 1);
+        for state in &dfa.states()[1..] {
+            let mut row: Vec<(u8, u32)> = Vec::new();
+            let mut push = |last: u8, dest: u32| match row.last_mut() {
+                Some(run) if run.1 == dest => run.0 = last,
+                _ => row.push((last, dest)),
+            };
+            // bytes between ranges lead to the sink
+            let mut from = 0;
+            for &(lo, hi, dest) in &state.next {
+                if usize::from(lo) > from {
+                    push(lo - 1, id(0));
+                }
+                push(hi, id(dest));
+                from = usize::from(hi) + 1;
+            }
+            if from <= 255 {
+                push(255, id(0));
+            }
+            rows.push(row.into_boxed_slice());
+        }
+        // unlike in the naive table, the sink has a row, leading back to it
+        rows.push(Box::new([(255, id(0))]));
+
+        let classes = dfa.states()[1..].iter().map(|state| state.class).chain(Some(None)).collect();
+        Self::from_rows(dfa, rows, classes)
+    }
+
+    /// Same as `new`, except that the class `i` has trailing context
+    /// `contexts[i]`, as in `NaiveLexTable::with_trailing_contexts`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `dfa` has `u32::MAX` or more states.
+    #[must_use]
+    pub fn with_trailing_contexts(dfa: &DFA, contexts: Vec<Option<TrailingContext>>) -> Self {
+        Self { contexts, ..Self::new(dfa) }
+    }
+
+    /// Same as `new`, except that tokens of class `i` are handled as given
+    /// by `commands[i]`, as in `NaiveLexTable::with_commands`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `dfa` has `u32::MAX` or more states.
+    #[must_use]
+    pub fn with_commands(dfa: &DFA, commands: &[Command]) -> Self {
+        Self { commands: commands.to_vec(), ..Self::new(dfa) }
+    }
+
+    /// Returns `self` with the class `i` named `names[i]`, as in
+    /// `NaiveLexTable::with_class_names`.
+    #[must_use]
+    pub fn with_class_names(self, names: &[&str]) -> Self {
+        Self { names: names.iter().map(|&name| name.to_string()).collect(), ..self }
+    }
+
+    fn from_rows(dfa: &DFA, rows: Vec<Box<[(u8, u32)]>>, classes: Vec<Option<usize>>) -> Self {
+        Self { rows, classes, class_count: dfa.class_count(), names: Vec::new(), contexts: Vec::new(), commands: Vec::new() }
+    }
+
+    /// Returns the number of runs of all rows, including that of the sink.
+    #[must_use]
+    pub fn runs(&self) -> usize {
+        self.rows.iter().map(|row| row.len()).sum()
+    }
+
+    /// Returns the number of bytes `self` holds on the heap, as in
+    /// `NaiveLexTable::memory_usage`.
+    #[must_use]
+    pub fn memory_usage(&self) -> usize {
+        let rows = heap_size(&self.rows) + self.runs() * size_of::<(u8, u32)>();
+        rows + heap_size(&self.classes) + class_data_size(&self.names, &self.contexts, &self.commands)
+    }
+}
+
+impl LexTable for SparseLexTable {
+    fn step(&self, state: usize, symbol: u8) -> usize {
+        let row = &self.rows[state];
+        let run = row.partition_point(|&(last, _)| last < symbol);
+        usize::try_from(row[run].1).unwrap()
+    }
+
+    fn class(&self, state: usize) -> Option<usize> {
+        self.classes[state]
+    }
+
+    fn sink(&self) -> usize {
+        self.classes.len() - 1
+    }
+
+    fn class_count(&self) -> usize {
+        self.class_count
+    }
+
+    fn class_name(&self, class: usize) -> Option<&str> {
+        self.names.get(class).map(String::as_str)
+    }
+
+    fn trailing_context(&self, class: usize) -> Option<&TrailingContext> {
+        self.contexts.get(class).and_then(Option::as_ref)
+    }
+
+    fn command(&self, class: usize) -> Command {
+        self.commands.get(class).copied().unwrap_or_default()
+    }
+}
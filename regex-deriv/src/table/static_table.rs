@@ -0,0 +1,95 @@
+use std::convert::TryFrom;
+use std::io::{self, Write};
+use super::LexTable;
+
+/// Transition table over byte classes, as in `ClassLexTable`, but of
+/// borrowed slices, so that it can be a `static`: as generated by
+/// `NaiveLexTable::write_rust` or `ClassLexTable::write_rust`, for building
+/// a lexer at compile time.
+///
+/// # Examples
+///
+/// ```
+/// # use regex_deriv::{LexTable, StaticLexTable, Scan};
+/// // any byte, one at a time
+/// static TABLE: StaticLexTable = StaticLexTable {
+///     remap: &[0; 256],
+///     stride: 1,
+///     next: &[1, 2, 2],
+///     classes: &[None, Some(0), None],
+///     class_count: 1,
+///     class_names: &["BYTE"],
+/// };
+/// assert_eq!(TABLE.sink(), 2);
+/// assert_eq!(TABLE.class_name(0), Some("BYTE"));
+/// assert_eq!(Scan::new(&TABLE, "xyz").flatten().count(), 3);
+/// ```
+pub struct StaticLexTable {
+    /// The byte class of each byte.
+    pub remap: &'static [u8; 256],
+    /// The number of byte classes, which is the length of each row.
+    pub stride: usize,
+    /// The transitions, by `stride * state + remap[symbol]`, with the sink
+    /// last.
+    pub next: &'static [u32],
+    /// The class accepted by each state, if any.
+    pub classes: &'static [Option<usize>],
+    /// The number of classes.
+    pub class_count: usize,
+    /// The name of each class, if any, as given by `LexTable::class_name`.
+    pub class_names: &'static [&'static str],
+}
+
+impl LexTable for StaticLexTable {
+    fn step(&self, state: usize, symbol: u8) -> usize {
+        let class = usize::from(self.remap[usize::from(symbol)]);
+        usize::try_from(self.next[self.stride * state + class]).unwrap()
+    }
+
+    fn class(&self, state: usize) -> Option<usize> {
+        self.classes[state]
+    }
+
+    fn sink(&self) -> usize {
+        self.classes.len() - 1
+    }
+
+    fn class_count(&self) -> usize {
+        self.class_count
+    }
+
+    fn class_name(&self, class: usize) -> Option<&str> {
+        self.class_names.get(class).copied()
+    }
+}
+
+/// The fields of a `StaticLexTable` to be written by `write_rust`.
+pub struct Source<'a> {
+    pub remap:       &'a [u8; 256],
+    pub stride:      usize,
+    pub next:        &'a [u32],
+    pub classes:     &'a [Option<usize>],
+    pub class_count: usize,
+    pub class_names: &'a [String],
+}
+
+/// Writes a `StaticLexTable` named `ident` with the fields of `source`, one
+/// row of `next` per line.
+pub fn write_rust(out: &mut impl Write, ident: &str, source: &Source<'_>) -> io::Result<()> {
+    let list = |items: &mut dyn Iterator<Item = String>| items.collect::<Vec<_>>().join(", ");
+
+    writeln!(out, "// Generated by regex-deriv. Do not edit.")?;
+    writeln!(out, "pub static {ident}: StaticLexTable = StaticLexTable {{")?;
+    writeln!(out, "    remap: &[{}],", list(&mut source.remap.iter().map(u8::to_string)))?;
+    writeln!(out, "    stride: {},", source.stride)?;
+    writeln!(out, "    next: &[")?;
+    for row in source.next.chunks(source.stride) {
+        writeln!(out, "        {},", list(&mut row.iter().map(u32::to_string)))?;
+    }
+    writeln!(out, "    ],")?;
+    let class = |class: &Option<usize>| class.map_or_else(|| "None".to_string(), |class| format!("Some({class})"));
+    writeln!(out, "    classes: &[{}],", list(&mut source.classes.iter().map(class)))?;
+    writeln!(out, "    class_count: {},", source.class_count)?;
+    writeln!(out, "    class_names: &[{}],", list(&mut source.class_names.iter().map(|name| format!("{name:?}"))))?;
+    writeln!(out, "}};")
+}
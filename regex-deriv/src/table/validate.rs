@@ -0,0 +1,200 @@
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::fmt;
+use super::{LexTable, NaiveLexTable, StaticLexTable, TableIndex};
+
+impl<I: TableIndex> NaiveLexTable<I> {
+    /// Checks that `self` is well-formed: that it has a sink, which doesn't
+    /// accept, that there is a row of 256 transitions for every other state,
+    /// that every transition leads to a state, and that every class is below
+    /// `class_count`. Tables built from a DFA always are, and deserialization
+    /// checks as much; this is for auditing tables from elsewhere.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first fault found, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use regex_deriv::{RegEx, ByteSet, DFA, NaiveLexTable};
+    /// let table = NaiveLexTable::from_rules(&[RegEx::set(ByteSet::range(b'a', b'z')).plus()]);
+    /// assert_eq!(table.validate(), Ok(()));
+    /// ```
+    pub fn validate(&self) -> Result<(), TableError> {
+        let states = self.classes.len();
+        if states == 0 {
+            return Err(TableError::NoSink)
+        }
+        if self.classes[states - 1].is_some() {
+            return Err(TableError::AcceptingSink)
+        }
+        if self.next.len() != 256 * (states - 1) {
+            return Err(TableError::BadLength { transitions: self.next.len(), rows: states - 1, stride: 256 })
+        }
+        if let Some(i) = self.next.iter().position(|&dest| dest.to_usize() >= states) {
+            return Err(TableError::TransitionOutOfRange { state: i / 256, column: i % 256, dest: self.next[i].to_usize() })
+        }
+        check_classes(&self.classes, self.class_count)
+    }
+
+    /// Same as `validate`, but also fails if no accepting state is reachable
+    /// from the start, as for a table that can never make a token.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first fault found, if any.
+    pub fn validate_accepting(&self) -> Result<(), TableError> {
+        self.validate().and_then(|()| check_accepting(self))
+    }
+}
+
+impl StaticLexTable {
+    /// Checks that `self` is well-formed, as `NaiveLexTable::validate` does,
+    /// for tables written by hand or by an older version of the generator:
+    /// that is, also that every byte class is within a row, and that the
+    /// row of the sink leads only back to it.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first fault found, if any.
+    pub fn validate(&self) -> Result<(), TableError> {
+        let states = self.classes.len();
+        if states == 0 {
+            return Err(TableError::NoSink)
+        }
+        let sink = states - 1;
+        if self.classes[sink].is_some() {
+            return Err(TableError::AcceptingSink)
+        }
+        if let Some(byte) = (0..=255).find(|&byte| usize::from(self.remap[usize::from(byte)]) >= self.stride) {
+            return Err(TableError::ByteClassOutOfRange { byte, class: usize::from(self.remap[usize::from(byte)]), stride: self.stride })
+        }
+        if self.next.len() != self.stride * states {
+            return Err(TableError::BadLength { transitions: self.next.len(), rows: states, stride: self.stride })
+        }
+        let dest = |i: usize| usize::try_from(self.next[i]).unwrap_or(usize::MAX);
+        if let Some(i) = (0..self.next.len()).find(|&i| dest(i) >= states) {
+            return Err(TableError::TransitionOutOfRange { state: i / self.stride, column: i % self.stride, dest: dest(i) })
+        }
+        if let Some(column) = (0..self.stride).find(|&column| dest(self.stride * sink + column) != sink) {
+            return Err(TableError::SinkNotClosed { column, dest: dest(self.stride * sink + column) })
+        }
+        check_classes(self.classes, self.class_count)
+    }
+
+    /// Same as `validate`, but also fails if no accepting state is reachable
+    /// from the start, as in `NaiveLexTable::validate_accepting`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first fault found, if any.
+    pub fn validate_accepting(&self) -> Result<(), TableError> {
+        self.validate().and_then(|()| check_accepting(self))
+    }
+}
+
+/// Error produced by `NaiveLexTable::validate` and `StaticLexTable::validate`
+/// when a table is malformed.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TableError {
+    /// There are no states, so not even a sink.
+    NoSink,
+    /// The sink, the last state, is accepting.
+    AcceptingSink,
+    /// There is not a row of transitions for each state that needs one.
+    BadLength {
+        /// The number of transitions.
+        transitions: usize,
+        /// The number of rows expected.
+        rows: usize,
+        /// The length of each row.
+        stride: usize,
+    },
+    /// A byte is of a class past the end of a row.
+    ByteClassOutOfRange {
+        /// The byte.
+        byte: u8,
+        /// Its class.
+        class: usize,
+        /// The length of each row.
+        stride: usize,
+    },
+    /// A transition leads to no state.
+    TransitionOutOfRange {
+        /// The source of the transition.
+        state: usize,
+        /// The byte, or byte class, labelling the transition.
+        column: usize,
+        /// The destination.
+        dest: usize,
+    },
+    /// A transition of the sink leads out of it.
+    SinkNotClosed {
+        /// The byte class labelling the transition.
+        column: usize,
+        /// The destination.
+        dest: usize,
+    },
+    /// The class of a state is not below the class count.
+    ClassOutOfRange {
+        /// The state with the class.
+        state: usize,
+        /// The class count.
+        class_count: usize,
+    },
+    /// No accepting state is reachable from the start.
+    NothingAccepted,
+}
+
+impl fmt::Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoSink => write!(f, "table has no sink"),
+            Self::AcceptingSink => write!(f, "sink is accepting"),
+            Self::BadLength { transitions, rows, stride } => write!(f, "{transitions} transitions for {rows} rows of {stride}"),
+            Self::ByteClassOutOfRange { byte, class, stride } => write!(f, "byte {byte} is of class {class}, past rows of {stride}"),
+            Self::TransitionOutOfRange { state, column, dest } => write!(f, "transition {state} -({column})-> {dest} is out of range"),
+            Self::SinkNotClosed { column, dest } => write!(f, "sink leads to {dest} on ({column})"),
+            Self::ClassOutOfRange { state, class_count } => write!(f, "class of state {state} is out of range for {class_count} classes"),
+            Self::NothingAccepted => write!(f, "no accepting state is reachable from the start"),
+        }
+    }
+}
+
+impl std::error::Error for TableError {}
+
+// =================
+// === INTERNALS ===
+// =================
+
+fn check_classes(classes: &[Option<usize>], class_count: usize) -> Result<(), TableError> {
+    match classes.iter().position(|class| class.is_some_and(|class| class >= class_count)) {
+        Some(state) => Err(TableError::ClassOutOfRange { state, class_count }),
+        None => Ok(()),
+    }
+}
+
+/// Searches the states reachable from the start of `table`, which must be
+/// well-formed, for an accepting one.
+fn check_accepting(table: &impl LexTable) -> Result<(), TableError> {
+    let start = table.start_state();
+    let mut seen = HashSet::new();
+    seen.insert(start);
+    let mut stack = vec![start];
+    while let Some(state) = stack.pop() {
+        if table.class(state).is_some() {
+            return Ok(())
+        }
+        if state == table.sink() {
+            continue;
+        }
+        for symbol in 0..=255 {
+            let next = table.step(state, symbol);
+            if seen.insert(next) {
+                stack.push(next);
+            }
+        }
+    }
+    Err(TableError::NothingAccepted)
+}
@@ -1,8 +1,24 @@
 use super::RegEx;
+use super::DerivCache;
 use super::ByteSet;
-use super::DFA;
-use super::NaiveLexTable;
+use super::{DFA, BuildLimits, BuildError};
+use super::{LexTable, NaiveLexTable, CompressedLexTable, ClassLexTable, SparseLexTable, Command, build_table};
+use super::{tables_equivalent, DfaRef, StaticLexTable, TableError};
+use super::{LazyDFA, Eviction};
 use super::Scan;
+use super::NFA;
+use super::Token;
+use super::Operator;
+
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::mem::size_of;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use rand_core::{RngCore, SeedableRng};
+use rand_pcg::Pcg32;
 
 #[test]
 fn approx_eq() {
@@ -11,6 +27,34 @@ fn approx_eq() {
     assert_ne!(re1, re2);
 }
 
+#[test]
+fn complemented_sets() {
+    let set = ByteSet::range(3, 17);
+    let any = RegEx::set(ByteSet::universe());
+
+    // the complement of a set also contains epsilon and all longer strings,
+    // so differs from the set complement; restricted to single bytes, they
+    // are the same
+    let re1 = RegEx::set(set.complement());
+    let re2 = RegEx::set(set.clone()).not();
+    assert!(!re1.equivalent(&re2));
+    assert_eq!(re1, re2.and(&any));
+    assert_eq!(re1, any.and(&re2));
+    for a in 0..=255 {
+        assert_eq!(re1.deriv(a), re2.and(&any).deriv(a));
+    }
+    assert_eq!(DFA::from(&re1).states().len(), DFA::from(&re2.and(&any)).states().len());
+
+    let x = ByteSet::range(10, 30);
+    let not_x = RegEx::set(x.clone()).not();
+    assert_eq!(re2.and(&not_x), RegEx::set(ByteSet::range(3, 30)).not());
+    assert_eq!(re2.or(&not_x), RegEx::set(ByteSet::range(10, 17)).not());
+    assert_eq!(re2.or(&RegEx::set(x.clone())), RegEx::set(ByteSet::range(3, 9)).not());
+    assert_eq!(re2.and(&RegEx::set(x)), RegEx::set(ByteSet::range(18, 30)));
+    assert_eq!(re2.and(&RegEx::set(set.clone())), RegEx::none());
+    assert_eq!(re2.or(&RegEx::set(set)), RegEx::none().not());
+}
+
 #[test]
 fn derivative() {
     let set1 = RegEx::set(ByteSet::range(0, 16));
@@ -23,15 +67,16 @@ fn derivative() {
 
 #[test]
 fn simple_lexer() {
-    let table = NaiveLexTable::new(&DFA::from(&[
+    let dfa = DFA::from(&[
         RegEx::set(ByteSet::point(b' ').union(&ByteSet::point(b','))).plus(),
         // RegEx::set(ByteSet::range('A' as u8, 'Z' as u8)),
         RegEx::set(ByteSet::range(b'a', b'z')).plus(),
-    ]).minimize());
+    ]).minimize();
+    let table = NaiveLexTable::with_commands(&dfa, &[Command::Skip]);
     let text = "waltz, bad nymph, for quick jigs vex";
 
-    let tokens: Vec<_> = Scan::new(&table, &text).take(14).collect::<Result<_, _>>().unwrap();
-    let tokens: Vec<_> = tokens.into_iter().filter(|token| token.class != 0).collect();
+    let tokens: Vec<_> = Scan::new(&table, &text).collect::<Result<_, _>>().unwrap();
+    assert_eq!(tokens.len(), 7);
 
     assert_eq!(&text[tokens[0].span.clone()], "waltz");
     assert_eq!(&text[tokens[1].span.clone()], "bad");
@@ -41,6 +86,438 @@ fn simple_lexer() {
     assert_eq!(&text[tokens[5].span.clone()], "jigs");
     assert_eq!(&text[tokens[6].span.clone()], "vex");
     assert!(tokens.iter().all(|token| token.class == 1));
+
+    let table = ClassLexTable::with_commands(&dfa, &[Command::Skip]);
+    assert_eq!(Scan::new(&table, &text).collect::<Result<Vec<_>, _>>().unwrap(), tokens);
+    let table = CompressedLexTable::from(NaiveLexTable::with_commands(&dfa, &[Command::Skip]));
+    assert_eq!(Scan::new(&table, &text).collect::<Result<Vec<_>, _>>().unwrap(), tokens);
+}
+
+#[test]
+fn lexer_from_rules() {
+    let rules = [
+        RegEx::set(ByteSet::ASCII_ALPHA).then(&RegEx::set(ByteSet::WORD).star()),
+        RegEx::set(ByteSet::ASCII_DIGIT).plus(),
+        RegEx::set(ByteSet::ASCII_WHITESPACE).plus(),
+    ];
+    let expected = NaiveLexTable::new(&DFA::from(&rules).minimize());
+    let table = NaiveLexTable::from_rules(&rules);
+    assert_eq!(table.next, expected.next);
+    assert_eq!(table.classes, expected.classes);
+    let table = NaiveLexTable::try_from_rules(&rules, &BuildLimits::default()).ok().unwrap();
+    assert_eq!(table.next, expected.next);
+
+    let limits = BuildLimits { max_states: 2, ..BuildLimits::default() };
+    assert!(matches!(NaiveLexTable::try_from_rules(&rules, &limits), Err(BuildError::StateLimitExceeded { .. })));
+
+    let commands = [Command::Emit, Command::Emit, Command::Skip];
+    let table = NaiveLexTable::from_rules_with_commands(&[
+        (rules[0].clone(), commands[0]), (rules[1].clone(), commands[1]), (rules[2].clone(), commands[2]),
+    ]);
+    assert_eq!(table.next, expected.next);
+    assert_eq!(table.commands, commands);
+}
+
+/// A naive table with an EOF class after its own.
+struct WithEof(NaiveLexTable);
+
+impl LexTable for WithEof {
+    fn step(&self, state: usize, symbol: u8) -> usize { self.0.step(state, symbol) }
+    fn class(&self, state: usize) -> Option<usize> { self.0.class(state) }
+    fn sink(&self) -> usize { self.0.sink() }
+    fn class_count(&self) -> usize { self.0.class_count() + 1 }
+    fn command(&self, class: usize) -> Command { self.0.command(class) }
+    fn eof_class(&self) -> Option<usize> { Some(self.0.class_count()) }
+}
+
+#[test]
+fn lexer_eof() {
+    fn tokens<S: LexTable>(scan: Scan<'_, S>) -> Vec<Result<Token, ()>> {
+        scan.map(|token| token.map_err(|_| ())).collect()
+    }
+
+    let word = RegEx::set(ByteSet::ASCII_ALPHA).plus();
+    let space = RegEx::set(ByteSet::point(b' ')).plus();
+    let table = NaiveLexTable::from_rules_with_commands(&[(word, Command::Emit), (space, Command::Skip)]);
+    let token = |class, span| Ok(Token { class, span });
+
+    assert_eq!(tokens(Scan::new(&table, "a b")), [token(0, 0..1), token(0, 2..3)]);
+    assert_eq!(tokens(Scan::new(&table, "a b").with_eof_class(2)), [token(0, 0..1), token(0, 2..3), token(2, 3..3)]);
+    assert_eq!(tokens(Scan::new(&table, "").with_eof_class(2)), [token(2, 0..0)]);
+    assert_eq!(tokens(Scan::new(&table, "a ?").with_eof_class(2)), [token(0, 0..1), Err(())]);
+    assert_eq!(tokens(Scan::new(&table, "a ?").with_eof_class(2).with_eof_after_error()), [token(0, 0..1), Err(()), token(2, 3..3)]);
+
+    let table = WithEof(table);
+    assert_eq!(tokens(Scan::new(&table, "a b")), [token(0, 0..1), token(0, 2..3), token(2, 3..3)]);
+    assert_eq!(tokens(Scan::new(&table, "a b").with_eof_class(5)).last(), Some(&token(5, 3..3)));
+}
+
+#[test]
+fn lexer_trait_objects() {
+    let word = RegEx::set(ByteSet::ASCII_ALPHA).plus();
+    let space = RegEx::set(ByteSet::point(b' ')).plus();
+    let dfa = DFA::from(&[word, space]).minimize();
+    let text = "hi  there x";
+    let spans = |scan: &mut dyn Iterator<Item = Result<Token, _>>| scan.map(|token| token.unwrap().span).collect::<Vec<_>>();
+    let expected = [0..2, 4..9, 10..11];
+
+    // skipping spaces, to check that overridden methods are forwarded
+    let table = NaiveLexTable::with_commands(&dfa, &[Command::Emit, Command::Skip]);
+    let boxed: Box<dyn LexTable> = Box::new(table);
+    assert_eq!(spans(&mut Scan::new(&boxed, text)), expected);
+    assert_eq!(spans(&mut Scan::new(&*boxed, text)), expected);
+    assert_eq!(spans(&mut Scan::new(&&*boxed, text)), expected);
+    let rc = Rc::new(NaiveLexTable::with_commands(&dfa, &[Command::Emit, Command::Skip]));
+    assert_eq!(spans(&mut Scan::new(&rc, text)), expected);
+    let arc = Arc::new(ClassLexTable::with_commands(&dfa, &[Command::Emit, Command::Skip]));
+    assert_eq!(spans(&mut Scan::new(&arc, text)), expected);
+
+    // tables of different start states behind one type
+    let tables: Vec<Box<dyn LexTable>> = vec![Box::new(NaiveLexTable::new(&dfa)), Box::new(dfa.to_dense()), Box::new(dfa)];
+    for table in &tables {
+        assert_eq!(spans(&mut Scan::new(table, text)), [0..2, 2..4, 4..9, 9..10, 10..11]);
+    }
+}
+
+#[test]
+fn sparse_lexer() {
+    let keyword = |s: &[u8]| s.iter().fold(RegEx::empty(), |re, &c| re.then(&RegEx::set(ByteSet::point(c))));
+    let ident = RegEx::set(ByteSet::ASCII_ALPHA).then(&RegEx::set(ByteSet::WORD).star());
+    let space = RegEx::set(ByteSet::ASCII_WHITESPACE).plus();
+
+    // many states, each leading to a few others: a synthetic sparse DFA
+    let syllables: [&[u8]; 8] = [b"ka", b"lo", b"mi", b"nu", b"pe", b"ra", b"si", b"to"];
+    let mut rules = Vec::new();
+    for a in &syllables {
+        for b in &syllables {
+            for c in &syllables {
+                rules.push(keyword(&[*a, *b, *c].concat()));
+            }
+        }
+    }
+    rules.extend([ident.clone(), space.clone()]);
+    let keywords = DFA::from(&rules).minimize();
+
+    let dfas = [
+        keywords,
+        DFA::from(&[ident, RegEx::set(ByteSet::ASCII_DIGIT).plus(), space]).minimize(),
+        DFA::from(&RegEx::none()),
+    ];
+    for dfa in &dfas {
+        let naive = NaiveLexTable::new(dfa);
+        let sparse = SparseLexTable::new(dfa);
+        assert_eq!(sparse.sink(), naive.sink());
+        assert_eq!(sparse.class_count(), naive.class_count());
+        for state in 0..naive.sink() {
+            assert_eq!(sparse.class(state), naive.class(state));
+            for symbol in 0..=255 {
+                assert_eq!(sparse.step(state, symbol), naive.step(state, symbol));
+            }
+        }
+        assert_eq!(sparse.step(sparse.sink(), b'a'), sparse.sink());
+        assert_eq!(tables_equivalent(&naive, &sparse), Ok(()));
+
+        let built = build_table(dfa);
+        for text in &["kalomi kalomix 42 x1", "sitoka  ", "", "?"] {
+            let expected: Vec<_> = Scan::new(&naive, text).map(|token| token.map_err(|_| ())).collect();
+            assert_eq!(Scan::new(&sparse, text).map(|token| token.map_err(|_| ())).collect::<Vec<_>>(), expected);
+            assert_eq!(Scan::new(&built, text).map(|token| token.map_err(|_| ())).collect::<Vec<_>>(), expected);
+        }
+    }
+
+    let naive = NaiveLexTable::new(&dfas[0]);
+    let sparse = SparseLexTable::new(&dfas[0]);
+    assert!(naive.sink() > 500);
+    // each keyword state leads to the next, to an identifier, or to the sink
+    assert!(sparse.runs() < 12 * (naive.sink() + 1));
+    assert!(8 * sparse.memory_usage() < naive.memory_usage());
+}
+
+#[test]
+fn equivalent_tables() {
+    let keyword = |s: &[u8]| s.iter().fold(RegEx::empty(), |re, &c| re.then(&RegEx::set(ByteSet::point(c))));
+    let rules = [
+        keyword(b"if"),
+        keyword(b"while"),
+        RegEx::set(ByteSet::ASCII_ALPHA).then(&RegEx::set(ByteSet::WORD).star()),
+        RegEx::set(ByteSet::ASCII_DIGIT).plus(),
+        RegEx::set(ByteSet::ASCII_WHITESPACE).plus(),
+        keyword(b"=="),
+    ];
+    let dfa = DFA::from(&rules);
+    let minimal = dfa.minimize();
+    let bytes = minimal.to_bytes();
+    let tables: Vec<Box<dyn LexTable>> = vec![
+        Box::new(NaiveLexTable::new(&minimal)),
+        Box::new(NaiveLexTable::<u16>::from_dfa(&dfa).unwrap()),
+        Box::new(CompressedLexTable::new(&minimal)),
+        Box::new(ClassLexTable::new(&dfa)),
+        Box::new(SparseLexTable::new(&minimal)),
+        Box::new(minimal.to_dense()),
+        Box::new(DfaRef::from_bytes(&bytes).unwrap()),
+        Box::new(LazyDFA::new(&rules)),
+        build_table(&dfa),
+    ];
+    for table in &tables {
+        assert_eq!(tables_equivalent(&dfa, table), Ok(()));
+        assert_eq!(tables_equivalent(table, &minimal), Ok(()));
+    }
+
+    // without the keyword, which is then an identifier
+    let mut fewer = rules.clone();
+    fewer[1] = RegEx::none();
+    let err = tables_equivalent(&tables[0], &NaiveLexTable::from_rules(&fewer)).unwrap_err();
+    assert_eq!(err.input(), b"while");
+    assert_eq!(err.classes(), (Some(1), Some(2)));
+    assert_eq!(err.to_string(), "tables differ on input \"while\": class Some(1) against Some(2)");
+
+    let skipping = NaiveLexTable::with_commands(&minimal, &[Command::Emit, Command::Emit, Command::Emit, Command::Emit, Command::Skip]);
+    let err = tables_equivalent(&tables[0], &skipping).unwrap_err();
+    assert_eq!(err.input(), b"\t");
+    assert_eq!(err.classes(), (Some(4), Some(4)));
+    assert_eq!(err.to_string(), "tables differ on input \"\\t\": tokens of class 4 are handled by Emit against Skip");
+}
+
+/// Counts the tokens of each class in `text`, for any table.
+fn class_histogram<T: LexTable>(table: &T, text: &str) -> Vec<usize> {
+    let mut counts = vec![0; table.class_count()];
+    for token in Scan::new(table, text) {
+        counts[token.unwrap().class] += 1;
+    }
+    counts
+}
+
+#[test]
+fn lexer_class_names() {
+    let rules = [
+        RegEx::set(ByteSet::ASCII_ALPHA).then(&RegEx::set(ByteSet::WORD).star()),
+        RegEx::set(ByteSet::ASCII_DIGIT).plus(),
+        RegEx::set(ByteSet::ASCII_WHITESPACE).plus(),
+    ];
+    let dfa = DFA::from(&rules).minimize();
+    let text = "x1 42 y 7";
+
+    let table = NaiveLexTable::new(&dfa);
+    assert_eq!(table.class_count(), 3);
+    assert_eq!(table.class_name(0), None);
+    assert_eq!(class_histogram(&table, text), [2, 2, 3]);
+    assert_eq!(class_histogram(&dfa, text), [2, 2, 3]);
+    assert_eq!(class_histogram(&dfa.to_dense(), text), [2, 2, 3]);
+    assert_eq!(class_histogram(&LazyDFA::new(&rules), text), [2, 2, 3]);
+
+    let names = ["IDENT", "NUMBER", "SPACE"];
+    let table = NaiveLexTable::new(&dfa).with_class_names(&names);
+    let token = Scan::new(&table, text).nth(2).unwrap().unwrap();
+    assert_eq!(table.class_name(token.class), Some("NUMBER"));
+    assert_eq!(table.class_name(3), None);
+    let class = ClassLexTable::new(&dfa).with_class_names(&names);
+    let compressed = CompressedLexTable::from(NaiveLexTable::new(&dfa).with_class_names(&names));
+    for (i, &name) in names.iter().enumerate() {
+        assert_eq!(class.class_name(i), Some(name));
+        assert_eq!(compressed.class_name(i), Some(name));
+    }
+    assert_eq!(class_histogram(&class, text), [2, 2, 3]);
+
+    // a DFA accepting nothing still has the class of its rule
+    assert_eq!(NaiveLexTable::new(&DFA::from(&RegEx::none())).class_count(), 1);
+}
+
+#[test]
+fn lexer_shadowed_last_class() {
+    // no token is ever of the last class, but it is counted all the same
+    let ident = RegEx::set(ByteSet::ASCII_LOWER).plus();
+    let rules = [ident, RegEx::set(ByteSet::point(b'i')).then(&RegEx::set(ByteSet::point(b'f')))];
+    let dfa = DFA::from(&rules);
+    let names = ["IDENT", "IF"];
+    let commands = [Command::Emit, Command::Skip];
+    let bytes = dfa.to_bytes();
+    let tables: Vec<Box<dyn LexTable>> = vec![
+        Box::new(DFA::from(&rules)),
+        Box::new(dfa.minimize()),
+        Box::new(dfa.to_dense()),
+        Box::new(DfaRef::from_bytes(&bytes).unwrap()),
+        Box::new(LazyDFA::new(&rules)),
+        Box::new(NaiveLexTable::new(&dfa)),
+        Box::new(CompressedLexTable::from(NaiveLexTable::new(&dfa))),
+        Box::new(ClassLexTable::new(&dfa)),
+        Box::new(SparseLexTable::new(&dfa)),
+        build_table(&dfa),
+    ];
+    for table in &tables {
+        assert_eq!(table.class_count(), 2);
+        assert_eq!(class_histogram(table, "if"), [1, 0]);
+    }
+
+    let table = NaiveLexTable::with_commands(&dfa, &commands).with_class_names(&names);
+    assert_eq!((table.class_name(1), table.command(1)), (Some("IF"), Command::Skip));
+    assert_eq!(table.validate(), Ok(()));
+    let class = ClassLexTable::with_commands(&dfa, &commands).with_class_names(&names);
+    assert_eq!((class.class_name(1), class.command(1)), (Some("IF"), Command::Skip));
+    let sparse = SparseLexTable::with_commands(&dfa, &commands).with_class_names(&names);
+    assert_eq!((sparse.class_name(1), sparse.command(1)), (Some("IF"), Command::Skip));
+
+    let (mut a, mut b) = (Vec::new(), Vec::new());
+    table.write_rust(&mut a, "LEX").unwrap();
+    class.write_rust(&mut b, "LEX").unwrap();
+    for source in [a, b] {
+        let source = String::from_utf8(source).unwrap();
+        assert!(source.contains("    class_count: 2,\n    class_names: &[\"IDENT\", \"IF\"],\n"), "{}", source);
+    }
+}
+
+#[test]
+fn lexer_memory_usage() {
+    let rules = [
+        RegEx::set(ByteSet::ASCII_ALPHA).then(&RegEx::set(ByteSet::WORD).star()),
+        RegEx::set(ByteSet::ASCII_DIGIT).plus(),
+        RegEx::set(ByteSet::ASCII_WHITESPACE).plus(),
+    ];
+    let dfa = DFA::from(&rules).minimize();
+
+    let table = NaiveLexTable::<usize>::from_dfa(&dfa).unwrap();
+    assert_eq!(table.memory_usage(), table.next.len() * size_of::<usize>() + table.classes.len() * size_of::<Option<usize>>());
+    let table = NaiveLexTable::new(&dfa);
+    assert_eq!(table.memory_usage(), table.next.len() * size_of::<u32>() + table.classes.len() * size_of::<Option<usize>>());
+    let named = NaiveLexTable::new(&dfa).with_class_names(&["IDENT", "NUMBER"]);
+    assert_eq!(named.memory_usage(), table.memory_usage() + 2 * size_of::<String>() + 11);
+
+    // the dense representations, from largest to smallest
+    let sizes = [dfa.to_dense().memory_usage(), table.memory_usage(), ClassLexTable::new(&dfa).memory_usage()];
+    assert!(sizes.windows(2).all(|pair| pair[0] > pair[1]), "{:?}", sizes);
+    // of u32 entries, padded past the last row
+    let compressed = CompressedLexTable::new(&dfa);
+    assert!(compressed.memory_usage() >= 2 * compressed.entries() * size_of::<u32>());
+    assert!(compressed.memory_usage() < table.memory_usage());
+    let narrow = CompressedLexTable::from(NaiveLexTable::<u16>::from_dfa(&dfa).unwrap());
+    assert!(narrow.memory_usage() < compressed.memory_usage());
+    assert!(dfa.memory_usage() < table.memory_usage());
+    assert!(dfa.memory_usage() >= std::mem::size_of_val(dfa.states()));
+}
+
+#[test]
+fn reproducible_lexer() {
+    let keyword = |s: &[u8]| s.iter().fold(RegEx::empty(), |re, &c| re.then(&RegEx::set(ByteSet::point(c))));
+    let build = || {
+        let rules = [
+            keyword(b"if"),
+            keyword(b"else"),
+            keyword(b"while"),
+            RegEx::set(ByteSet::ASCII_ALPHA).then(&RegEx::set(ByteSet::WORD).star()),
+            RegEx::set(ByteSet::ASCII_DIGIT).plus(),
+            RegEx::set(ByteSet::ASCII_WHITESPACE).plus(),
+        ];
+        NaiveLexTable::new(&DFA::from(&rules).minimize())
+    };
+
+    let (table1, table2) = (build(), build());
+    assert_eq!(table1.next, table2.next);
+    assert_eq!(table1.classes, table2.classes);
+}
+
+#[test]
+fn prioritized_lexer() {
+    let keyword = |s: &[u8]| s.iter().fold(RegEx::empty(), |re, &c| re.then(&RegEx::set(ByteSet::point(c))));
+    let rules = [
+        (keyword(b"if"), 1),
+        (keyword(b"else"), 1),
+        (keyword(b"while"), 1),
+        (RegEx::set(ByteSet::ASCII_ALPHA).then(&RegEx::set(ByteSet::WORD).star()), 0),
+        (RegEx::set(ByteSet::ASCII_DIGIT).plus(), 0),
+        (RegEx::set(ByteSet::ASCII_WHITESPACE).plus(), 0),
+    ];
+    let text = "while x1 if 12 else iffy elsewhere whil 3 if";
+
+    // tokens with their class mapped back to the index of the rule in rules
+    let scan = |order: &[usize]| -> Vec<Token> {
+        let reordered: Vec<_> = order.iter().map(|&i| rules[i].clone()).collect();
+        let table = NaiveLexTable::new(&DFA::from_rules(&reordered).minimize());
+        Scan::new(&table, text)
+            .map(|token| token.map(|token| Token { class: order[token.class], ..token }).unwrap())
+            .collect()
+    };
+
+    let expected = scan(&[0, 1, 2, 3, 4, 5]);
+    assert_eq!(expected.iter().filter(|token| token.class < 3).count(), 4);
+    assert_eq!(expected.iter().filter(|token| token.class == 3).count(), 4);
+    assert_eq!(scan(&[5, 4, 3, 2, 1, 0]), expected);
+    assert_eq!(scan(&[3, 0, 5, 1, 4, 2]), expected);
+
+    // with equal priorities, order decides
+    let plain: Vec<_> = rules.iter().map(|(regex, _)| regex.clone()).collect();
+    let equal: Vec<_> = plain.iter().map(|regex| (regex.clone(), 7)).collect();
+    assert_eq!(DFA::from_rules(&equal).to_string(), DFA::from(&plain).to_string());
+}
+
+#[test]
+fn shadowed_rules() {
+    let keyword = |s: &[u8]| s.iter().fold(RegEx::empty(), |re, &c| re.then(&RegEx::set(ByteSet::point(c))));
+    let ident  = RegEx::set(ByteSet::ASCII_ALPHA).then(&RegEx::set(ByteSet::WORD).star());
+    let number = RegEx::set(ByteSet::ASCII_DIGIT).plus();
+    let space  = RegEx::set(ByteSet::ASCII_WHITESPACE).plus();
+
+    // the identifier rule before the keywords claims all of them, and
+    // "0" is claimed by the number rule, while "0x" only partly overlaps it
+    let rules = [
+        ident.clone(),
+        keyword(b"if"),
+        keyword(b"else"),
+        number.clone(),
+        keyword(b"0"),
+        keyword(b"0x"),
+        space.clone(),
+    ];
+    for dfa in &[DFA::from(&rules), DFA::from(&rules).minimize()] {
+        assert_eq!(dfa.unreachable_classes(), [1, 2, 4]);
+        let err = NaiveLexTable::try_new(dfa).err().unwrap();
+        assert_eq!(err.classes(), [1, 2, 4]);
+        assert_eq!(err.to_string(), "rules of classes 1, 2, 4 never match: they are shadowed by rules of higher priority, or match nothing");
+    }
+
+    // keywords first, or given priority, are fine
+    let rules = [keyword(b"if"), keyword(b"else"), ident.clone(), number.clone(), space.clone()];
+    assert!(NaiveLexTable::try_new(&DFA::from(&rules).minimize()).is_ok());
+    let rules = [(ident.clone(), 0), (keyword(b"if"), 1), (keyword(b"else"), 1), (number.clone(), 0)];
+    assert_eq!(DFA::from_rules(&rules).unreachable_classes(), []);
+
+    // a rule matching nothing is reported, wherever it comes
+    assert_eq!(DFA::from(&[number.clone(), RegEx::none(), space.clone()]).unreachable_classes(), [1]);
+    assert_eq!(DFA::from(&[number, space, RegEx::none()]).unreachable_classes(), [2]);
+}
+
+#[test]
+fn lazy_lexer() {
+    let keyword = |s: &[u8]| s.iter().fold(RegEx::empty(), |re, &c| re.then(&RegEx::set(ByteSet::point(c))));
+    let lexers = [
+        (vec![
+            RegEx::set(ByteSet::point(b' ').union(&ByteSet::point(b','))).plus(),
+            RegEx::set(ByteSet::range(b'a', b'z')).plus(),
+        ], "waltz, bad nymph, for quick jigs vex"),
+        (vec![
+            keyword(b"if"),
+            keyword(b"else"),
+            keyword(b"while"),
+            RegEx::set(ByteSet::ASCII_ALPHA).then(&RegEx::set(ByteSet::WORD).star()),
+            RegEx::set(ByteSet::ASCII_DIGIT).plus(),
+            RegEx::set(ByteSet::ASCII_WHITESPACE).plus(),
+        ], "while x1 if 12 else iffy elsewhere whil 3 x?"),
+        (vec![RegEx::set(ByteSet::range(b'A', b'Z'))], "bad"),
+    ];
+
+    for (rules, text) in &lexers {
+        let table = NaiveLexTable::new(&DFA::from(rules).minimize());
+        let expected: Vec<_> = Scan::new(&table, text).map(|token| token.map_err(|_| ())).collect();
+        for lazy in &[
+            LazyDFA::new(rules),
+            LazyDFA::builder().state_limit(3).build(rules),
+            LazyDFA::builder().state_limit(3).eviction(Eviction::Flush).build(rules),
+        ] {
+            // twice over, with the states from the first scan cached
+            for _ in 0..2 {
+                let tokens: Vec<_> = Scan::new(lazy, text).map(|token| token.map_err(|_| ())).collect();
+                assert_eq!(tokens, expected);
+            }
+        }
+    }
 }
 
 #[test]
@@ -51,4 +528,908 @@ fn fail_lexer() {
     let text = "bad";
     let res = Scan::new(&table, &text).next().unwrap();
     assert!(res.is_err());
-}
\ No newline at end of file
+}
+
+#[test]
+fn compressed_lexer() {
+    let keyword = |s: &[u8]| s.iter().fold(RegEx::empty(), |re, &c| re.then(&RegEx::set(ByteSet::point(c))));
+    let rules = [
+        keyword(b"if"),
+        keyword(b"else"),
+        keyword(b"while"),
+        RegEx::set(ByteSet::ASCII_ALPHA).then(&RegEx::set(ByteSet::WORD).star()),
+        RegEx::set(ByteSet::ASCII_DIGIT).plus(),
+        RegEx::set(ByteSet::ASCII_WHITESPACE).plus(),
+        keyword(b"=="),
+        keyword(b"="),
+    ];
+    let dfa = DFA::from(&rules).minimize();
+    let naive = NaiveLexTable::new(&dfa);
+    let compressed = CompressedLexTable::new(&dfa);
+    println!(
+        "{} states: {} entries naive, {} compressed",
+        dfa.states().len(), 256 * (dfa.states().len() - 1), compressed.entries(),
+    );
+    assert!(compressed.entries() < 256 * (dfa.states().len() - 1) / 3);
+    assert!(compressed.memory_usage() < naive.memory_usage());
+
+    assert_eq!(compressed.sink(), naive.sink());
+    for state in 0..=naive.sink() {
+        assert_eq!(compressed.class(state), naive.class(state));
+        // the naive table has no row for its sink
+        if state != naive.sink() {
+            for symbol in 0..=255 {
+                assert_eq!(compressed.step(state, symbol), naive.step(state, symbol));
+            }
+        }
+    }
+
+    let mut rng = Pcg32::seed_from_u64(1117);
+    let alphabet = b"aefhilsw019 =\t!";
+    for _ in 0..200 {
+        let len = rng.next_u32() % 40;
+        let text: Vec<u8> = (0..len).map(|_| alphabet[rng.next_u32() as usize % alphabet.len()]).collect();
+        let a: Vec<_> = Scan::new(&naive, &text).map(|token| token.map_err(|_| ())).collect();
+        let b: Vec<_> = Scan::new(&compressed, &text).map(|token| token.map_err(|_| ())).collect();
+        assert_eq!(a, b);
+    }
+    assert_eq!(tables_equivalent(&naive, &compressed), Ok(()));
+}
+
+#[test]
+fn class_lexer() {
+    let keyword = |s: &[u8]| s.iter().fold(RegEx::empty(), |re, &c| re.then(&RegEx::set(ByteSet::point(c))));
+    let rules = [
+        keyword(b"if"),
+        keyword(b"while"),
+        RegEx::set(ByteSet::ASCII_ALPHA).then(&RegEx::set(ByteSet::WORD).star()),
+        RegEx::set(ByteSet::ASCII_DIGIT).plus(),
+        RegEx::set(ByteSet::ASCII_WHITESPACE).plus(),
+        keyword(b"=="),
+    ];
+    for dfa in &[DFA::from(&rules), DFA::from(&rules).minimize()] {
+        let table = ClassLexTable::new(dfa);
+        assert_eq!(table.byte_class_count(), dfa.byte_classes().1);
+
+        // rows are numbered as the states of the DFA, less one, with the
+        // sink last
+        let row = |id: usize| if id == 0 { table.sink() } else { id - 1 };
+        assert_eq!(table.sink(), dfa.states().len() - 1);
+        for id in 0..dfa.states().len() {
+            assert_eq!(table.class(row(id)), dfa.class(id));
+            for symbol in 0..=255 {
+                assert_eq!(table.step(row(id), symbol), row(dfa.step(id, symbol)));
+            }
+        }
+
+        let naive = NaiveLexTable::new(dfa);
+        let text = "while x1 == 42 if\tiff ==";
+        let a: Vec<_> = Scan::new(&naive, text).collect::<Result<_, _>>().unwrap();
+        let b: Vec<_> = Scan::new(&table, text).collect::<Result<_, _>>().unwrap();
+        assert_eq!(a, b);
+        assert_eq!(tables_equivalent(&naive, &table), Ok(()));
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_lexer() {
+    let rules = [
+        RegEx::set(ByteSet::ASCII_ALPHA).then(&RegEx::set(ByteSet::WORD).star()),
+        RegEx::set(ByteSet::ASCII_DIGIT).plus(),
+        RegEx::set(ByteSet::ASCII_WHITESPACE).plus(),
+    ];
+    for dfa in &[DFA::from(&rules).minimize(), DFA::from(&RegEx::none())] {
+        let table = NaiveLexTable::with_commands(dfa, &[Command::Emit, Command::Emit, Command::Skip]).with_class_names(&["IDENT", "NUMBER"]);
+        let bytes = bincode::serialize(&table).unwrap();
+        let copy: NaiveLexTable = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(copy.next, table.next);
+        assert_eq!(copy.classes, table.classes);
+        assert_eq!(copy.commands, table.commands);
+        assert_eq!(copy.class_count(), table.class_count());
+        assert_eq!(copy.class_name(1), Some("NUMBER"));
+        assert_eq!(bincode::serialize(&copy).unwrap(), bytes);
+        assert_eq!(tables_equivalent(&table, &copy), Ok(()));
+        let a: Vec<_> = Scan::new(&table, "x1 42 y").map(|token| token.map_err(|_| ())).collect();
+        let b: Vec<_> = Scan::new(&copy, "x1 42 y").map(|token| token.map_err(|_| ())).collect();
+        assert_eq!(a, b);
+    }
+
+    // as serialized by serde, but perhaps corrupted
+    let valid = |next: Vec<usize>, classes: Vec<Option<usize>>| {
+        let bytes = bincode::serialize(&(3_u32, next, classes, 1_usize, Vec::<String>::new(), Vec::<Command>::new())).unwrap();
+        bincode::deserialize::<NaiveLexTable>(&bytes).map(|_| ()).map_err(|e| e.to_string())
+    };
+    assert_eq!(valid(vec![1; 256], vec![Some(0), None]), Ok(()));
+    assert_eq!(valid(Vec::new(), vec![None]), Ok(()));
+    assert_eq!(valid(vec![1; 255], vec![Some(0), None]).unwrap_err(), "255 transitions for 1 rows of 256");
+    assert_eq!(valid(vec![1; 512], vec![Some(0), None]).unwrap_err(), "512 transitions for 1 rows of 256");
+    let mut next = vec![1; 256];
+    next[97] = 2;
+    assert_eq!(valid(next, vec![Some(0), None]).unwrap_err(), "transition 0 -(97)-> 2 is out of range");
+    assert_eq!(valid(Vec::new(), Vec::new()).unwrap_err(), "table has no sink");
+    assert_eq!(valid(vec![1; 256], vec![None, Some(0)]).unwrap_err(), "sink is accepting");
+    assert_eq!(valid(vec![1; 256], vec![Some(1), None]).unwrap_err(), "class of state 0 is out of range for 1 classes");
+
+    let mut bytes = bincode::serialize(&NaiveLexTable::new(&DFA::from(&RegEx::empty()))).unwrap();
+    bytes[0] = 2;
+    assert_eq!(bincode::deserialize::<NaiveLexTable>(&bytes).map(|_| ()).unwrap_err().to_string(), "unsupported table format version 2");
+
+    // a shadowed last class survives the round trip
+    let table = NaiveLexTable::new(&DFA::from(&[RegEx::set(ByteSet::ASCII_LOWER).plus(), RegEx::set(ByteSet::point(b'x'))]));
+    let copy: NaiveLexTable = bincode::deserialize(&bincode::serialize(&table).unwrap()).unwrap();
+    assert_eq!(copy.class_count(), 2);
+
+    let int = RegEx::set(ByteSet::ASCII_DIGIT).plus().followed_by(&RegEx::set(ByteSet::point(b'.')));
+    let table = NaiveLexTable::with_trailing_contexts(&DFA::from(&int.regex()), vec![Some(int)]);
+    assert_eq!(bincode::serialize(&table).unwrap_err().to_string(), "trailing contexts cannot be serialized");
+}
+
+#[test]
+fn validate_tables() {
+    let rules = [
+        RegEx::set(ByteSet::ASCII_ALPHA).then(&RegEx::set(ByteSet::WORD).star()),
+        RegEx::set(ByteSet::ASCII_WHITESPACE).plus(),
+    ];
+    let table = NaiveLexTable::new(&DFA::from(&rules).minimize());
+    assert_eq!(table.validate(), Ok(()));
+    assert_eq!(table.validate_accepting(), Ok(()));
+    assert_eq!(NaiveLexTable::new(&DFA::from(&RegEx::none())).validate(), Ok(()));
+    assert_eq!(NaiveLexTable::new(&DFA::from(&RegEx::none())).validate_accepting(), Err(TableError::NothingAccepted));
+    let sink = table.sink();
+
+    // each corruption of a valid table gives its own error
+    let corrupt = |f: &dyn Fn(&mut NaiveLexTable)| {
+        let mut copy = NaiveLexTable { next: table.next.clone(), classes: table.classes.clone(), class_count: table.class_count, names: Vec::new(), contexts: Vec::new(), commands: Vec::new() };
+        f(&mut copy);
+        copy.validate()
+    };
+    assert_eq!(corrupt(&|t| t.classes.clear()), Err(TableError::NoSink));
+    assert_eq!(corrupt(&|t| t.classes[sink] = Some(0)), Err(TableError::AcceptingSink));
+    assert_eq!(corrupt(&|t| { t.next.pop(); }), Err(TableError::BadLength { transitions: 256 * sink - 1, rows: sink, stride: 256 }));
+    assert_eq!(corrupt(&|t| t.next[256 + 97] = 7), Err(TableError::TransitionOutOfRange { state: 1, column: 97, dest: 7 }));
+    assert_eq!(corrupt(&|t| t.class_count = 1), Err(TableError::ClassOutOfRange { state: 1, class_count: 1 }));
+    assert_eq!(corrupt(&|t| t.class_count = 1).unwrap_err().to_string(), "class of state 1 is out of range for 1 classes");
+
+    // one byte class, an accepting state looping on it, and the sink
+    let valid = StaticLexTable { remap: &[0; 256], stride: 1, next: &[0, 1], classes: &[Some(0), None], class_count: 1, class_names: &[] };
+    assert_eq!(valid.validate(), Ok(()));
+    assert_eq!(valid.validate_accepting(), Ok(()));
+    assert_eq!(StaticLexTable { classes: &[], next: &[], ..valid }.validate(), Err(TableError::NoSink));
+    assert_eq!(StaticLexTable { classes: &[None, Some(0)], ..valid }.validate(), Err(TableError::AcceptingSink));
+    assert_eq!(StaticLexTable { remap: &[1; 256], ..valid }.validate(), Err(TableError::ByteClassOutOfRange { byte: 0, class: 1, stride: 1 }));
+    assert_eq!(StaticLexTable { next: &[0], ..valid }.validate(), Err(TableError::BadLength { transitions: 1, rows: 2, stride: 1 }));
+    assert_eq!(StaticLexTable { next: &[2, 1], ..valid }.validate(), Err(TableError::TransitionOutOfRange { state: 0, column: 0, dest: 2 }));
+    assert_eq!(StaticLexTable { next: &[0, 0], ..valid }.validate(), Err(TableError::SinkNotClosed { column: 0, dest: 0 }));
+    assert_eq!(StaticLexTable { class_count: 0, ..valid }.validate(), Err(TableError::ClassOutOfRange { state: 0, class_count: 0 }));
+    assert_eq!(StaticLexTable { next: &[1, 1], ..valid }.validate_accepting(), Ok(()));
+    assert_eq!(StaticLexTable { next: &[1, 1], classes: &[None, None], ..valid }.validate_accepting(), Err(TableError::NothingAccepted));
+}
+
+#[test]
+fn deriv_cached() {
+    let lower = RegEx::set(ByteSet::range(b'a', b'z'));
+    let digit = RegEx::set(ByteSet::range(b'0', b'9'));
+    let regex = lower.then(&lower.or(&digit).star()).and(&digit.then(&digit).not());
+    let mut cache = DerivCache::new();
+    for a in 0..=255 {
+        assert_eq!(regex.deriv(a), regex.deriv_cached(a, &mut cache));
+        assert_eq!(regex.deriv(a).deriv(b'7'), regex.deriv_cached(a, &mut cache).deriv_cached(b'7', &mut cache));
+    }
+    assert!(!cache.is_empty());
+    for text in ["", "a", "a7", "abc123", "7a", "a_b"] {
+        assert_eq!(regex.is_fullmatch(text), regex.is_fullmatch_cached(text, &mut cache));
+    }
+}
+
+#[test]
+fn sample_strings() {
+    let lower = RegEx::set(ByteSet::range(b'a', b'z'));
+    let digit = RegEx::set(ByteSet::range(b'0', b'9'));
+    let regex = lower.then(&lower.or(&digit).star()).and(&lower.star().then(&digit).not());
+
+    let samples = regex.sample_strings(50, 6, &mut Pcg32::seed_from_u64(17));
+    assert_eq!(samples.len(), 50);
+    for sample in &samples {
+        assert!(sample.len() <= 6);
+        assert!(regex.is_fullmatch(std::str::from_utf8(sample).unwrap()));
+    }
+    assert_eq!(samples, regex.sample_strings(50, 6, &mut Pcg32::seed_from_u64(17)));
+
+    assert!(RegEx::none().sample_strings(10, 10, &mut Pcg32::seed_from_u64(0)).is_empty());
+    assert!(lower.and(&digit.plus()).sample_strings(10, 10, &mut Pcg32::seed_from_u64(0)).is_empty());
+    assert_eq!(digit.star().sample_strings(10, 0, &mut Pcg32::seed_from_u64(0)), vec![Vec::<u8>::new()]);
+    assert_eq!(lower.opt().sample_strings(100, 1, &mut Pcg32::seed_from_u64(0)).len(), 27);
+}
+
+#[test]
+fn shortest_string() {
+    let a = RegEx::set(ByteSet::point(b'a'));
+    let b = RegEx::set(ByteSet::point(b'b'));
+    let digit = RegEx::set(ByteSet::range(b'0', b'9'));
+
+    assert_eq!(a.then(&b).or(&b).shortest_string(), Some(b"b".to_vec()));
+    assert_eq!(digit.then(&a.star()).then(&b).shortest_string(), Some(b"0b".to_vec()));
+    assert_eq!(a.then(&b).shortest_match_len(), Some(2));
+    assert_eq!(a.star().shortest_match_len(), Some(0));
+    assert!(a.star().accepts_empty());
+
+    // Not and And are accounted for
+    assert_eq!(a.not().shortest_string(), Some(vec![]));
+    assert_eq!(RegEx::empty().not().shortest_string(), Some(vec![0]));
+    assert_eq!(a.plus().and(&a.then(&a).plus()).shortest_string(), Some(b"aa".to_vec()));
+    assert_eq!(a.plus().and(&b.plus()).shortest_string(), None);
+    assert_eq!(RegEx::none().shortest_match_len(), None);
+}
+
+#[test]
+fn distinguish() {
+    let a = RegEx::set(ByteSet::point(b'a'));
+    let any = RegEx::set(ByteSet::universe());
+    let digit = RegEx::set(ByteSet::range(b'0', b'9'));
+
+    // differing only on the empty string
+    assert_eq!(a.star().distinguish(&a.plus()), Some(vec![]));
+    assert_eq!(a.plus().distinguish(&a.star()), Some(vec![]));
+
+    // or on 0xFF, or 0x00
+    let almost = RegEx::set(ByteSet::range(0, 0xFE));
+    assert_eq!(any.plus().distinguish(&almost.plus()), Some(vec![0xFF]));
+    assert_eq!(any.distinguish(&RegEx::set(ByteSet::range(1, 0xFF))), Some(vec![0x00]));
+    assert_eq!(any.star().distinguish(&almost.star()), Some(vec![0xFF]));
+
+    // shortest, not just any
+    let long = digit.star().then(&a).then(&digit).then(&digit);
+    assert_eq!(long.distinguish(&long.and(&a.then(&any.star()).not())), Some(b"a00".to_vec()));
+
+    // none for equivalent regexes, however built
+    assert_eq!(a.star().distinguish(&a.plus().opt()), None);
+    assert_eq!(digit.not().not().distinguish(&digit), None);
+    assert!(a.star().equivalent(&a.plus().opt()));
+}
+
+#[test]
+fn finiteness() {
+    let lit = |s: &[u8]| s.iter().fold(RegEx::empty(), |r, &byte| r.then(&RegEx::set(ByteSet::point(byte))));
+    let digit = RegEx::set(ByteSet::range(b'0', b'9'));
+
+    let regex = lit(b"ab").or(&lit(b"xyz"));
+    assert!(regex.is_finite());
+    assert_eq!(regex.longest_match_len(), Some(3));
+
+    assert!(RegEx::empty().is_finite());
+    assert_eq!(RegEx::empty().longest_match_len(), Some(0));
+    assert!(RegEx::none().is_finite());
+    assert_eq!(RegEx::none().longest_match_len(), None);
+
+    assert!(!digit.star().is_finite());
+    assert!(!lit(b"ab").then(&digit.star()).or(&lit(b"xyz")).is_finite());
+    assert_eq!(digit.plus().longest_match_len(), None);
+
+    // the cycle is dead, and must be ignored
+    let regex = digit.star().then(&lit(b"ab")).and(&lit(b"0ab").or(&lit(b"12ab")));
+    assert!(regex.is_finite());
+    assert_eq!(regex.longest_match_len(), Some(4));
+    assert!(lit(b"abc").not().and(&digit.then(&digit)).is_finite());
+}
+
+#[test]
+fn strings_up_to() {
+    let a = RegEx::set(ByteSet::point(b'a'));
+    let b = RegEx::set(ByteSet::point(b'b'));
+    let regex = a.or(&b).star().and(&a.then(&a).not()).and(&RegEx::empty().not());
+
+    let strings: Vec<_> = regex.strings_up_to(3).collect();
+    let mut expected: Vec<Vec<u8>> = Vec::new();
+    for len in 1..=3 {
+        for i in 0..1 << len {
+            let string: Vec<u8> = (0..len).rev().map(|j| if i >> j & 1 == 0 { b'a' } else { b'b' }).collect();
+            if string != b"aa" {
+                expected.push(string);
+            }
+        }
+    }
+    assert_eq!(strings, expected);
+    assert_eq!(regex.count_up_to(3), expected.len() as u64);
+
+    // laziness over a language too large to enumerate
+    let any = RegEx::set(ByteSet::universe()).star();
+    assert_eq!(any.strings_up_to(8).take(3).collect::<Vec<_>>(), [vec![], vec![0], vec![1]]);
+    assert_eq!(any.count_up_to(2), 1 + 256 + 256 * 256);
+    assert_eq!(any.count_up_to(8), u64::MAX);
+
+    assert_eq!(RegEx::none().strings_up_to(4).count(), 0);
+    assert_eq!(RegEx::none().count_up_to(4), 0);
+}
+
+#[test]
+fn count_matches_of_len() {
+    let digit = RegEx::set(ByteSet::range(b'0', b'9'));
+    let zero = RegEx::set(ByteSet::point(b'0'));
+    let number = zero.or(&digit.and(&zero.not()).then(&digit.star()));
+
+    assert_eq!(number.count_matches_of_len(0), 0);
+    assert_eq!(number.count_matches_of_len(1), 10);
+    assert_eq!(number.count_matches_of_len(3), 900);
+    assert_eq!(number.count_up_to(3), 10 + 90 + 900);
+    assert_eq!(RegEx::empty().count_matches_of_len(0), 1);
+    assert_eq!(RegEx::none().count_matches_of_len(0), 0);
+}
+
+#[test]
+fn map_sets() {
+    let upper      = RegEx::set(ByteSet::range(b'A', b'Z'));
+    let lower      = RegEx::set(ByteSet::range(b'a', b'z'));
+    let digit      = RegEx::set(ByteSet::range(b'0', b'9'));
+    let underscore = RegEx::set(ByteSet::point(b'_'));
+
+    let ident = lower.or(&underscore).then(&lower.or(&digit).or(&underscore).star());
+    let folded = ident.map_sets(|set| {
+        set.bytes().fold(set.clone(), |acc, b| acc.union(&ByteSet::point(b.to_ascii_uppercase())))
+    });
+    let expected = lower.or(&upper).or(&underscore).then(&lower.or(&upper).or(&digit).or(&underscore).star());
+
+    for len in 0..=2 {
+        assert_eq!(folded.count_matches_of_len(len), expected.count_matches_of_len(len));
+    }
+    assert!(folded.strings_up_to(2).eq(expected.strings_up_to(2)));
+    assert!(folded.is_fullmatch("Snake_Case9"));
+
+    // identity transforms preserve the tree
+    assert_eq!(ident.map_bytes(Some), ident);
+    let digits = digit.plus().then(&ident);
+    assert_eq!(digits.map_sets(ByteSet::clone), digits);
+
+    // erasing a set can collapse the expression
+    assert_eq!(ident.map_bytes(|b| if b.is_ascii_lowercase() { None } else { Some(b) }),
+        underscore.then(&digit.or(&underscore).star()));
+    assert_eq!(lower.then(&digit).map_bytes(|b| if b.is_ascii_digit() { None } else { Some(b) }), RegEx::none());
+}
+
+#[test]
+fn operators() {
+    let a = RegEx::set(ByteSet::point(b'a'));
+    let b = RegEx::set(ByteSet::point(b'b'));
+
+    assert_eq!(&a + &b, a.then(&b));
+    assert_eq!(&a | &b, a.or(&b));
+    assert_eq!(&a & &b, a.and(&b));
+    assert_eq!(!&a, a.not());
+    assert_eq!(a.clone() + &b, a.then(&b));
+    assert_eq!(&a | b.clone(), a.or(&b));
+    assert_eq!(a.clone() & b.clone(), a.and(&b));
+    assert_eq!(!!a.clone(), a);
+}
+
+#[test]
+fn literal_prefix() {
+    let lit = |s: &[u8]| s.iter().fold(RegEx::empty(), |r, &byte| r.then(&RegEx::set(ByteSet::point(byte))));
+    let digit = RegEx::set(ByteSet::range(b'0', b'9'));
+
+    let keywords = lit(b"interface").or(&lit(b"internal")).or(&lit(b"int"));
+    assert_eq!(keywords.literal_prefix(), b"int");
+    assert_eq!(keywords.prefix_bytes(), ByteSet::point(b'i'));
+
+    let regex = lit(b"0x").then(&digit.plus()).or(&lit(b"0b").then(&digit.plus()));
+    assert_eq!(regex.literal_prefix(), b"0");
+    assert_eq!(lit(b"ab").then(&digit).then(&lit(b"cd")).literal_prefix(), b"ab");
+
+    // nullable heads
+    let regex = lit(b"a").opt().then(&lit(b"bc"));
+    assert_eq!(regex.literal_prefix(), b"");
+    assert_eq!(regex.prefix_bytes(), ByteSet::range(b'a', b'b'));
+    assert_eq!(lit(b"ab").or(&RegEx::empty()).literal_prefix(), b"");
+    assert_eq!(digit.star().then(&lit(b"x")).prefix_bytes(), ByteSet::range(b'0', b'9').union(&ByteSet::point(b'x')));
+
+    assert_eq!(lit(b"ab").and(&lit(b"a").then(&digit.not())).literal_prefix(), b"ab");
+    assert_eq!(RegEx::none().prefix_bytes(), ByteSet::empty());
+    assert_eq!(RegEx::empty().literal_prefix(), b"");
+}
+
+#[test]
+fn is_fullmatch_iter() {
+    let lowercase = RegEx::set(ByteSet::range(b'a', b'z'));
+    let space     = RegEx::set(ByteSet::point(b' '));
+    let words     = lowercase.plus().then(&space).star();
+
+    assert!(words.is_fullmatch_iter(b"lorem ipsum ".iter().copied()));
+    assert!(!words.is_fullmatch_iter(b"lorem ipsum".iter().copied()));
+    assert!(!words.is_fullmatch_iter(std::iter::repeat(b'_')));
+
+    let text = "lorem ipsum ".repeat(100_000);
+    assert!(words.is_fullmatch_read(text.as_bytes()).unwrap());
+    assert!(!words.is_fullmatch_read(&text.as_bytes()[1..text.len() - 1]).unwrap());
+    assert!(!words.is_fullmatch_read(std::io::repeat(b'_')).unwrap());
+}
+
+#[test]
+fn is_fullmatch_bytes() {
+    let frame = RegEx::set(ByteSet::point(0x00))
+        .then(&RegEx::set(ByteSet::range(0x01, 0xfe)).star())
+        .then(&RegEx::set(ByteSet::point(0xff)));
+    let mut cache = DerivCache::new();
+
+    for input in [&[0x00, 0xff][..], &[0x00, 0x80, 0xc0, 0xfe, 0xff]] {
+        assert!(frame.is_fullmatch_bytes(input));
+        assert!(frame.is_fullmatch_cached(input, &mut cache));
+    }
+    for input in [&[0x00, 0x80, 0x00, 0xff][..], &[0xff, 0x00], &[]] {
+        assert!(!frame.is_fullmatch_bytes(input));
+        assert!(!frame.is_fullmatch_cached(input, &mut cache));
+    }
+    assert!(!frame.is_fullmatch("\0abc"));
+}
+
+#[test]
+fn partial_deriv() {
+    let a = RegEx::set(ByteSet::point(b'a'));
+    let b = RegEx::set(ByteSet::point(b'b'));
+    let ab = a.or(&b);
+    let digit = RegEx::set(ByteSet::range(b'0', b'9'));
+
+    let corpus = [
+        RegEx::none(),
+        RegEx::empty(),
+        a.then(&b).or(&a.then(&a)),
+        ab.star().then(&a).then(&ab).then(&ab),
+        a.opt().then(&b.star()).then(&a),
+        ab.star().and(&a.then(&ab.star())).and(&ab.star().then(&b)),
+        a.then(&b).not().then(&a),
+        ab.plus().and(&a.then(&a).not()),
+        digit.star().then(&a.star().then(&b).not()).or(&b.then(&digit)),
+    ];
+
+    for regex in &corpus {
+        for byte in [b'a', b'b', b'0', b'z'] {
+            let union = regex.partial_deriv(byte).iter().fold(RegEx::none(), |acc, pd| acc.or(pd));
+            let deriv = regex.deriv(byte);
+            let symmetric_difference = union.diff(&deriv).or(&deriv.diff(&union));
+            assert_eq!(symmetric_difference.shortest_string(), None, "{regex:?} wrt {byte}");
+        }
+    }
+
+    // partial derivatives of a+b-free expressions are bounded by its size
+    let regex = ab.star().then(&a).then(&ab).then(&ab).then(&ab);
+    assert_eq!(regex.partial_deriv(b'a').len(), 2);
+    assert!(regex.partial_deriv(b'z').is_empty());
+}
+
+#[test]
+fn idempotence() {
+    let a = RegEx::set(ByteSet::point(b'a'));
+    let b = RegEx::set(ByteSet::point(b'b'));
+    let digit = RegEx::set(ByteSet::range(b'0', b'9'));
+    let regex = a.star().then(&b);
+
+    assert_eq!(regex.or(&regex), regex);
+    assert_eq!(regex.and(&regex), regex);
+    assert_eq!(regex.or(&digit).or(&regex), regex.or(&digit));
+
+    // derivatives of the following only stay finite if Or is idempotent
+    let regex = digit.star().then(&regex.not());
+    assert!(DFA::from(&regex).states().len() < 10);
+}
+
+/// Returns `true` iff `a` and `b` accept the same strings with the same classes.
+fn dfa_equivalent(a: &DFA, b: &DFA) -> bool {
+    let mut visited = std::collections::HashSet::new();
+    let mut stack = vec![(DFA::START, DFA::START)];
+    while let Some((p, q)) = stack.pop() {
+        if a.class(p) != b.class(q) {
+            return false;
+        }
+        for symbol in 0..=255 {
+            let next = (a.step(p, symbol), b.step(q, symbol));
+            if visited.insert(next) {
+                stack.push(next);
+            }
+        }
+    }
+    true
+}
+
+#[test]
+fn nfa_determinize() {
+    let uppercase  = RegEx::set(ByteSet::range(b'A', b'Z'));
+    let lowercase  = RegEx::set(ByteSet::range(b'a', b'z'));
+    let digit      = RegEx::set(ByteSet::range(b'0', b'9'));
+    let underscore = RegEx::set(ByteSet::point(b'_'));
+    let zero       = RegEx::set(ByteSet::point(b'0'));
+    let space      = RegEx::set(ByteSet::point(b' ').union(&ByteSet::point(b',')));
+
+    let character  = uppercase.or(&lowercase);
+    let identifier = character.or(&underscore).then(&character.or(&digit).or(&underscore).star());
+    let nonzero_digit = digit.and(&zero.not());
+    let ab = RegEx::set(ByteSet::range(b'a', b'b'));
+    let third_last_a = ab.star().then(&RegEx::set(ByteSet::point(b'a'))).then(&ab).then(&ab);
+
+    for regex in [&identifier, &nonzero_digit, &third_last_a, &identifier.and(&lowercase.plus().not()), &RegEx::empty()] {
+        let nfa = NFA::from(regex);
+        assert!(nfa.states().iter().all(|state| state.transitions().iter().all(|(set, _)| !set.is_empty())));
+        assert!(dfa_equivalent(&DFA::from(regex), &nfa.determinize().minimize()));
+    }
+
+    // the NFA for (a|b)*a(a|b)(a|b) is linear in size, its DFA is not
+    assert_eq!(NFA::from(&third_last_a).states().len(), 4);
+    assert_eq!(DFA::from(&third_last_a).minimize().states().len(), 9);
+
+    let rules = [space.plus(), lowercase.plus(), identifier];
+    let nfa = NFA::from(&rules);
+    assert_eq!(nfa.starts().len(), 3);
+    assert!(dfa_equivalent(&DFA::from(&rules), &nfa.determinize().minimize()));
+
+    let table = NaiveLexTable::new(&nfa.determinize().minimize());
+    let tokens: Vec<_> = Scan::new(&table, "waltz, Bad nymph").collect::<Result<_, _>>().unwrap();
+    let classes: Vec<_> = tokens.iter().map(|token| token.class).collect();
+    assert_eq!(classes, [1, 0, 2, 0, 1]);
+}
+
+#[test]
+fn canonicalize() {
+    let set = ByteSet::range(3, 17);
+    let any = RegEx::set(ByteSet::universe());
+    let a = RegEx::set(ByteSet::point(b'a'));
+    let b = RegEx::set(ByteSet::point(b'b'));
+
+    // different trees, but the same language
+    let re1 = RegEx::set(set.clone()).plus();
+    let re2 = RegEx::set(set.clone()).star().and(&RegEx::empty().not());
+    assert_ne!(re1, re2);
+    assert!(re1.equivalent(&re2));
+    assert_eq!(re1.canonicalize(), re2.canonicalize());
+
+    // whereas the complement of a set (as a regex) recognizes more than the
+    // set complement
+    let re3 = RegEx::set(set.clone()).not();
+    assert!(!RegEx::set(set.complement()).equivalent(&re3));
+    assert_eq!(RegEx::set(set.complement()).canonicalize(), re3.and(&any).canonicalize());
+
+    let regexes = [
+        a.or(&b).star(),
+        a.star().then(&b.star()).star(),
+        a.then(&b).or(&b.then(&a)),
+        a.plus().and(&a.then(&a.plus()).not()),
+        RegEx::empty(),
+        RegEx::none(),
+        a.and(&b),
+    ];
+    for regex in &regexes {
+        let canonical = regex.canonicalize();
+        assert!(canonical.equivalent(regex), "{:?}", regex);
+        assert_eq!(canonical.canonicalize(), canonical);
+    }
+    assert_eq!(regexes[0].canonicalize(), regexes[1].canonicalize());
+    assert_eq!(regexes[3].canonicalize(), a);
+    assert_eq!(regexes[5].canonicalize(), regexes[6].canonicalize());
+    assert_eq!(RegEx::empty().canonicalize(), RegEx::empty());
+}
+
+#[test]
+fn interleave() {
+    let a = RegEx::set(ByteSet::point(b'a'));
+    let b = RegEx::set(ByteSet::point(b'b'));
+    let c = RegEx::set(ByteSet::point(b'c'));
+
+    let re = a.interleave(&b);
+    assert_eq!(re, b.interleave(&a));
+    assert_eq!(re.strings_up_to(4).collect::<Vec<_>>(), [b"ab".to_vec(), b"ba".to_vec()]);
+    assert!(!re.is_nullable());
+
+    assert_eq!(a.interleave(&RegEx::empty()), a);
+    assert_eq!(a.interleave(&RegEx::none()), RegEx::none());
+
+    // every permutation of the three markers
+    let re = a.interleave(&b).interleave(&c);
+    assert_eq!(re.strings_up_to(4).count(), 6);
+    assert!(re.equivalent(&c.interleave(&b.interleave(&a))));
+
+    // the markers may appear anywhere within the payload
+    let re = c.star().interleave(&a).interleave(&b);
+    assert!(re.is_fullmatch("cacbc"));
+    assert!(re.is_fullmatch("bcccca"));
+    assert!(!re.is_fullmatch("cacc"));
+    assert!(!re.is_fullmatch("abab"));
+    assert!(DFA::from(&re).matches("ccbcca"));
+    assert!(NFA::from(&re).determinize().matches("ccbcca"));
+}
+
+#[test]
+fn and_epsilon() {
+    let a = RegEx::set(ByteSet::point(b'a'));
+    let b = RegEx::set(ByteSet::point(b'b'));
+
+    let nullable = [
+        RegEx::empty(),
+        a.star(),
+        a.opt(),
+        a.star().then(&b.star()),
+        a.star().and(&b.star()),
+        a.not(),
+        a.then(&b).not(),
+        a.star().interleave(&b.star()),
+    ];
+    let non_nullable = [
+        RegEx::none(),
+        a.clone(),
+        a.then(&b),
+        a.or(&b.star().then(&a)),
+        a.plus().and(&a.then(&a).star()),
+        a.star().not(),
+        a.interleave(&b.star()),
+    ];
+
+    for regex in &nullable {
+        assert_eq!(RegEx::empty().and(regex), RegEx::empty(), "{regex:?}");
+        assert_eq!(regex.and(&RegEx::empty()), RegEx::empty(), "{regex:?}");
+    }
+    for regex in &non_nullable {
+        assert_eq!(RegEx::empty().and(regex), RegEx::none(), "{regex:?}");
+        assert_eq!(regex.and(&RegEx::empty()), RegEx::none(), "{regex:?}");
+    }
+}
+
+fn random_regex(rng: &mut Pcg32, depth: u32) -> RegEx {
+    let choice = if depth == 0 { rng.next_u32() % 4 } else { rng.next_u32() % 10 };
+    match choice {
+        0 => RegEx::none(),
+        1 => RegEx::empty(),
+        2 => RegEx::set(ByteSet::point(b'a')),
+        3 => RegEx::set(ByteSet::range(b'a', b'b')),
+        4 => random_regex(rng, depth - 1).then(&random_regex(rng, depth - 1)),
+        5 => random_regex(rng, depth - 1).star(),
+        6 => random_regex(rng, depth - 1).or(&random_regex(rng, depth - 1)),
+        7 => random_regex(rng, depth - 1).and(&random_regex(rng, depth - 1)),
+        8 => random_regex(rng, depth - 1).not(),
+        _ => random_regex(rng, depth - 1).interleave(&random_regex(rng, depth - 1)),
+    }
+}
+
+#[test]
+fn and_matches_both() {
+    let mut rng = Pcg32::seed_from_u64(1044);
+    let texts: Vec<String> = (0..=5).flat_map(|len| {
+        (0..1_u32 << len).map(move |bits| (0..len).map(|i| if bits >> i & 1 == 0 { 'a' } else { 'b' }).collect())
+    }).collect();
+
+    for _ in 0..200 {
+        let re1 = random_regex(&mut rng, 3);
+        let re2 = random_regex(&mut rng, 3);
+        let both = re1.and(&re2);
+        for text in &texts {
+            assert_eq!(
+                both.is_fullmatch(text),
+                re1.is_fullmatch(text) && re2.is_fullmatch(text),
+                "{re1:?} & {re2:?} on {text:?}",
+            );
+        }
+    }
+}
+
+fn size(regex: &RegEx) -> usize {
+    1 + match regex.operator() {
+        Operator::None | Operator::Epsilon | Operator::Set(_) => 0,
+        Operator::Star(re) | Operator::Not(re) => size(re),
+        Operator::Cat(res) | Operator::Or(res) | Operator::And(res) | Operator::Interleave(res) => res.iter().map(size).sum(),
+    }
+}
+
+#[test]
+fn simplify() {
+    let [a, b, c] = [b'a', b'b', b'c'].map(|x| RegEx::set(ByteSet::point(x)));
+
+    assert_eq!(a.star().opt().simplify(), a.star());
+    assert_eq!(a.or(&b.star()).star().simplify(), a.or(&b).star());
+    assert_eq!(a.or(&a.and(&b.star())).simplify(), a);
+    assert_eq!(a.star().and(&a.star().or(&b)).simplify(), a.star());
+    assert_eq!(a.then(&b).or(&a.then(&c)).or(&b).simplify(), a.then(&b.or(&c)).or(&b));
+
+    // a machine-generated keyword list
+    let keywords = ["for", "foreach", "format", "fn", "false", "if", "in", "int", "into", "impl"];
+    let regex = keywords.iter().fold(RegEx::none(), |acc, keyword| {
+        acc.or(&keyword.bytes().fold(RegEx::empty(), |acc, x| acc.then(&RegEx::set(ByteSet::point(x)))))
+    });
+    let simplified = regex.simplify();
+    assert!(simplified.equivalent(&regex));
+    assert!(size(&simplified) < size(&regex), "{} -> {}", size(&regex), size(&simplified));
+
+    let mut rng = Pcg32::seed_from_u64(1045);
+    let (mut before, mut after) = (0, 0);
+    for _ in 0..200 {
+        let regex = random_regex(&mut rng, 4);
+        let simplified = regex.simplify();
+        assert!(simplified.equivalent(&regex), "{:?} -> {:?}", regex, simplified);
+        assert!(size(&simplified) <= size(&regex), "{:?} -> {:?}", regex, simplified);
+        before += size(&regex);
+        after += size(&simplified);
+    }
+    assert!(after < before, "{} -> {}", before, after);
+}
+
+#[test]
+fn trailing_context() {
+    let digit = RegEx::set(ByteSet::range(b'0', b'9'));
+    let dot = RegEx::set(ByteSet::point(b'.'));
+
+    // INT := digit+ / ~(.digit), where the lookahead is at most two bytes
+    // long, so that it can't run past the following token
+    let any = RegEx::set(ByteSet::universe());
+    let int = digit.plus().followed_by(&dot.then(&digit).not().and(&any.opt().then(&any.opt())));
+    let float = digit.plus().then(&dot).then(&digit.plus());
+    let dotdot = dot.then(&dot);
+
+    let table = NaiveLexTable::with_trailing_contexts(
+        &DFA::from(&[int.regex(), float, dotdot]).minimize(),
+        vec![Some(int), None, None],
+    );
+
+    let tokens: Vec<_> = Scan::new(&table, "1..5").collect::<Result<_, _>>().unwrap();
+    assert_eq!(tokens, [
+        Token { class: 0, span: 0..1 },
+        Token { class: 2, span: 1..3 },
+        Token { class: 0, span: 3..4 },
+    ]);
+
+    let tokens: Vec<_> = Scan::new(&table, "12.5..7").collect::<Result<_, _>>().unwrap();
+    assert_eq!(tokens, [
+        Token { class: 1, span: 0..4 },
+        Token { class: 2, span: 4..6 },
+        Token { class: 0, span: 6..7 },
+    ]);
+}
+
+#[test]
+fn fullmatch_lines() {
+    let word = RegEx::set(ByteSet::range(b'a', b'z')).plus();
+    let any = RegEx::set(ByteSet::universe()).star();
+    let text = "foo\r\nbar baz\nqux\r\n\nquux";
+
+    let results: Vec<_> = word.fullmatch_lines(text).collect();
+    assert_eq!(results, [(0, true), (1, false), (2, true), (3, false), (4, true)]);
+
+    // every line matches any line, but the text as a whole doesn't
+    let line = RegEx::line_of(&any);
+    assert!(line.fullmatch_lines(text).all(|(_, matched)| matched));
+    assert!(!line.is_fullmatch(text));
+    assert!(!line.is_fullmatch("foo\r"));
+    assert!(line.equivalent(&RegEx::line_of(&line)));
+
+    // a final newline does not yield an extra empty line
+    assert_eq!(word.fullmatch_lines("foo\n").count(), 1);
+    assert_eq!(word.fullmatch_lines("").count(), 0);
+}
+
+#[test]
+fn as_literal() {
+    let [a, b, c] = [b'a', b'b', b'c'].map(|x| RegEx::set(ByteSet::point(x)));
+    let abc = a.then(&b).then(&c);
+
+    assert_eq!(abc.as_literal(), Some(b"abc".to_vec()));
+    assert_eq!(a.or(&b).as_literal(), None);
+    assert_eq!(a.or(&a).as_literal(), Some(b"a".to_vec()));
+    assert_eq!(RegEx::none().as_literal(), None);
+    assert_eq!(a.not().as_literal(), None);
+    assert_eq!(a.interleave(&b).as_literal(), None);
+
+    // sets that merge into a singleton
+    let ab = RegEx::set(ByteSet::range(b'a', b'b'));
+    let bc = RegEx::set(ByteSet::range(b'b', b'c'));
+    assert_eq!(ab.and(&bc).as_literal(), Some(b"b".to_vec()));
+    assert_eq!(ab.or(&a).as_literal(), None);
+
+    assert_eq!(abc.and(&a.then(&RegEx::set(ByteSet::universe()).star())).as_literal(), Some(b"abc".to_vec()));
+    assert!(!abc.and(&b.star()).is_literal());
+    assert!(abc.is_literal());
+}
+
+#[test]
+fn debug() {
+    let [a, b, c] = [b'a', b'b', b'c'].map(|x| RegEx::set(ByteSet::point(x)));
+    let lower = RegEx::set(ByteSet::range(b'a', b'z'));
+    let star = RegEx::set(ByteSet::point(b'*'));
+
+    let cases = [
+        (a.then(&b).or(&c), "c|ab"),
+        (a.or(&b).then(&c), "[ab]c"),
+        (a.then(&b.or(&c.star())).star(), "(a(b|c*))*"),
+        (a.then(&b).opt(), "(ab)?"),
+        (a.opt().then(&b.star().or(&c.plus())), "a?(cc*|b*)"),
+        (lower.plus().and(&a.then(&b).not()), "[a-z][a-z]*&~(ab)"),
+        (a.then(&b).interleave(&star.star()), "ab\u{29E2}\\**"),
+        (a.or(&b.then(&c)).and(&lower.star()), "[a-z]*&(a|bc)"),
+        (RegEx::empty().or(&RegEx::none().then(&a)), "\u{03B5}"),
+        (RegEx::none(), "\u{2205}"),
+    ];
+    for (regex, expected) in &cases {
+        assert_eq!(&format!("{regex:?}"), expected);
+        assert_eq!(&format!("{regex}"), expected);
+    }
+
+    let regex = a.then(&b.star()).or(&c.not());
+    assert_eq!(format!("{regex:#?}"), "(or\n  (cat\n    a\n    (star\n      b))\n  (not\n    c))");
+}
+
+#[test]
+fn eq_hash_ord() {
+    fn hash(regex: &RegEx) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        regex.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    let mut rng = Pcg32::seed_from_u64(1051);
+    for _ in 0..200 {
+        // built twice from the same seed, so equal but not shared
+        let seed = u64::from(rng.next_u32());
+        let re1 = random_regex(&mut Pcg32::seed_from_u64(seed), 4);
+        let re2 = random_regex(&mut Pcg32::seed_from_u64(seed), 4);
+        let re3 = random_regex(&mut rng, 4);
+
+        assert_eq!(re1, re2);
+        assert_eq!(hash(&re1), hash(&re2));
+        assert_eq!(re1.cmp(&re2), Ordering::Equal);
+        assert_eq!(re1 == re3, re1.cmp(&re3) == Ordering::Equal);
+        assert_eq!(re1.cmp(&re3), re3.cmp(&re1).reverse());
+        if re1 == re3 {
+            assert_eq!(hash(&re1), hash(&re3));
+        }
+    }
+}
+
+#[test]
+fn overlapping_pairs() {
+    let lower = RegEx::set(ByteSet::range(b'a', b'z'));
+    let digit = RegEx::set(ByteSet::range(b'0', b'9'));
+    let keyword = [b'i', b'f'].iter().fold(RegEx::empty(), |acc, &x| acc.then(&RegEx::set(ByteSet::point(x))));
+    let identifier = lower.then(&lower.or(&digit).star());
+
+    let rules = [keyword.clone(), identifier.clone(), digit.plus(), digit.plus().then(&lower)];
+    assert_eq!(RegEx::overlapping_pairs(&rules), [(0, 1)]);
+
+    let rules = [keyword.clone(), identifier.and(&keyword.not()), digit.plus()];
+    assert!(RegEx::overlapping_pairs(&rules).is_empty());
+    assert!(RegEx::overlapping_pairs(&[]).is_empty());
+
+    // agrees with checking each pair separately
+    let mut rng = Pcg32::seed_from_u64(1052);
+    let rules: Vec<_> = (0..6).map(|_| random_regex(&mut rng, 3)).collect();
+    let mut expected = Vec::new();
+    for i in 0..rules.len() {
+        for j in i + 1..rules.len() {
+            let overlaps = rules[i].and(&rules[j]).shortest_string().is_some();
+            assert_eq!(rules[i].overlaps(&rules[j]), overlaps);
+            if overlaps {
+                expected.push((i, j));
+            }
+        }
+    }
+    assert_eq!(RegEx::overlapping_pairs(&rules), expected);
+}
+
+#[test]
+fn utf8_any() {
+    // returns true iff bytes is the UTF-8 encoding of exactly one char
+    let is_char = |bytes: &[u8]| std::str::from_utf8(bytes).is_ok_and(|s| s.chars().count() == 1);
+
+    let dfa = DFA::from(&super::utf8_any()).minimize();
+    let matches = |bytes: &[u8]| dfa.matches_iter(bytes.iter().copied());
+
+    assert!(!matches(&[]));
+    for a in 0..=255 {
+        assert_eq!(matches(&[a]), is_char(&[a]), "{:?}", [a]);
+        for b in 0..=255 {
+            assert_eq!(matches(&[a, b]), is_char(&[a, b]), "{:?}", [a, b]);
+            if a >= 0xe0 {
+                for c in 0..=255 {
+                    assert_eq!(matches(&[a, b, c]), is_char(&[a, b, c]), "{:?}", [a, b, c]);
+                }
+            }
+            if a >= 0xf0 {
+                for &c in &[0x00, 0x7f, 0x80, 0xbf, 0xc0] {
+                    for &d in &[0x00, 0x7f, 0x80, 0xbf, 0xc0] {
+                        assert_eq!(matches(&[a, b, c, d]), is_char(&[a, b, c, d]), "{:?}", [a, b, c, d]);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,60 @@
+#![deny(warnings)]
+
+use regex_deriv::{RegEx, ByteSet, DFA, LexTable, NaiveLexTable, ClassLexTable, Scan, tables_equivalent};
+
+mod naive {
+    use regex_deriv::StaticLexTable;
+    include!("data/ident_naive.rs");
+}
+
+mod class {
+    use regex_deriv::StaticLexTable;
+    include!("data/ident_class.rs");
+}
+
+/// Identifiers and whitespace.
+fn ident_dfa() -> DFA {
+    let alpha = RegEx::set(ByteSet::ASCII_ALPHA.union(&ByteSet::point(b'_')));
+    DFA::from(&[
+        alpha.then(&RegEx::set(ByteSet::WORD).star()),
+        RegEx::set(ByteSet::ASCII_WHITESPACE).plus(),
+    ]).minimize()
+}
+
+const NAMES: [&str; 2] = ["IDENT", "SPACE"];
+
+fn source(write: impl Fn(&mut Vec<u8>) -> std::io::Result<()>) -> String {
+    let mut out = Vec::new();
+    write(&mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn generated_up_to_date() {
+    let dfa = ident_dfa();
+    let naive = source(|out| NaiveLexTable::new(&dfa).with_class_names(&NAMES).write_rust(out, "IDENT"));
+    let class = source(|out| ClassLexTable::new(&dfa).with_class_names(&NAMES).write_rust(out, "IDENT"));
+    // the output is deterministic, so these only change with the format
+    assert_eq!(naive, include_str!("data/ident_naive.rs"), "tests/data/ident_naive.rs is out of date");
+    assert_eq!(class, include_str!("data/ident_class.rs"), "tests/data/ident_class.rs is out of date");
+    assert_eq!(naive, source(|out| NaiveLexTable::new(&ident_dfa()).with_class_names(&NAMES).write_rust(out, "IDENT")));
+}
+
+#[test]
+fn scan_static() {
+    let table = NaiveLexTable::new(&ident_dfa());
+    for text in &["foo bar_1  baz", "x", "", "  _9 ", "9lives"] {
+        let expected: Vec<_> = Scan::new(&table, text).map(|token| token.map_err(|_| ())).collect();
+        let a: Vec<_> = Scan::new(&naive::IDENT, text).map(|token| token.map_err(|_| ())).collect();
+        let b: Vec<_> = Scan::new(&class::IDENT, text).map(|token| token.map_err(|_| ())).collect();
+        assert_eq!(a, expected);
+        assert_eq!(b, expected);
+    }
+    for generated in [&naive::IDENT, &class::IDENT] {
+        assert_eq!(tables_equivalent(&table, generated), Ok(()));
+        assert_eq!(generated.class_count(), 2);
+        assert_eq!(generated.class_name(1), Some("SPACE"));
+        assert_eq!(generated.validate(), Ok(()));
+        assert_eq!(generated.validate_accepting(), Ok(()));
+    }
+}